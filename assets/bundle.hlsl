@@ -0,0 +1,6 @@
+RWByteAddressBuffer counter : register(u0);
+
+[numthreads(64, 1, 1)]
+void CSMain() {
+    counter.InterlockedAdd(0, 1);
+}
@@ -0,0 +1,44 @@
+// Frustum culling of bounding spheres, compacting the indices of visible instances and counting them into the
+// InstanceCount of a D3D12_DRAW_INDEXED_ARGUMENTS, for ExecuteIndirect
+
+cbuffer Constants : register(b0) {
+    float4 frustumPlanes[6];
+    uint instanceCount;
+    uint indexCountPerInstance;
+    uint startIndexLocation;
+    int baseVertexLocation;
+};
+
+struct CullingInstance {
+    float3 center;
+    float radius;
+};
+
+StructuredBuffer<CullingInstance> instances : register(t0);
+RWStructuredBuffer<uint> visibleInstances : register(u0);
+RWByteAddressBuffer drawArguments : register(u1);
+
+[numthreads(1, 1, 1)]
+void ResetMain() {
+    drawArguments.Store4(0, uint4(indexCountPerInstance, 0, startIndexLocation, asuint(baseVertexLocation)));
+    drawArguments.Store(16, 0);
+}
+
+[numthreads(64, 1, 1)]
+void CullMain(uint3 dispatchThreadId : SV_DispatchThreadID) {
+    uint instanceIndex = dispatchThreadId.x;
+    if (instanceIndex >= instanceCount) {
+        return;
+    }
+
+    CullingInstance bounds = instances[instanceIndex];
+    for (uint i = 0; i < 6; i++) {
+        if (dot(frustumPlanes[i].xyz, bounds.center) + frustumPlanes[i].w < -bounds.radius) {
+            return;
+        }
+    }
+
+    uint visibleIndex;
+    drawArguments.InterlockedAdd(4, 1, visibleIndex);
+    visibleInstances[visibleIndex] = instanceIndex;
+}
@@ -0,0 +1,34 @@
+cbuffer Constants : register(b0) {
+    // Top left corner of the overlay in pixels
+    uint2 origin;
+    // Size of each font pixel in screen pixels
+    uint scale;
+    uint padding;
+    // 4 rows of 16 glyph indices, packed 4 per uint
+    uint4 text[4];
+};
+
+// 3x5 pixel font, 1 bit per pixel, starting from the top left in the most significant bit.
+// Order must match GLYPHS in diagnostics_overlay.rs
+static const uint glyphs[24] = {
+    0x7B6F, 0x2C97, 0x73E7, 0x73CF, 0x5BC9, 0x79CF, 0x79EF, 0x7249, 0x7BEF, 0x7BCF, 0x0002, 0x0000,
+    0x2BED, 0x7927, 0x79E7, 0x79E4, 0x796F, 0x5FED, 0x7B6D, 0x7BE4, 0x6BAD, 0x79CF, 0x7492, 0x5B6F,
+};
+
+float4 VSMain(uint vertexId : SV_VertexID) : SV_Position {
+    float2 uv = float2((vertexId << 1) & 2, vertexId & 2);
+    return float4(uv * float2(2, -2) + float2(-1, 1), 0, 1);
+}
+
+float4 PSMain(float4 position : SV_Position) : SV_Target {
+    // Each glyph occupies a 4x6 cell, leaving a 1 pixel gap to the right and below
+    uint2 fontPixel = (uint2(position.xy) - origin) / scale;
+    uint2 cell = fontPixel / uint2(4, 6);
+    uint2 texel = fontPixel % uint2(4, 6);
+
+    uint glyph = (text[cell.y][cell.x / 4] >> ((cell.x % 4) * 8)) & 0xFF;
+    bool lit = texel.x < 3 && texel.y < 5 && ((glyphs[glyph] >> (14 - (texel.y * 3 + texel.x))) & 1);
+
+    // Premultiplied alpha
+    return lit ? float4(1, 1, 1, 1) : float4(0, 0, 0, 0.6);
+}
@@ -0,0 +1,22 @@
+// Vertex shader for Gpu::draw_fullscreen_triangle(), drawing a single triangle that covers the whole viewport without
+// any vertex buffers. Include this in a pixel shader's source to use FullscreenVertexOutput as its input.
+//
+// Vertex IDs 0, 1, and 2 map to UVs (0, 0), (2, 0), and (0, 2), i.e. ((id << 1) & 2, id & 2). Positions are the UVs
+// scaled to clip space with Y flipped, (-1, 1), (3, 1), and (-1, -3), so the triangle's right angle is at the top left
+// corner of the viewport, and its hypotenuse passes through the bottom right corner. The parts outside the viewport
+// are clipped, leaving UVs from (0, 0) at the top left to (1, 1) at the bottom right, matching texture coordinates.
+//
+// fullscreen_triangle_vs.dxil holds this vertex shader precompiled with DXC. After making changes, recompile it, or
+// delete it for build.rs to compile it instead.
+
+struct FullscreenVertexOutput {
+    float4 clipPosition : SV_Position;
+    float2 uv : TEXCOORD0;
+};
+
+FullscreenVertexOutput FullscreenVSMain(uint vertexId : SV_VertexID) {
+    FullscreenVertexOutput output;
+    output.uv = float2((vertexId << 1) & 2, vertexId & 2);
+    output.clipPosition = float4(output.uv * float2(2, -2) + float2(-1, 1), 0, 1);
+    return output;
+}
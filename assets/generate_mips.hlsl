@@ -0,0 +1,66 @@
+Texture2D<float4> source : register(t0);
+RWTexture2D<float4> destination : register(u0);
+SamplerState linearClampSampler : register(s0);
+
+cbuffer Constants : register(b0) {
+    // 1 / destination size
+    float2 texelSize;
+    // Bit 0: source width is odd, bit 1: source height is odd
+    uint oddDimensions;
+    uint isSrgb;
+};
+
+// Built with DXC and HLSL 2021, which replaced the per-component ternary with select(), or with FXC at runtime
+#if defined(__HLSL_VERSION) && __HLSL_VERSION >= 2021
+#define SELECT(condition, a, b) select(condition, a, b)
+#else
+#define SELECT(condition, a, b) ((condition) ? (a) : (b))
+#endif
+
+float3 linearToSrgb(float3 color) {
+    return SELECT(color < 0.0031308, 12.92 * color, 1.055 * pow(abs(color), 1.0 / 2.4) - 0.055);
+}
+
+float4 sampleSource(float2 uv) {
+    return source.SampleLevel(linearClampSampler, uv, 0.0);
+}
+
+[numthreads(8, 8, 1)]
+void CSMain(uint3 id : SV_DispatchThreadID) {
+    uint2 size;
+    destination.GetDimensions(size.x, size.y);
+    if (any(id.xy >= size)) {
+        return;
+    }
+
+    // A single bilinear sample averages the 2x2 source texels under each destination texel. Odd source
+    // dimensions cover slightly more than 2 texels, so take two samples along that axis to include the extra texel.
+    float2 uv = texelSize * (id.xy + 0.5);
+    float4 color;
+    switch (oddDimensions) {
+    case 0:
+        color = sampleSource(uv);
+        break;
+    case 1:
+        color = 0.5 * (sampleSource(uv + texelSize * float2(-0.25, 0.0))
+                     + sampleSource(uv + texelSize * float2(0.25, 0.0)));
+        break;
+    case 2:
+        color = 0.5 * (sampleSource(uv + texelSize * float2(0.0, -0.25))
+                     + sampleSource(uv + texelSize * float2(0.0, 0.25)));
+        break;
+    default:
+        color = 0.25 * (sampleSource(uv + texelSize * float2(-0.25, -0.25))
+                      + sampleSource(uv + texelSize * float2(0.25, -0.25))
+                      + sampleSource(uv + texelSize * float2(-0.25, 0.25))
+                      + sampleSource(uv + texelSize * float2(0.25, 0.25)));
+        break;
+    }
+
+    // UAVs can't be sRGB, so encode manually
+    if (isSrgb != 0) {
+        color.rgb = linearToSrgb(color.rgb);
+    }
+
+    destination[id.xy] = color;
+}
@@ -0,0 +1,21 @@
+struct VertexInput {
+    float2 position : POSITION;
+    float2 instanceOffset : INSTANCE_OFFSET;
+    float4 instanceColor : INSTANCE_COLOR;
+};
+
+struct VertexOutput {
+    float4 clipPosition : SV_Position;
+    float4 color : COLOR0;
+};
+
+VertexOutput VSMain(VertexInput input) {
+    VertexOutput output;
+    output.clipPosition = float4(input.position + input.instanceOffset, 0.0, 1.0);
+    output.color = input.instanceColor;
+    return output;
+}
+
+float4 PSMain(VertexOutput input) : SV_Target {
+    return input.color;
+}
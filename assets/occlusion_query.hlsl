@@ -0,0 +1,18 @@
+cbuffer Constants : register(b0) {
+    // Clip space offset of the box's center
+    float2 offset;
+};
+
+// Two triangles covering a box half the size of the viewport
+static const float2 corners[6] = {
+    float2(-0.5, -0.5), float2(-0.5, 0.5), float2(0.5, 0.5),
+    float2(-0.5, -0.5), float2(0.5, 0.5), float2(0.5, -0.5),
+};
+
+float4 VSMain(uint vertexId : SV_VertexID) : SV_Position {
+    return float4(corners[vertexId] + offset, 0.0, 1.0);
+}
+
+float4 PSMain() : SV_Target {
+    return float4(1.0, 1.0, 1.0, 1.0);
+}
@@ -0,0 +1,12 @@
+// Minimal pixel shader recording which mips of a texture are sampled, for use with SamplerFeedbackMap.
+// Requires shader model 6.5, compile with DXC: dxc -T ps_6_5 -E PSMain sampler_feedback.hlsl -Fo sampler_feedback_ps.dxil
+
+Texture2D<float4> color_texture : register(t0);
+FeedbackTexture2D<SAMPLER_FEEDBACK_MIN_MIP> color_texture_feedback : register(u0);
+SamplerState linear_sampler : register(s0);
+
+float4 PSMain(float4 position : SV_POSITION, float2 uv : TEXCOORD) : SV_TARGET {
+    // Record the mip(s) this sample would read, then sample as usual
+    color_texture_feedback.WriteSamplerFeedback(color_texture, linear_sampler, uv);
+    return color_texture.Sample(linear_sampler, uv);
+}
@@ -0,0 +1,42 @@
+cbuffer Constants : register(b0) {
+    float4x4 cameraViewProjection;
+    float4x4 lightViewProjection;
+};
+
+Texture2D<float> shadowMap : register(t0);
+SamplerComparisonState shadowSampler : register(s0);
+
+// A ground plane, and a triangle floating above it
+static const float3 positions[9] = {
+    float3(-2, 0, -2), float3(2, 0, -2), float3(2, 0, 2),
+    float3(-2, 0, -2), float3(2, 0, 2), float3(-2, 0, 2),
+    float3(-0.8, 0.6, -0.5), float3(0.8, 0.6, -0.5), float3(0, 0.9, 0.8),
+};
+
+// Depth-only pass from the light's point of view
+float4 ShadowVSMain(uint vertexId : SV_VertexID) : SV_Position {
+    return mul(lightViewProjection, float4(positions[vertexId], 1));
+}
+
+struct VertexOutput {
+    float4 clipPosition : SV_Position;
+    float3 worldPosition : POSITION;
+};
+
+VertexOutput VSMain(uint vertexId : SV_VertexID) {
+    VertexOutput output;
+    output.worldPosition = positions[vertexId];
+    output.clipPosition = mul(cameraViewProjection, float4(output.worldPosition, 1));
+    return output;
+}
+
+float4 PSMain(VertexOutput input) : SV_Target {
+    // Project into the shadow map, and compare against the depth the light saw
+    float4 lightClipPosition = mul(lightViewProjection, float4(input.worldPosition, 1));
+    float3 lightNdc = lightClipPosition.xyz / lightClipPosition.w;
+    float2 shadowUv = lightNdc.xy * float2(0.5, -0.5) + 0.5;
+    float lit = shadowMap.SampleCmpLevelZero(shadowSampler, shadowUv, lightNdc.z - 0.002);
+
+    float3 albedo = input.worldPosition.y > 0.01 ? float3(0.9, 0.3, 0.2) : float3(0.8, 0.8, 0.8);
+    return float4(albedo * (0.2 + 0.8 * lit), 1);
+}
@@ -0,0 +1,21 @@
+struct Particle {
+    float3 position;
+    uint id;
+};
+
+StructuredBuffer<Particle> input : register(t0);
+RWStructuredBuffer<Particle> output : register(u0);
+
+[numthreads(64, 1, 1)]
+void CSMain(uint3 dispatchThreadId : SV_DispatchThreadID) {
+    uint count, stride;
+    input.GetDimensions(count, stride);
+    if (dispatchThreadId.x >= count) {
+        return;
+    }
+
+    Particle particle = input[dispatchThreadId.x];
+    particle.position *= 2.0;
+    particle.id += 1000;
+    output[dispatchThreadId.x] = particle;
+}
@@ -0,0 +1,82 @@
+#include "fullscreen_triangle.hlsl"
+
+Texture2D<float4> hdrColor : register(t0);
+SamplerState pointSampler : register(s0);
+
+#define TONEMAP_REINHARD 0
+#define TONEMAP_ACES 1
+#define TONEMAP_AGX 2
+
+cbuffer Constants : register(b0) {
+    float exposure;
+    uint tonemapOperator;
+    uint encodeSrgb;
+};
+
+// Reinhard tonemapping, applied to luminance to preserve hue
+float3 tonemapReinhard(float3 color) {
+    float luminance = dot(color, float3(0.2126, 0.7152, 0.0722));
+    return color / (1.0 + luminance);
+}
+
+// Krzysztof Narkowicz's fit of the ACES filmic curve
+float3 tonemapAces(float3 color) {
+    color *= 0.6;
+    return saturate((color * (2.51 * color + 0.03)) / (color * (2.43 * color + 0.59) + 0.14));
+}
+
+// Benjamin Wrensch's approximation of Troy Sobotka's AgX, with the default look
+float3 agxContrastApprox(float3 x) {
+    float3 x2 = x * x;
+    float3 x4 = x2 * x2;
+    return 15.5 * x4 * x2 - 40.14 * x4 * x + 31.96 * x4 - 6.868 * x2 * x + 0.4298 * x2 + 0.1191 * x - 0.00232;
+}
+
+float3 tonemapAgx(float3 color) {
+    const float3x3 agxInset = float3x3(
+        0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+        0.0784335999999992, 0.878468636469772, 0.0784336,
+        0.0792237451477643, 0.0791661274605434, 0.879142973793104);
+    const float3x3 agxOutset = float3x3(
+        1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+        -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+        -0.0990297440797205, -0.0989611768448433, 1.15107367264116);
+    const float minEv = -12.47393;
+    const float maxEv = 4.026069;
+
+    color = mul(color, agxInset);
+    color = clamp(log2(max(color, 1e-10)), minEv, maxEv);
+    color = agxContrastApprox((color - minEv) / (maxEv - minEv));
+    color = mul(color, agxOutset);
+
+    // AgX outputs display-encoded values, so decode them back to linear
+    return pow(max(color, 0.0), 2.2);
+}
+
+// Built with DXC and HLSL 2021, which replaced the per-component ternary with select(), or with FXC at runtime
+#if defined(__HLSL_VERSION) && __HLSL_VERSION >= 2021
+#define SELECT(condition, a, b) select(condition, a, b)
+#else
+#define SELECT(condition, a, b) ((condition) ? (a) : (b))
+#endif
+
+float3 linearToSrgb(float3 color) {
+    return SELECT(color <= 0.0031308, color * 12.92, 1.055 * pow(color, 1.0 / 2.4) - 0.055);
+}
+
+float4 PSMain(FullscreenVertexOutput vertexOutput) : SV_Target {
+    float3 color = hdrColor.SampleLevel(pointSampler, vertexOutput.uv, 0.0).rgb * exposure;
+
+    if (tonemapOperator == TONEMAP_ACES) {
+        color = tonemapAces(color);
+    } else if (tonemapOperator == TONEMAP_AGX) {
+        color = tonemapAgx(color);
+    } else {
+        color = tonemapReinhard(color);
+    }
+
+    if (encodeSrgb != 0) {
+        color = linearToSrgb(saturate(color));
+    }
+    return float4(color, 1.0);
+}
@@ -0,0 +1,44 @@
+// Helpers and a starting point for the passes of a VisibilityBuffer.
+
+// Pack an instance and triangle ID into a visibility buffer value. Supports up to 4095 instances of up to 2^20
+// triangles each. 0 is reserved for pixels without geometry.
+uint packVisibility(uint instanceId, uint triangleId) {
+    return ((instanceId << 20) | (triangleId & 0xFFFFF)) + 1;
+}
+
+// Inverse of packVisibility(). Returns false for pixels without geometry.
+bool unpackVisibility(uint visibility, out uint instanceId, out uint triangleId) {
+    visibility -= 1;
+    instanceId = visibility >> 20;
+    triangleId = visibility & 0xFFFFF;
+    return visibility != 0xFFFFFFFF;
+}
+
+// Raster pass pixel shader, writing the IDs of the triangle covering each pixel
+cbuffer RasterConstants : register(b0) {
+    uint instanceId;
+};
+
+uint RasterPSMain(float4 position : SV_Position, uint triangleId : SV_PrimitiveID) : SV_Target {
+    return packVisibility(instanceId, triangleId);
+}
+
+// Material pass compute shader, bound to the VisibilityBuffer's descriptor table.
+// Replace the debug coloring with fetching the triangle's vertices, interpolating attributes, and shading.
+Texture2D<uint> visibility : register(t0);
+Texture2D<float> depth : register(t1);
+RWTexture2D<float4> output : register(u0);
+
+[numthreads(8, 8, 1)]
+void MaterialCSMain(uint3 dispatchThreadId : SV_DispatchThreadID) {
+    uint instanceIndex, triangleIndex;
+    if (!unpackVisibility(visibility[dispatchThreadId.xy], instanceIndex, triangleIndex)) {
+        output[dispatchThreadId.xy] = float4(0, 0, 0, 1);
+        return;
+    }
+
+    // Debug coloring by triangle
+    uint hash = (triangleIndex * 0x9E3779B1) ^ (instanceIndex * 0x85EBCA77);
+    float3 color = float3(hash & 0xFF, (hash >> 8) & 0xFF, (hash >> 16) & 0xFF) / 255.0;
+    output[dispatchThreadId.xy] = float4(color, 1);
+}
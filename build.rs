@@ -0,0 +1,69 @@
+//! Compiles the crate's built-in shaders from `assets/` to DXIL, for `include_bytes!` from `OUT_DIR`.
+//!
+//! A precompiled `assets/<output>` is used as is when present, unless the `DXC` environment variable is set to the path
+//! of DXC, in which case every shader is recompiled with it. Missing outputs are compiled with DXC from the `PATH`,
+//! e.g. from the Windows SDK or Vulkan SDK, if there is one.
+//!
+//! Without DXC, missing outputs are left empty, and the crate compiles those shaders from their HLSL source with FXC
+//! at runtime instead, so DXC is never required to build the crate.
+
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// HLSL source in `assets/`, entry point, target profile, and output file name.
+const SHADERS: &[(&str, &str, &str, &str)] = &[
+    (
+        "fullscreen_triangle.hlsl",
+        "FullscreenVSMain",
+        "vs_6_0",
+        "fullscreen_triangle_vs.dxil",
+    ),
+    (
+        "generate_mips.hlsl",
+        "CSMain",
+        "cs_6_0",
+        "generate_mips_cs.dxil",
+    ),
+    ("tonemap.hlsl", "PSMain", "ps_6_0", "tonemap_ps.dxil"),
+];
+
+fn main() {
+    println!("cargo:rerun-if-changed=assets");
+    println!("cargo:rerun-if-env-changed=DXC");
+
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    let explicit_dxc = env::var_os("DXC").map(PathBuf::from);
+    let dxc = explicit_dxc.clone().unwrap_or_else(|| PathBuf::from("dxc"));
+
+    for &(source, entry_point, profile, output) in SHADERS {
+        let source = Path::new("assets").join(source);
+        let precompiled = Path::new("assets").join(output);
+        let output = out_dir.join(output);
+        if precompiled.exists() && explicit_dxc.is_none() {
+            fs::copy(&precompiled, &output).unwrap();
+            continue;
+        }
+
+        let status = Command::new(&dxc)
+            .args(["-T", profile, "-E", entry_point, "-HV", "2021", "-Fo"])
+            .arg(&output)
+            .arg(&source)
+            .status();
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => panic!(
+                "BevyDirectX: DXC failed to compile {} ({status})",
+                source.display()
+            ),
+            Err(e) if explicit_dxc.is_some() => panic!(
+                "BevyDirectX: Couldn't run DXC from the DXC environment variable to compile {} ({e})",
+                source.display()
+            ),
+            // An empty output makes the crate compile the shader with FXC at runtime instead
+            Err(_) => fs::write(&output, []).unwrap(),
+        }
+    }
+}
@@ -1,22 +1,27 @@
 use bevy::{
     app::{App, Startup},
-    prelude::{Commands, IntoSystemConfigs, Query, Res, ResMut, Resource},
+    prelude::{Commands, IntoSystemConfigs, Query, Res, Resource},
     DefaultPlugins,
 };
 use bevy_directx::{
-    update_render_target,
     windows::Win32::Graphics::{
         Direct3D::*, Direct3D12::*, Dxgi::Common::DXGI_FORMAT_R8G8B8A8_UNORM,
     },
-    BevyDirectXPlugin, Gpu, Render, WindowRenderTarget,
+    BevyDirectXPlugin, CurrentBackbuffer, Gpu, Render, RenderSet, WindowRenderTarget,
 };
-use std::mem::{transmute_copy, ManuallyDrop};
+use std::mem::transmute_copy;
 
 fn main() {
     App::new()
-        .add_plugins((DefaultPlugins, BevyDirectXPlugin))
+        .add_plugins((
+            DefaultPlugins,
+            BevyDirectXPlugin {
+                manage_backbuffer: true,
+                ..Default::default()
+            },
+        ))
         .add_systems(Startup, setup_pipeline)
-        .add_systems(Render, render_frame.after(update_render_target))
+        .add_systems(Render, render_frame.in_set(RenderSet::Draw))
         .run();
 }
 
@@ -34,7 +39,10 @@ fn setup_pipeline(gpu: Res<Gpu>, mut commands: Commands) {
         .create_root_signature(&[], &[], D3D12_ROOT_SIGNATURE_FLAG_NONE)
         .unwrap();
     let pipeline_desc = pipeline_desc(&root_signature, shader_vs, shader_ps);
-    let pipeline = unsafe { gpu.device.CreateGraphicsPipelineState(&pipeline_desc) }.unwrap();
+    let pipeline = gpu
+        .pipeline_cache()
+        .create_graphics_pipeline(&gpu.device, &pipeline_desc)
+        .unwrap();
 
     commands.insert_resource(Pipeline {
         root_signature,
@@ -43,54 +51,27 @@ fn setup_pipeline(gpu: Res<Gpu>, mut commands: Commands) {
 }
 
 fn render_frame(
-    mut gpu: ResMut<Gpu>,
+    gpu: Res<Gpu>,
     pipeline: Res<Pipeline>,
     render_target: Query<&WindowRenderTarget>,
+    backbuffer: Option<Res<CurrentBackbuffer>>,
 ) {
-    let Ok(render_target) = render_target.get_single() else {
+    let (Ok(render_target), Some(backbuffer)) = (render_target.get_single(), backbuffer) else {
         return;
     };
-    let (render_target_texture, render_target_rtv) = render_target.rtv();
 
-    let command_list = gpu.reset_commands(Some(&pipeline.pipeline)).unwrap();
+    let command_list = gpu.command_list();
     unsafe {
         // TODO: Enhanced barriers
+        command_list.SetPipelineState(&pipeline.pipeline);
         command_list.SetGraphicsRootSignature(&pipeline.root_signature);
         command_list.RSSetViewports(&[render_target.viewport()]);
         command_list.RSSetScissorRects(&[render_target.scissor_rect()]);
-        command_list.ResourceBarrier(&[D3D12_RESOURCE_BARRIER {
-            Type: D3D12_RESOURCE_BARRIER_TYPE_TRANSITION,
-            Flags: D3D12_RESOURCE_BARRIER_FLAG_NONE,
-            Anonymous: D3D12_RESOURCE_BARRIER_0 {
-                Transition: ManuallyDrop::new(D3D12_RESOURCE_TRANSITION_BARRIER {
-                    pResource: transmute_copy(render_target_texture),
-                    Subresource: D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES,
-                    StateBefore: D3D12_RESOURCE_STATE_PRESENT,
-                    StateAfter: D3D12_RESOURCE_STATE_RENDER_TARGET,
-                }),
-            },
-        }]);
-        command_list.OMSetRenderTargets(1, Some(&render_target_rtv), false, None);
-        command_list.ClearRenderTargetView(render_target_rtv, &[0.0, 0.0, 0.0, 1.0], None);
+        command_list.OMSetRenderTargets(1, Some(&backbuffer.rtv), false, None);
+        command_list.ClearRenderTargetView(backbuffer.rtv, &[0.0, 0.0, 0.0, 1.0], None);
         command_list.IASetPrimitiveTopology(D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
         command_list.DrawInstanced(3, 1, 0, 0);
-        command_list.ResourceBarrier(&[D3D12_RESOURCE_BARRIER {
-            Type: D3D12_RESOURCE_BARRIER_TYPE_TRANSITION,
-            Flags: D3D12_RESOURCE_BARRIER_FLAG_NONE,
-            Anonymous: D3D12_RESOURCE_BARRIER_0 {
-                Transition: ManuallyDrop::new(D3D12_RESOURCE_TRANSITION_BARRIER {
-                    pResource: transmute_copy(render_target_texture),
-                    Subresource: D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES,
-                    StateBefore: D3D12_RESOURCE_STATE_RENDER_TARGET,
-                    StateAfter: D3D12_RESOURCE_STATE_PRESENT,
-                }),
-            },
-        }]);
     }
-
-    gpu.execute_command_list().unwrap();
-    render_target.present();
-    gpu.signal_fence().unwrap();
 }
 
 fn pipeline_desc(
@@ -0,0 +1,165 @@
+//! Generates the mips of a gradient texture with `Gpu::generate_mips`, reads them back, and checks them against a
+//! 2x2 box filter computed on the CPU.
+
+use bevy_directx::{
+    windows::Win32::Graphics::{Direct3D12::*, Dxgi::Common::*},
+    Gpu, GpuConfig,
+};
+use std::{mem::transmute_copy, ptr, slice};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 32;
+const MIP_LEVELS: u16 = 7;
+/// Allowed difference per channel, for rounding in the GPU's filtering and UNORM conversion.
+const TOLERANCE: u8 = 1;
+
+fn main() {
+    let mut gpu = Gpu::new(&GpuConfig::default()).unwrap();
+
+    let desc = D3D12_RESOURCE_DESC {
+        Dimension: D3D12_RESOURCE_DIMENSION_TEXTURE2D,
+        Width: WIDTH as u64,
+        Height: HEIGHT,
+        DepthOrArraySize: 1,
+        MipLevels: MIP_LEVELS,
+        Format: DXGI_FORMAT_R8G8B8A8_UNORM,
+        SampleDesc: DXGI_SAMPLE_DESC {
+            Count: 1,
+            Quality: 0,
+        },
+        Layout: D3D12_TEXTURE_LAYOUT_UNKNOWN,
+        Flags: D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS,
+        ..Default::default()
+    };
+    let layouts = gpu.subresource_layouts(&desc);
+
+    // Gradients along X, Y, and the diagonal, then a reference for each following mip from the previous one
+    let mut expected = vec![(0..HEIGHT)
+        .flat_map(|y| (0..WIDTH).flat_map(move |x| [x * 4, y * 8, (x + y) * 2, 255]))
+        .map(|value| value as u8)
+        .collect::<Vec<_>>()];
+    for mip in 1..MIP_LEVELS as u32 {
+        expected.push(box_filter(
+            &expected[mip as usize - 1],
+            (WIDTH >> (mip - 1)).max(1),
+            (HEIGHT >> (mip - 1)).max(1),
+        ));
+    }
+
+    // Only the first mip is uploaded, the rest start zeroed
+    let subresources = layouts
+        .iter()
+        .enumerate()
+        .map(|(mip, layout)| match mip {
+            0 => expected[0].clone(),
+            _ => vec![0; layout.packed_size_in_bytes() as usize],
+        })
+        .collect::<Vec<_>>();
+    let subresource_slices = subresources.iter().map(Vec::as_slice).collect::<Vec<_>>();
+    let texture = gpu
+        .create_texture_with_data(&desc, &subresource_slices)
+        .unwrap();
+    gpu.generate_mips(&texture).unwrap();
+
+    // Copy every mip back to a readback buffer, using the same footprints
+    let readback_size = layouts
+        .last()
+        .map(|layout| layout.footprint.Offset + layout.size_in_bytes())
+        .unwrap();
+    let readback_buffer = gpu
+        .create_buffer(
+            readback_size,
+            D3D12_HEAP_TYPE_READBACK,
+            D3D12_RESOURCE_FLAG_NONE,
+            D3D12_RESOURCE_STATE_COPY_DEST,
+        )
+        .unwrap();
+    let command_list = gpu.reset_commands(None).unwrap();
+    unsafe {
+        for (i, layout) in layouts.iter().enumerate() {
+            command_list.CopyTextureRegion(
+                &D3D12_TEXTURE_COPY_LOCATION {
+                    pResource: transmute_copy(&readback_buffer),
+                    Type: D3D12_TEXTURE_COPY_TYPE_PLACED_FOOTPRINT,
+                    Anonymous: D3D12_TEXTURE_COPY_LOCATION_0 {
+                        PlacedFootprint: layout.footprint,
+                    },
+                },
+                0,
+                0,
+                0,
+                &D3D12_TEXTURE_COPY_LOCATION {
+                    pResource: transmute_copy(&texture),
+                    Type: D3D12_TEXTURE_COPY_TYPE_SUBRESOURCE_INDEX,
+                    Anonymous: D3D12_TEXTURE_COPY_LOCATION_0 {
+                        SubresourceIndex: i as u32,
+                    },
+                },
+                None,
+            );
+        }
+    }
+    gpu.execute_command_list().unwrap();
+    gpu.signal_fence().unwrap();
+    gpu.wait_for_fence().unwrap();
+
+    let mut mismatched_rows = 0;
+    unsafe {
+        let mut mapped = ptr::null_mut();
+        readback_buffer
+            .Map(
+                0,
+                Some(&D3D12_RANGE {
+                    Begin: 0,
+                    End: readback_size as usize,
+                }),
+                Some(&mut mapped),
+            )
+            .unwrap();
+        let readback = slice::from_raw_parts(mapped as *const u8, readback_size as usize);
+
+        for (mip, (layout, data)) in layouts.iter().zip(&expected).enumerate().skip(1) {
+            for (row, expected) in data.chunks_exact(layout.row_size as usize).enumerate() {
+                let offset = layout.footprint.Offset as usize
+                    + row * layout.footprint.Footprint.RowPitch as usize;
+                let actual = &readback[offset..offset + expected.len()];
+                if actual
+                    .iter()
+                    .zip(expected)
+                    .any(|(actual, expected)| actual.abs_diff(*expected) > TOLERANCE)
+                {
+                    println!("Mip {mip}, row {row} doesn't match: {actual:?} vs {expected:?}");
+                    mismatched_rows += 1;
+                }
+            }
+        }
+        readback_buffer.Unmap(0, None);
+    }
+
+    if mismatched_rows == 0 {
+        println!(
+            "All {} generated mips matched the CPU reference",
+            MIP_LEVELS - 1
+        );
+    } else {
+        panic!("{mismatched_rows} rows didn't match the CPU reference");
+    }
+}
+
+/// Average each 2x2 block of RGBA8 texels, repeating the last row or column of sizes smaller than 2.
+fn box_filter(source: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let (dest_width, dest_height) = ((width / 2).max(1), (height / 2).max(1));
+    let texel = |x: u32, y: u32, channel: u32| {
+        source[((y.min(height - 1) * width + x.min(width - 1)) * 4 + channel) as usize] as u32
+    };
+    (0..dest_height)
+        .flat_map(|y| (0..dest_width).flat_map(move |x| (0..4).map(move |channel| (x, y, channel))))
+        .map(|(x, y, channel)| {
+            let sum = texel(2 * x, 2 * y, channel)
+                + texel(2 * x + 1, 2 * y, channel)
+                + texel(2 * x, 2 * y + 1, channel)
+                + texel(2 * x + 1, 2 * y + 1, channel);
+            ((sum + 2) / 4) as u8
+        })
+        .collect()
+}
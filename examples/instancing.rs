@@ -0,0 +1,214 @@
+use bevy::{
+    app::{App, Startup},
+    prelude::{Commands, IntoSystemConfigs, Query, Res, ResMut, Resource},
+    DefaultPlugins,
+};
+use bevy_directx::{
+    compile_shader, draw_indexed_instanced, set_vertex_buffer,
+    windows::{
+        core::s,
+        Win32::Graphics::{
+            Direct3D::*,
+            Direct3D12::*,
+            Dxgi::Common::{
+                DXGI_FORMAT_R16_UINT, DXGI_FORMAT_R32G32B32A32_FLOAT, DXGI_FORMAT_R32G32_FLOAT,
+            },
+        },
+    },
+    BevyDirectXPlugin, CurrentBackbuffer, Gpu, InputLayout, MappedBuffer, Render, RenderSet,
+    WindowRenderTarget,
+};
+use std::{
+    mem::{self, transmute_copy},
+    slice,
+};
+
+const GRID_SIZE: u32 = 8;
+const INSTANCE_COUNT: u32 = GRID_SIZE * GRID_SIZE;
+
+fn main() {
+    App::new()
+        .add_plugins((
+            DefaultPlugins,
+            BevyDirectXPlugin {
+                manage_backbuffer: true,
+                ..Default::default()
+            },
+        ))
+        .add_systems(Startup, setup)
+        .add_systems(Render, render_frame.in_set(RenderSet::Draw))
+        .run();
+}
+
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct Instance {
+    offset: [f32; 2],
+    color: [f32; 4],
+}
+
+#[derive(Resource)]
+struct Scene {
+    root_signature: ID3D12RootSignature,
+    pipeline: ID3D12PipelineState,
+    vertex_buffer: ID3D12Resource,
+    index_buffer: ID3D12Resource,
+    instance_buffer: ID3D12Resource,
+}
+
+const INDICES: [u16; 6] = [0, 1, 2, 0, 2, 3];
+
+fn setup(mut gpu: ResMut<Gpu>, mut commands: Commands) {
+    let source = include_str!("../assets/instancing.hlsl");
+    let shader_vs = compile_shader(source, "VSMain", "vs_5_1").unwrap();
+    let shader_ps = compile_shader(source, "PSMain", "ps_5_1").unwrap();
+
+    let root_signature = gpu
+        .create_root_signature(
+            &[],
+            &[],
+            D3D12_ROOT_SIGNATURE_FLAG_ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT,
+        )
+        .unwrap();
+
+    // Quad corner positions in slot 0, and per-instance offsets and colors in slot 1
+    let input_layout = InputLayout::new()
+        .vertex_attribute(s!("POSITION"), 0, DXGI_FORMAT_R32G32_FLOAT, 0)
+        .instance_attribute(s!("INSTANCE_OFFSET"), 0, DXGI_FORMAT_R32G32_FLOAT, 1, 1)
+        .instance_attribute(
+            s!("INSTANCE_COLOR"),
+            0,
+            DXGI_FORMAT_R32G32B32A32_FLOAT,
+            1,
+            1,
+        );
+
+    let mut desc = D3D12_GRAPHICS_PIPELINE_STATE_DESC {
+        pRootSignature: unsafe { transmute_copy(&root_signature) },
+        VS: D3D12_SHADER_BYTECODE {
+            pShaderBytecode: shader_vs.as_ptr() as _,
+            BytecodeLength: shader_vs.len(),
+        },
+        PS: D3D12_SHADER_BYTECODE {
+            pShaderBytecode: shader_ps.as_ptr() as _,
+            BytecodeLength: shader_ps.len(),
+        },
+        InputLayout: input_layout.desc(),
+        SampleMask: u32::MAX,
+        RasterizerState: D3D12_RASTERIZER_DESC {
+            FillMode: D3D12_FILL_MODE_SOLID,
+            CullMode: D3D12_CULL_MODE_NONE,
+            ..Default::default()
+        },
+        PrimitiveTopologyType: D3D12_PRIMITIVE_TOPOLOGY_TYPE_TRIANGLE,
+        NumRenderTargets: 1,
+        ..Default::default()
+    };
+    desc.BlendState.RenderTarget[0].RenderTargetWriteMask = D3D12_COLOR_WRITE_ENABLE_ALL.0 as u8;
+    desc.RTVFormats[0] = WindowRenderTarget::FORMAT;
+    desc.SampleDesc.Count = 1;
+    let pipeline = gpu
+        .pipeline_cache()
+        .create_graphics_pipeline(&gpu.device, &desc)
+        .unwrap();
+
+    // The quad never changes, so lives in GPU memory
+    let cell_size = 2.0 / GRID_SIZE as f32;
+    let vertices: [[f32; 2]; 4] = [
+        [-cell_size * 0.4, cell_size * 0.4],
+        [cell_size * 0.4, cell_size * 0.4],
+        [cell_size * 0.4, -cell_size * 0.4],
+        [-cell_size * 0.4, -cell_size * 0.4],
+    ];
+    let vertex_buffer = gpu
+        .create_buffer_with_data(
+            as_bytes(&vertices),
+            D3D12_RESOURCE_STATE_VERTEX_AND_CONSTANT_BUFFER,
+        )
+        .unwrap();
+    let index_buffer = gpu
+        .create_buffer_with_data(as_bytes(&INDICES), D3D12_RESOURCE_STATE_INDEX_BUFFER)
+        .unwrap();
+
+    let instances = (0..INSTANCE_COUNT)
+        .map(|i| {
+            let (x, y) = ((i % GRID_SIZE) as f32, (i / GRID_SIZE) as f32);
+            Instance {
+                offset: [-1.0 + (x + 0.5) * cell_size, -1.0 + (y + 0.5) * cell_size],
+                color: [x / GRID_SIZE as f32, y / GRID_SIZE as f32, 1.0, 1.0],
+            }
+        })
+        .collect::<Vec<_>>();
+    // Upload heap buffers are fine for data written once and read by the GPU every frame in a small example
+    let instance_buffer = create_upload_buffer(&gpu, &instances);
+
+    commands.insert_resource(Scene {
+        root_signature,
+        pipeline,
+        vertex_buffer,
+        index_buffer,
+        instance_buffer,
+    });
+}
+
+fn as_bytes<T: Copy>(data: &[T]) -> &[u8] {
+    unsafe { slice::from_raw_parts(data.as_ptr() as *const u8, mem::size_of_val(data)) }
+}
+
+fn create_upload_buffer<T: Copy>(gpu: &Gpu, data: &[T]) -> ID3D12Resource {
+    let buffer = gpu
+        .create_buffer(
+            mem::size_of_val(data) as u64,
+            D3D12_HEAP_TYPE_UPLOAD,
+            D3D12_RESOURCE_FLAG_NONE,
+            D3D12_RESOURCE_STATE_GENERIC_READ,
+        )
+        .unwrap();
+    MappedBuffer::<T>::write_only(&buffer)
+        .unwrap()
+        .as_mut_slice()
+        .copy_from_slice(data);
+    buffer
+}
+
+fn render_frame(
+    gpu: Res<Gpu>,
+    scene: Res<Scene>,
+    render_target: Query<&WindowRenderTarget>,
+    backbuffer: Option<Res<CurrentBackbuffer>>,
+) {
+    let (Ok(render_target), Some(backbuffer)) = (render_target.get_single(), backbuffer) else {
+        return;
+    };
+
+    let command_list = gpu.command_list();
+    unsafe {
+        command_list.SetPipelineState(&scene.pipeline);
+        command_list.SetGraphicsRootSignature(&scene.root_signature);
+        command_list.RSSetViewports(&[render_target.viewport()]);
+        command_list.RSSetScissorRects(&[render_target.scissor_rect()]);
+        command_list.OMSetRenderTargets(1, Some(&backbuffer.rtv), false, None);
+        command_list.ClearRenderTargetView(backbuffer.rtv, &[0.0, 0.0, 0.0, 1.0], None);
+        command_list.IASetPrimitiveTopology(D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
+    }
+    set_vertex_buffer(
+        command_list,
+        0,
+        &scene.vertex_buffer,
+        mem::size_of::<[f32; 2]>() as u32,
+    );
+    set_vertex_buffer(
+        command_list,
+        1,
+        &scene.instance_buffer,
+        mem::size_of::<Instance>() as u32,
+    );
+    unsafe {
+        command_list.IASetIndexBuffer(Some(&D3D12_INDEX_BUFFER_VIEW {
+            BufferLocation: scene.index_buffer.GetGPUVirtualAddress(),
+            SizeInBytes: mem::size_of_val(&INDICES) as u32,
+            Format: DXGI_FORMAT_R16_UINT,
+        }))
+    };
+    draw_indexed_instanced(command_list, 0..INDICES.len() as u32, 0, 0..INSTANCE_COUNT);
+}
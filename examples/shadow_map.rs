@@ -0,0 +1,182 @@
+use bevy::{
+    app::{App, Startup},
+    math::{Mat4, UVec2, Vec3},
+    prelude::{Commands, IntoSystemConfigs, Query, Res, Resource},
+    DefaultPlugins,
+};
+use bevy_directx::{
+    compile_shader,
+    windows::Win32::Graphics::{Direct3D::*, Direct3D12::*, Dxgi::Common::DXGI_SAMPLE_DESC},
+    BevyDirectXPlugin, CurrentBackbuffer, DepthTarget, Gpu, Render, RenderSet, WindowRenderTarget,
+};
+use std::mem::transmute_copy;
+
+const SHADOW_MAP_SIZE: u32 = 1024;
+
+fn main() {
+    App::new()
+        .add_plugins((
+            DefaultPlugins,
+            BevyDirectXPlugin {
+                manage_backbuffer: true,
+                ..Default::default()
+            },
+        ))
+        .add_systems(Startup, setup)
+        .add_systems(Render, render_frame.in_set(RenderSet::Draw))
+        .run();
+}
+
+#[derive(Resource)]
+struct Scene {
+    root_signature: ID3D12RootSignature,
+    shadow_pipeline: ID3D12PipelineState,
+    main_pipeline: ID3D12PipelineState,
+    shadow_map: DepthTarget,
+}
+
+fn setup(gpu: Res<Gpu>, mut commands: Commands) {
+    let source = include_str!("../assets/shadow_map.hlsl");
+    let shadow_vs = compile_shader(source, "ShadowVSMain", "vs_5_1").unwrap();
+    let main_vs = compile_shader(source, "VSMain", "vs_5_1").unwrap();
+    let main_ps = compile_shader(source, "PSMain", "ps_5_1").unwrap();
+
+    // Camera and light matrices as root constants, and the shadow map with a comparison sampler
+    let srv_range = D3D12_DESCRIPTOR_RANGE1 {
+        RangeType: D3D12_DESCRIPTOR_RANGE_TYPE_SRV,
+        NumDescriptors: 1,
+        ..Default::default()
+    };
+    let root_signature = gpu
+        .create_root_signature(
+            &[
+                D3D12_ROOT_PARAMETER1 {
+                    ParameterType: D3D12_ROOT_PARAMETER_TYPE_32BIT_CONSTANTS,
+                    Anonymous: D3D12_ROOT_PARAMETER1_0 {
+                        Constants: D3D12_ROOT_CONSTANTS {
+                            Num32BitValues: 32,
+                            ..Default::default()
+                        },
+                    },
+                    ShaderVisibility: D3D12_SHADER_VISIBILITY_ALL,
+                },
+                D3D12_ROOT_PARAMETER1 {
+                    ParameterType: D3D12_ROOT_PARAMETER_TYPE_DESCRIPTOR_TABLE,
+                    Anonymous: D3D12_ROOT_PARAMETER1_0 {
+                        DescriptorTable: D3D12_ROOT_DESCRIPTOR_TABLE1 {
+                            NumDescriptorRanges: 1,
+                            pDescriptorRanges: &srv_range,
+                        },
+                    },
+                    ShaderVisibility: D3D12_SHADER_VISIBILITY_PIXEL,
+                },
+            ],
+            &[D3D12_STATIC_SAMPLER_DESC {
+                Filter: D3D12_FILTER_COMPARISON_MIN_MAG_LINEAR_MIP_POINT,
+                AddressU: D3D12_TEXTURE_ADDRESS_MODE_BORDER,
+                AddressV: D3D12_TEXTURE_ADDRESS_MODE_BORDER,
+                AddressW: D3D12_TEXTURE_ADDRESS_MODE_BORDER,
+                ComparisonFunc: D3D12_COMPARISON_FUNC_LESS_EQUAL,
+                BorderColor: D3D12_STATIC_BORDER_COLOR_OPAQUE_WHITE,
+                ShaderVisibility: D3D12_SHADER_VISIBILITY_PIXEL,
+                ..Default::default()
+            }],
+            D3D12_ROOT_SIGNATURE_FLAG_NONE,
+        )
+        .unwrap();
+
+    let shadow_pipeline = gpu
+        .pipeline_cache()
+        .create_graphics_pipeline(
+            &gpu.device,
+            &DepthTarget::pipeline_desc(&root_signature, &shadow_vs),
+        )
+        .unwrap();
+
+    let mut main_desc = D3D12_GRAPHICS_PIPELINE_STATE_DESC {
+        pRootSignature: unsafe { transmute_copy(&root_signature) },
+        VS: D3D12_SHADER_BYTECODE {
+            pShaderBytecode: main_vs.as_ptr() as _,
+            BytecodeLength: main_vs.len(),
+        },
+        PS: D3D12_SHADER_BYTECODE {
+            pShaderBytecode: main_ps.as_ptr() as _,
+            BytecodeLength: main_ps.len(),
+        },
+        SampleMask: u32::MAX,
+        RasterizerState: D3D12_RASTERIZER_DESC {
+            FillMode: D3D12_FILL_MODE_SOLID,
+            CullMode: D3D12_CULL_MODE_NONE,
+            ..Default::default()
+        },
+        PrimitiveTopologyType: D3D12_PRIMITIVE_TOPOLOGY_TYPE_TRIANGLE,
+        NumRenderTargets: 1,
+        SampleDesc: DXGI_SAMPLE_DESC {
+            Count: 1,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    main_desc.BlendState.RenderTarget[0].RenderTargetWriteMask =
+        D3D12_COLOR_WRITE_ENABLE_ALL.0 as u8;
+    main_desc.RTVFormats[0] = WindowRenderTarget::FORMAT;
+    let main_pipeline = gpu
+        .pipeline_cache()
+        .create_graphics_pipeline(&gpu.device, &main_desc)
+        .unwrap();
+
+    let shadow_map = DepthTarget::new(&gpu, UVec2::splat(SHADOW_MAP_SIZE)).unwrap();
+
+    commands.insert_resource(Scene {
+        root_signature,
+        shadow_pipeline,
+        main_pipeline,
+        shadow_map,
+    });
+}
+
+fn render_frame(
+    gpu: Res<Gpu>,
+    scene: Res<Scene>,
+    render_target: Query<&WindowRenderTarget>,
+    backbuffer: Option<Res<CurrentBackbuffer>>,
+) {
+    let (Ok(render_target), Some(backbuffer)) = (render_target.get_single(), backbuffer) else {
+        return;
+    };
+
+    // The ground is drawn before the triangle, so no depth buffer is needed for the camera
+    let viewport = render_target.viewport();
+    let aspect_ratio = viewport.Width / viewport.Height;
+    let camera_view_projection = Mat4::perspective_rh(60f32.to_radians(), aspect_ratio, 0.1, 100.0)
+        * Mat4::look_at_rh(Vec3::new(3.0, 3.0, 4.0), Vec3::new(0.0, 0.3, 0.0), Vec3::Y);
+    let light_view_projection = Mat4::orthographic_rh(-3.0, 3.0, -3.0, 3.0, 0.1, 10.0)
+        * Mat4::look_at_rh(Vec3::new(2.0, 5.0, 1.0), Vec3::ZERO, Vec3::Y);
+    let constants = [camera_view_projection, light_view_projection];
+
+    let shadow_map = &scene.shadow_map;
+    let command_list = gpu.command_list();
+    unsafe { command_list.SetGraphicsRootSignature(&scene.root_signature) };
+    gpu.set_root_constants(command_list, 0, &constants);
+    unsafe {
+        command_list.IASetPrimitiveTopology(D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
+
+        // Render depth from the light's point of view
+        shadow_map.begin_render(command_list);
+        command_list.SetPipelineState(&scene.shadow_pipeline);
+        command_list.RSSetViewports(&[shadow_map.viewport()]);
+        command_list.RSSetScissorRects(&[shadow_map.scissor_rect()]);
+        command_list.DrawInstanced(9, 1, 0, 0);
+        shadow_map.end_render(command_list);
+
+        // Render the scene from the camera, sampling the shadow map
+        command_list.OMSetRenderTargets(1, Some(&backbuffer.rtv), false, None);
+        command_list.ClearRenderTargetView(backbuffer.rtv, &[0.1, 0.1, 0.15, 1.0], None);
+        command_list.SetPipelineState(&scene.main_pipeline);
+        command_list.SetDescriptorHeaps(&[Some(shadow_map.srv_heap().clone())]);
+        command_list.SetGraphicsRootDescriptorTable(1, shadow_map.srv());
+        command_list.RSSetViewports(&[viewport]);
+        command_list.RSSetScissorRects(&[render_target.scissor_rect()]);
+        command_list.DrawInstanced(9, 1, 0, 0);
+    }
+}
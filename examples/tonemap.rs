@@ -0,0 +1,149 @@
+use bevy::{
+    app::{App, Startup},
+    prelude::{Commands, IntoSystemConfigs, Local, Query, Res, Resource},
+    DefaultPlugins,
+};
+use bevy_directx::{
+    windows::Win32::Graphics::{
+        Direct3D::*,
+        Direct3D12::*,
+        Dxgi::Common::{DXGI_FORMAT, DXGI_FORMAT_R16G16B16A16_FLOAT, DXGI_SAMPLE_DESC},
+    },
+    BevyDirectXPlugin, CurrentBackbuffer, Gpu, OffscreenTarget, Render, RenderSet, TonemapPass,
+    TonemapSettings, WindowRenderTarget,
+};
+use std::mem::transmute_copy;
+
+const HDR_FORMAT: DXGI_FORMAT = DXGI_FORMAT_R16G16B16A16_FLOAT;
+const EXPOSURE: f32 = 4.0;
+
+fn main() {
+    App::new()
+        .add_plugins((
+            DefaultPlugins,
+            BevyDirectXPlugin {
+                manage_backbuffer: true,
+                ..Default::default()
+            },
+        ))
+        .insert_resource(TonemapSettings {
+            exposure: EXPOSURE,
+            ..Default::default()
+        })
+        .add_systems(Startup, setup_pipelines)
+        .add_systems(Render, render_frame.in_set(RenderSet::Draw))
+        .run();
+}
+
+#[derive(Resource)]
+struct Pipelines {
+    scene_root_signature: ID3D12RootSignature,
+    scene_pipeline: ID3D12PipelineState,
+    tonemap_pass: TonemapPass,
+}
+
+fn setup_pipelines(gpu: Res<Gpu>, mut commands: Commands) {
+    // Scene, rendered to an HDR offscreen target
+    let scene_vs = include_bytes!("../assets/triangle_vs.dxil");
+    let scene_ps = include_bytes!("../assets/triangle_ps.dxil");
+    let scene_root_signature = gpu
+        .create_root_signature(&[], &[], D3D12_ROOT_SIGNATURE_FLAG_NONE)
+        .unwrap();
+    let scene_pipeline = gpu
+        .pipeline_cache()
+        .create_graphics_pipeline(
+            &gpu.device,
+            &pipeline_desc(&scene_root_signature, scene_vs, scene_ps, HDR_FORMAT),
+        )
+        .unwrap();
+
+    // Tonemapping, sampling the offscreen target and rendering to the window
+    let tonemap_pass = TonemapPass::new(&gpu, WindowRenderTarget::FORMAT).unwrap();
+
+    commands.insert_resource(Pipelines {
+        scene_root_signature,
+        scene_pipeline,
+        tonemap_pass,
+    });
+}
+
+fn render_frame(
+    gpu: Res<Gpu>,
+    pipelines: Res<Pipelines>,
+    tonemap_settings: Res<TonemapSettings>,
+    render_target: Query<&WindowRenderTarget>,
+    backbuffer: Option<Res<CurrentBackbuffer>>,
+    mut hdr_target: Local<Option<OffscreenTarget>>,
+) {
+    let (Ok(render_target), Some(backbuffer)) = (render_target.get_single(), backbuffer) else {
+        return;
+    };
+
+    // Recreate the offscreen target when the window is resized. The GPU is idle after wait_for_ready_frame(),
+    // so the old texture can be dropped.
+    let size = render_target.render_size();
+    if hdr_target.as_ref().map(OffscreenTarget::size) != Some(size) {
+        *hdr_target = Some(OffscreenTarget::new(&gpu, size, HDR_FORMAT).unwrap());
+    }
+    let hdr_target = hdr_target.as_ref().unwrap();
+
+    let command_list = gpu.command_list();
+    unsafe {
+        // Render the scene to the offscreen target
+        hdr_target.begin_render(command_list);
+        command_list.ClearRenderTargetView(hdr_target.rtv(), &[0.0, 0.0, 0.0, 1.0], None);
+        command_list.SetPipelineState(&pipelines.scene_pipeline);
+        command_list.SetGraphicsRootSignature(&pipelines.scene_root_signature);
+        command_list.RSSetViewports(&[hdr_target.viewport()]);
+        command_list.RSSetScissorRects(&[hdr_target.scissor_rect()]);
+        command_list.IASetPrimitiveTopology(D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
+        command_list.DrawInstanced(3, 1, 0, 0);
+        hdr_target.end_render(command_list);
+
+        // Tonemap it to the window
+        pipelines.tonemap_pass.draw(
+            command_list,
+            &tonemap_settings,
+            hdr_target.srv_heap(),
+            hdr_target.srv(),
+            backbuffer.rtv,
+            render_target.viewport(),
+            render_target.scissor_rect(),
+        );
+    }
+}
+
+fn pipeline_desc(
+    root_signature: &ID3D12RootSignature,
+    shader_vs: &[u8],
+    shader_ps: &[u8],
+    rtv_format: DXGI_FORMAT,
+) -> D3D12_GRAPHICS_PIPELINE_STATE_DESC {
+    let mut desc = D3D12_GRAPHICS_PIPELINE_STATE_DESC {
+        pRootSignature: unsafe { transmute_copy(root_signature) },
+        VS: D3D12_SHADER_BYTECODE {
+            pShaderBytecode: shader_vs.as_ptr() as _,
+            BytecodeLength: shader_vs.len(),
+        },
+        PS: D3D12_SHADER_BYTECODE {
+            pShaderBytecode: shader_ps.as_ptr() as _,
+            BytecodeLength: shader_ps.len(),
+        },
+        SampleMask: u32::MAX,
+        RasterizerState: D3D12_RASTERIZER_DESC {
+            FillMode: D3D12_FILL_MODE_SOLID,
+            CullMode: D3D12_CULL_MODE_NONE,
+            ..Default::default()
+        },
+        PrimitiveTopologyType: D3D12_PRIMITIVE_TOPOLOGY_TYPE_TRIANGLE,
+        NumRenderTargets: 1,
+        SampleDesc: DXGI_SAMPLE_DESC {
+            Count: 1,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    desc.BlendState.RenderTarget[0].RenderTargetWriteMask = D3D12_COLOR_WRITE_ENABLE_ALL.0 as u8;
+    desc.RTVFormats[0] = rtv_format;
+    desc
+}
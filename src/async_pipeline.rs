@@ -0,0 +1,155 @@
+use crate::{error::DxError, gpu::Gpu, pipeline_cache::PipelineCache};
+use bevy::tasks::{block_on, poll_once, AsyncComputeTaskPool, Task};
+use std::{
+    ffi::{CStr, CString},
+    mem::{transmute_copy, ManuallyDrop},
+    slice,
+    sync::Arc,
+};
+use windows::{core::PCSTR, Win32::Graphics::Direct3D12::*};
+
+/// A pipeline being compiled in the background, created by [`Gpu::create_graphics_pipeline_async`].
+pub struct PipelineHandle {
+    task: Option<Task<Result<ID3D12PipelineState, DxError>>>,
+    result: Option<Result<ID3D12PipelineState, DxError>>,
+}
+
+impl PipelineHandle {
+    /// Check whether compilation has finished, without blocking. Returns `None` while still compiling.
+    ///
+    /// Render with a fallback pipeline until this returns `Some`.
+    pub fn poll(&mut self) -> Option<&Result<ID3D12PipelineState, DxError>> {
+        if let Some(task) = &mut self.task {
+            if let Some(result) = block_on(poll_once(task)) {
+                self.result = Some(result);
+                self.task = None;
+            }
+        }
+        self.result.as_ref()
+    }
+
+    /// Block until compilation has finished.
+    pub fn wait(mut self) -> Result<ID3D12PipelineState, DxError> {
+        match self.task.take() {
+            Some(task) => block_on(task),
+            None => self.result.take().unwrap(),
+        }
+    }
+}
+
+impl Gpu {
+    /// Compile a graphics pipeline on [`AsyncComputeTaskPool`], going through [`Gpu::pipeline_cache`].
+    ///
+    /// Shader bytecode, the input layout, and the root signature referenced by `desc` are copied, so they don't need
+    /// to outlive this call. Stream output is not supported.
+    ///
+    /// Any number of pipelines can be compiled at once, including duplicates, which wait for the first to finish and
+    /// then load it from the cache, see [`PipelineCache`].
+    pub fn create_graphics_pipeline_async(
+        &self,
+        desc: &D3D12_GRAPHICS_PIPELINE_STATE_DESC,
+    ) -> PipelineHandle {
+        let desc = OwnedGraphicsPipelineDesc::new(desc);
+        let device = self.device.clone();
+        let pipeline_cache: Arc<PipelineCache> = self.pipeline_cache.clone();
+
+        let task = AsyncComputeTaskPool::get()
+            .spawn(async move { pipeline_cache.create_graphics_pipeline(&device, &desc.desc()) });
+
+        PipelineHandle {
+            task: Some(task),
+            result: None,
+        }
+    }
+}
+
+/// Copy of a [`D3D12_GRAPHICS_PIPELINE_STATE_DESC`] that owns everything it points to.
+struct OwnedGraphicsPipelineDesc {
+    desc: D3D12_GRAPHICS_PIPELINE_STATE_DESC,
+    root_signature: Option<ID3D12RootSignature>,
+    shaders: [Vec<u8>; 5],
+    input_elements: Vec<D3D12_INPUT_ELEMENT_DESC>,
+    _semantic_names: Vec<CString>,
+}
+
+// Safety: All pointers in `desc` point into data owned by this struct, or are null
+unsafe impl Send for OwnedGraphicsPipelineDesc {}
+
+impl OwnedGraphicsPipelineDesc {
+    fn new(desc: &D3D12_GRAPHICS_PIPELINE_STATE_DESC) -> Self {
+        assert!(
+            desc.StreamOutput.NumEntries == 0,
+            "BevyDirectX: Stream output is not supported for async pipeline compilation"
+        );
+
+        let shaders = [&desc.VS, &desc.PS, &desc.DS, &desc.HS, &desc.GS].map(|shader| {
+            if shader.pShaderBytecode.is_null() {
+                Vec::new()
+            } else {
+                unsafe {
+                    slice::from_raw_parts(
+                        shader.pShaderBytecode as *const u8,
+                        shader.BytecodeLength,
+                    )
+                }
+                .to_vec()
+            }
+        });
+
+        let mut semantic_names = Vec::new();
+        let mut input_elements = Vec::new();
+        if !desc.InputLayout.pInputElementDescs.is_null() {
+            let elements = unsafe {
+                slice::from_raw_parts(
+                    desc.InputLayout.pInputElementDescs,
+                    desc.InputLayout.NumElements as usize,
+                )
+            };
+            for element in elements {
+                let name = unsafe { CStr::from_ptr(element.SemanticName.0 as _) }.to_owned();
+                input_elements.push(D3D12_INPUT_ELEMENT_DESC {
+                    SemanticName: PCSTR::from_raw(name.as_ptr() as _),
+                    ..*element
+                });
+                semantic_names.push(name);
+            }
+        }
+
+        let mut owned_desc = desc.clone();
+        owned_desc.pRootSignature = ManuallyDrop::new(None);
+        owned_desc.CachedPSO = D3D12_CACHED_PIPELINE_STATE::default();
+
+        Self {
+            desc: owned_desc,
+            root_signature: (*desc.pRootSignature).clone(),
+            shaders,
+            input_elements,
+            _semantic_names: semantic_names,
+        }
+    }
+
+    fn desc(&self) -> D3D12_GRAPHICS_PIPELINE_STATE_DESC {
+        let mut desc = self.desc.clone();
+        desc.pRootSignature = unsafe { transmute_copy(&self.root_signature) };
+        for (shader, bytecode) in [
+            &mut desc.VS,
+            &mut desc.PS,
+            &mut desc.DS,
+            &mut desc.HS,
+            &mut desc.GS,
+        ]
+        .into_iter()
+        .zip(&self.shaders)
+        {
+            *shader = D3D12_SHADER_BYTECODE {
+                pShaderBytecode: bytecode.as_ptr() as _,
+                BytecodeLength: bytecode.len(),
+            };
+        }
+        desc.InputLayout = D3D12_INPUT_LAYOUT_DESC {
+            pInputElementDescs: self.input_elements.as_ptr(),
+            NumElements: self.input_elements.len() as u32,
+        };
+        desc
+    }
+}
@@ -0,0 +1,82 @@
+use crate::{gpu::Gpu, swapchain::WindowRenderTarget};
+use bevy::{
+    prelude::{Commands, Query, Res, ResMut, Resource, With},
+    window::PrimaryWindow,
+};
+use windows::Win32::Graphics::Direct3D12::{
+    ID3D12Resource, D3D12_CPU_DESCRIPTOR_HANDLE, D3D12_RESOURCE_STATE_PRESENT,
+    D3D12_RESOURCE_STATE_RENDER_TARGET,
+};
+
+/// The primary window's texture to render to this frame, already in the RENDER_TARGET state.
+///
+/// Only present between [`begin_frame`] and [`end_frame`], when [`crate::BevyDirectXPlugin::manage_backbuffer`] is
+/// enabled. Systems in [`crate::RenderSet::Draw`] record into [`Gpu::command_list`] without calling
+/// [`Gpu::reset_commands`], and must leave the texture in the RENDER_TARGET state.
+#[derive(Resource, Clone, Debug)]
+pub struct CurrentBackbuffer {
+    /// Texture returned by [`WindowRenderTarget::rtv`], which is an intermediate texture if [`crate::RenderScale`]
+    /// is not 1.0 or [`crate::FixedResolution`] is set.
+    pub resource: ID3D12Resource,
+    pub rtv: D3D12_CPU_DESCRIPTOR_HANDLE,
+    /// Index of the swapchain's current backbuffer.
+    pub index: u32,
+}
+
+/// Reset the command list, transition the primary window's render target to RENDER_TARGET, and insert
+/// [`CurrentBackbuffer`].
+///
+/// The transition starts from the render target's tracked state (see [`WindowRenderTarget::rtv_state`]), so it's
+/// correct for backbuffers that haven't been presented yet, which start in PRESENT.
+///
+/// Skips the frame, leaving [`CurrentBackbuffer`] absent, while the window has no swapchain or is occluded, see
+/// [`WindowRenderTarget::is_occluded`].
+pub fn begin_frame(
+    mut window: Query<&mut WindowRenderTarget, With<PrimaryWindow>>,
+    gpu: Res<Gpu>,
+    mut commands: Commands,
+) {
+    let Ok(mut render_target) = window.get_single_mut() else {
+        return;
+    };
+    if !render_target.has_swapchain() || render_target.is_occluded() {
+        return;
+    }
+
+    let command_list = gpu
+        .reset_commands(None)
+        .expect("BevyDirectX: Failed to reset command list");
+    render_target.transition_rtv(command_list, D3D12_RESOURCE_STATE_RENDER_TARGET);
+    let (resource, rtv) = render_target.rtv();
+
+    commands.insert_resource(CurrentBackbuffer {
+        resource: resource.clone(),
+        rtv,
+        index: render_target.backbuffer_index().unwrap(),
+    });
+}
+
+/// Transition [`CurrentBackbuffer`] back to PRESENT, upscale it if needed, submit the command list, present,
+/// and signal the fence. Removes [`CurrentBackbuffer`].
+pub fn end_frame(
+    mut window: Query<&mut WindowRenderTarget, With<PrimaryWindow>>,
+    backbuffer: Option<Res<CurrentBackbuffer>>,
+    mut gpu: ResMut<Gpu>,
+    mut commands: Commands,
+) {
+    let (Some(_), Ok(mut render_target)) = (backbuffer, window.get_single_mut()) else {
+        return;
+    };
+    if !render_target.has_swapchain() {
+        return;
+    }
+
+    let command_list = gpu.command_list();
+    render_target.transition_rtv(command_list, D3D12_RESOURCE_STATE_PRESENT);
+    render_target.upscale_to_backbuffer(command_list);
+
+    gpu.submit_and_present(&render_target)
+        .expect("BevyDirectX: Failed to submit and present frame");
+
+    commands.remove_resource::<CurrentBackbuffer>();
+}
@@ -0,0 +1,120 @@
+use crate::{error::DxError, gpu::Gpu, shader::compile_shader};
+use std::mem::transmute_copy;
+use windows::Win32::{
+    Foundation::RECT,
+    Graphics::{
+        Direct3D::D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST, Direct3D12::*, Dxgi::Common::DXGI_FORMAT,
+    },
+};
+
+/// Pipeline for copying a texture onto a render target of a potentially different size, using bilinear filtering.
+pub struct BlitPipeline {
+    root_signature: ID3D12RootSignature,
+    pipeline: ID3D12PipelineState,
+}
+
+impl BlitPipeline {
+    pub fn new(gpu: &Gpu, rtv_format: DXGI_FORMAT) -> Result<Self, DxError> {
+        Self::with_filter(gpu, rtv_format, D3D12_FILTER_MIN_MAG_MIP_LINEAR)
+    }
+
+    pub fn with_filter(
+        gpu: &Gpu,
+        rtv_format: DXGI_FORMAT,
+        filter: D3D12_FILTER,
+    ) -> Result<Self, DxError> {
+        let source = include_str!("../assets/blit.hlsl");
+        let shader_vs = compile_shader(source, "VSMain", "vs_5_1")?;
+        let shader_ps = compile_shader(source, "PSMain", "ps_5_1")?;
+
+        let srv_range = D3D12_DESCRIPTOR_RANGE1 {
+            RangeType: D3D12_DESCRIPTOR_RANGE_TYPE_SRV,
+            NumDescriptors: 1,
+            BaseShaderRegister: 0,
+            RegisterSpace: 0,
+            Flags: D3D12_DESCRIPTOR_RANGE_FLAG_NONE,
+            OffsetInDescriptorsFromTableStart: 0,
+        };
+        let root_signature = gpu.create_root_signature(
+            &[D3D12_ROOT_PARAMETER1 {
+                ParameterType: D3D12_ROOT_PARAMETER_TYPE_DESCRIPTOR_TABLE,
+                Anonymous: D3D12_ROOT_PARAMETER1_0 {
+                    DescriptorTable: D3D12_ROOT_DESCRIPTOR_TABLE1 {
+                        NumDescriptorRanges: 1,
+                        pDescriptorRanges: &srv_range,
+                    },
+                },
+                ShaderVisibility: D3D12_SHADER_VISIBILITY_PIXEL,
+            }],
+            &[D3D12_STATIC_SAMPLER_DESC {
+                Filter: filter,
+                AddressU: D3D12_TEXTURE_ADDRESS_MODE_CLAMP,
+                AddressV: D3D12_TEXTURE_ADDRESS_MODE_CLAMP,
+                AddressW: D3D12_TEXTURE_ADDRESS_MODE_CLAMP,
+                MaxLOD: D3D12_FLOAT32_MAX,
+                ShaderVisibility: D3D12_SHADER_VISIBILITY_PIXEL,
+                ..Default::default()
+            }],
+            D3D12_ROOT_SIGNATURE_FLAG_NONE,
+        )?;
+
+        let mut desc = D3D12_GRAPHICS_PIPELINE_STATE_DESC {
+            pRootSignature: unsafe { transmute_copy(&root_signature) },
+            VS: D3D12_SHADER_BYTECODE {
+                pShaderBytecode: shader_vs.as_ptr() as _,
+                BytecodeLength: shader_vs.len(),
+            },
+            PS: D3D12_SHADER_BYTECODE {
+                pShaderBytecode: shader_ps.as_ptr() as _,
+                BytecodeLength: shader_ps.len(),
+            },
+            SampleMask: u32::MAX,
+            RasterizerState: D3D12_RASTERIZER_DESC {
+                FillMode: D3D12_FILL_MODE_SOLID,
+                CullMode: D3D12_CULL_MODE_NONE,
+                ..Default::default()
+            },
+            PrimitiveTopologyType: D3D12_PRIMITIVE_TOPOLOGY_TYPE_TRIANGLE,
+            NumRenderTargets: 1,
+            ..Default::default()
+        };
+        desc.BlendState.RenderTarget[0].RenderTargetWriteMask =
+            D3D12_COLOR_WRITE_ENABLE_ALL.0 as u8;
+        desc.RTVFormats[0] = rtv_format;
+        desc.SampleDesc.Count = 1;
+        let pipeline = gpu
+            .pipeline_cache()
+            .create_graphics_pipeline(&gpu.device, &desc)?;
+
+        Ok(Self {
+            root_signature,
+            pipeline,
+        })
+    }
+
+    /// Draw `source_srv` (a GPU handle within `srv_heap`) onto `destination_rtv`, covering `viewport`.
+    ///
+    /// The source must be in a pixel shader resource state, and the destination in the render target state.
+    /// Binds `srv_heap` as the command list's descriptor heap.
+    pub fn blit(
+        &self,
+        command_list: &ID3D12GraphicsCommandList7,
+        srv_heap: &ID3D12DescriptorHeap,
+        source_srv: D3D12_GPU_DESCRIPTOR_HANDLE,
+        destination_rtv: D3D12_CPU_DESCRIPTOR_HANDLE,
+        viewport: D3D12_VIEWPORT,
+        scissor_rect: RECT,
+    ) {
+        unsafe {
+            command_list.SetPipelineState(&self.pipeline);
+            command_list.SetGraphicsRootSignature(&self.root_signature);
+            command_list.SetDescriptorHeaps(&[Some(srv_heap.clone())]);
+            command_list.SetGraphicsRootDescriptorTable(0, source_srv);
+            command_list.RSSetViewports(&[viewport]);
+            command_list.RSSetScissorRects(&[scissor_rect]);
+            command_list.OMSetRenderTargets(1, Some(&destination_rtv), false, None);
+            command_list.IASetPrimitiveTopology(D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
+            command_list.DrawInstanced(3, 1, 0, 0);
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use crate::gpu::Gpu;
+use windows::Win32::Graphics::{
+    Direct3D12::*,
+    Dxgi::Common::{DXGI_FORMAT, DXGI_FORMAT_R32_TYPELESS, DXGI_FORMAT_UNKNOWN},
+};
+
+/// Append/consume counter for a structured buffer UAV, see [`Gpu::create_structured_buffer_uav`].
+#[derive(Clone, Copy)]
+pub struct UavCounter<'a> {
+    /// Buffer holding the counter, a single `u32`. May be the structured buffer itself.
+    pub resource: &'a ID3D12Resource,
+    /// Byte offset of the counter within `resource`. Must be a multiple of
+    /// `D3D12_UAV_COUNTER_PLACEMENT_ALIGNMENT` (4096).
+    pub offset: u64,
+}
+
+impl Gpu {
+    /// Create an SRV at `descriptor` over `count` elements of `stride` bytes each, starting at element `first_element`
+    /// of `resource`, viewed as a `StructuredBuffer<T>` where `T` is `stride` bytes.
+    pub fn create_structured_buffer_srv(
+        &self,
+        resource: &ID3D12Resource,
+        stride: u32,
+        first_element: u64,
+        count: u32,
+        descriptor: D3D12_CPU_DESCRIPTOR_HANDLE,
+    ) {
+        assert!(
+            stride > 0,
+            "BevyDirectX: Structured buffer stride must be non-zero"
+        );
+        let desc = buffer_srv_desc(
+            DXGI_FORMAT_UNKNOWN,
+            D3D12_BUFFER_SRV {
+                FirstElement: first_element,
+                NumElements: count,
+                StructureByteStride: stride,
+                Flags: D3D12_BUFFER_SRV_FLAG_NONE,
+            },
+        );
+        unsafe {
+            self.device
+                .CreateShaderResourceView(resource, Some(&desc), descriptor)
+        };
+    }
+
+    /// Create an SRV at `descriptor` over `size` bytes of `resource` starting at `offset`, viewed as a
+    /// `ByteAddressBuffer`. Both must be multiples of `D3D12_RAW_UAV_SRV_BYTE_ALIGNMENT` (16).
+    pub fn create_raw_buffer_srv(
+        &self,
+        resource: &ID3D12Resource,
+        offset: u64,
+        size: u64,
+        descriptor: D3D12_CPU_DESCRIPTOR_HANDLE,
+    ) {
+        let (first_element, count) = raw_elements(offset, size);
+        let desc = buffer_srv_desc(
+            DXGI_FORMAT_R32_TYPELESS,
+            D3D12_BUFFER_SRV {
+                FirstElement: first_element,
+                NumElements: count,
+                StructureByteStride: 0,
+                Flags: D3D12_BUFFER_SRV_FLAG_RAW,
+            },
+        );
+        unsafe {
+            self.device
+                .CreateShaderResourceView(resource, Some(&desc), descriptor)
+        };
+    }
+
+    /// Create an SRV at `descriptor` over `count` elements of `format`, starting at element `first_element` of
+    /// `resource`, viewed as a `Buffer<T>`.
+    pub fn create_typed_buffer_srv(
+        &self,
+        resource: &ID3D12Resource,
+        format: DXGI_FORMAT,
+        first_element: u64,
+        count: u32,
+        descriptor: D3D12_CPU_DESCRIPTOR_HANDLE,
+    ) {
+        assert!(
+            format != DXGI_FORMAT_UNKNOWN,
+            "BevyDirectX: Typed buffer views need a format, use a structured buffer view instead"
+        );
+        let desc = buffer_srv_desc(
+            format,
+            D3D12_BUFFER_SRV {
+                FirstElement: first_element,
+                NumElements: count,
+                StructureByteStride: 0,
+                Flags: D3D12_BUFFER_SRV_FLAG_NONE,
+            },
+        );
+        unsafe {
+            self.device
+                .CreateShaderResourceView(resource, Some(&desc), descriptor)
+        };
+    }
+
+    /// Create a UAV at `descriptor` over `count` elements of `stride` bytes each, starting at element `first_element`
+    /// of `resource`, viewed as a `RWStructuredBuffer<T>`, or with a `counter`, an `AppendStructuredBuffer<T>` or
+    /// `ConsumeStructuredBuffer<T>`.
+    ///
+    /// `resource` must have been created with `D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS`.
+    pub fn create_structured_buffer_uav(
+        &self,
+        resource: &ID3D12Resource,
+        stride: u32,
+        first_element: u64,
+        count: u32,
+        counter: Option<UavCounter>,
+        descriptor: D3D12_CPU_DESCRIPTOR_HANDLE,
+    ) {
+        assert!(
+            stride > 0,
+            "BevyDirectX: Structured buffer stride must be non-zero"
+        );
+        let counter_offset = counter.map_or(0, |counter| counter.offset);
+        assert!(
+            counter_offset % D3D12_UAV_COUNTER_PLACEMENT_ALIGNMENT as u64 == 0,
+            "BevyDirectX: UAV counter offset must be a multiple of {D3D12_UAV_COUNTER_PLACEMENT_ALIGNMENT}, was {counter_offset}"
+        );
+
+        let desc = buffer_uav_desc(
+            DXGI_FORMAT_UNKNOWN,
+            D3D12_BUFFER_UAV {
+                FirstElement: first_element,
+                NumElements: count,
+                StructureByteStride: stride,
+                CounterOffsetInBytes: counter_offset,
+                Flags: D3D12_BUFFER_UAV_FLAG_NONE,
+            },
+        );
+        unsafe {
+            self.device.CreateUnorderedAccessView(
+                resource,
+                counter.map(|counter| counter.resource),
+                Some(&desc),
+                descriptor,
+            )
+        };
+    }
+
+    /// Create a UAV at `descriptor` over `size` bytes of `resource` starting at `offset`, viewed as a
+    /// `RWByteAddressBuffer`. Both must be multiples of `D3D12_RAW_UAV_SRV_BYTE_ALIGNMENT` (16).
+    ///
+    /// `resource` must have been created with `D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS`.
+    pub fn create_raw_buffer_uav(
+        &self,
+        resource: &ID3D12Resource,
+        offset: u64,
+        size: u64,
+        descriptor: D3D12_CPU_DESCRIPTOR_HANDLE,
+    ) {
+        let (first_element, count) = raw_elements(offset, size);
+        let desc = buffer_uav_desc(
+            DXGI_FORMAT_R32_TYPELESS,
+            D3D12_BUFFER_UAV {
+                FirstElement: first_element,
+                NumElements: count,
+                StructureByteStride: 0,
+                CounterOffsetInBytes: 0,
+                Flags: D3D12_BUFFER_UAV_FLAG_RAW,
+            },
+        );
+        unsafe {
+            self.device
+                .CreateUnorderedAccessView(resource, None, Some(&desc), descriptor)
+        };
+    }
+
+    /// Create a UAV at `descriptor` over `count` elements of `format`, starting at element `first_element` of
+    /// `resource`, viewed as a `RWBuffer<T>`. See [`crate::GpuCapabilities::typed_uav_load_additional_formats`] for
+    /// loading formats other than `R32_FLOAT`, `R32_UINT`, and `R32_SINT`.
+    ///
+    /// `resource` must have been created with `D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS`.
+    pub fn create_typed_buffer_uav(
+        &self,
+        resource: &ID3D12Resource,
+        format: DXGI_FORMAT,
+        first_element: u64,
+        count: u32,
+        descriptor: D3D12_CPU_DESCRIPTOR_HANDLE,
+    ) {
+        assert!(
+            format != DXGI_FORMAT_UNKNOWN,
+            "BevyDirectX: Typed buffer views need a format, use a structured buffer view instead"
+        );
+        let desc = buffer_uav_desc(
+            format,
+            D3D12_BUFFER_UAV {
+                FirstElement: first_element,
+                NumElements: count,
+                StructureByteStride: 0,
+                CounterOffsetInBytes: 0,
+                Flags: D3D12_BUFFER_UAV_FLAG_NONE,
+            },
+        );
+        unsafe {
+            self.device
+                .CreateUnorderedAccessView(resource, None, Some(&desc), descriptor)
+        };
+    }
+}
+
+/// Raw views address the buffer in 32-bit elements.
+fn raw_elements(offset: u64, size: u64) -> (u64, u32) {
+    let alignment = D3D12_RAW_UAV_SRV_BYTE_ALIGNMENT as u64;
+    assert!(
+        offset % alignment == 0 && size % alignment == 0,
+        "BevyDirectX: Raw buffer view offset and size must be multiples of {alignment}, were {offset} and {size}"
+    );
+    (offset / 4, (size / 4) as u32)
+}
+
+fn buffer_srv_desc(
+    format: DXGI_FORMAT,
+    buffer: D3D12_BUFFER_SRV,
+) -> D3D12_SHADER_RESOURCE_VIEW_DESC {
+    D3D12_SHADER_RESOURCE_VIEW_DESC {
+        Format: format,
+        ViewDimension: D3D12_SRV_DIMENSION_BUFFER,
+        Shader4ComponentMapping: D3D12_DEFAULT_SHADER_4_COMPONENT_MAPPING,
+        Anonymous: D3D12_SHADER_RESOURCE_VIEW_DESC_0 { Buffer: buffer },
+    }
+}
+
+fn buffer_uav_desc(
+    format: DXGI_FORMAT,
+    buffer: D3D12_BUFFER_UAV,
+) -> D3D12_UNORDERED_ACCESS_VIEW_DESC {
+    D3D12_UNORDERED_ACCESS_VIEW_DESC {
+        Format: format,
+        ViewDimension: D3D12_UAV_DIMENSION_BUFFER,
+        Anonymous: D3D12_UNORDERED_ACCESS_VIEW_DESC_0 { Buffer: buffer },
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{error::DxError, gpu::Gpu};
+use windows::Win32::Graphics::Direct3D12::*;
+
+/// A bundle command list, recorded once and replayed any number of times within direct command lists with
+/// [`Bundle::execute`], to save the CPU cost of re-recording repeated draw sequences.
+///
+/// Bundles can only record a subset of commands. They can't record resource barriers, clears, copies, queries,
+/// `OMSetRenderTargets`, `RSSetViewports`, `RSSetScissorRects`, or execute other bundles. `SetDescriptorHeaps` may
+/// only be called with the same heaps as the executing command list.
+///
+/// When executed, a bundle inherits the executing command list's render targets, viewports, scissor rects, descriptor
+/// heaps, blend factor, and stencil ref. It also inherits root arguments, but only if it sets the same root signature
+/// as the executing command list has bound. It doesn't inherit the pipeline state, which starts as the one given to
+/// [`Gpu::create_bundle`], or the primitive topology, which starts undefined. Pipeline, root signature, and root
+/// argument changes made by the bundle persist in the executing command list afterwards.
+pub struct Bundle {
+    command_list: ID3D12GraphicsCommandList7,
+    // Backs the recorded commands, so must live as long as the command list
+    _command_allocator: ID3D12CommandAllocator,
+}
+
+impl Gpu {
+    /// Create a [`Bundle`], open for recording with `pipeline` as its initial pipeline state.
+    pub fn create_bundle(&self, pipeline: Option<&ID3D12PipelineState>) -> Result<Bundle, DxError> {
+        unsafe {
+            let command_allocator = self
+                .device
+                .CreateCommandAllocator(D3D12_COMMAND_LIST_TYPE_BUNDLE)?;
+            let command_list = self.device.CreateCommandList(
+                0,
+                D3D12_COMMAND_LIST_TYPE_BUNDLE,
+                &command_allocator,
+                pipeline,
+            )?;
+
+            Ok(Bundle {
+                command_list,
+                _command_allocator: command_allocator,
+            })
+        }
+    }
+}
+
+impl Bundle {
+    /// The bundle's command list, to record commands into before [`Bundle::close`].
+    pub fn command_list(&self) -> &ID3D12GraphicsCommandList7 {
+        &self.command_list
+    }
+
+    /// Finish recording. The bundle can't be recorded into again, and must be closed before being executed.
+    pub fn close(&self) -> Result<(), DxError> {
+        unsafe { self.command_list.Close()? };
+        Ok(())
+    }
+
+    /// Replay the bundle's commands within `command_list`, which must be a direct command list.
+    pub fn execute(&self, command_list: &ID3D12GraphicsCommandList7) {
+        unsafe { command_list.ExecuteBundle(&self.command_list) };
+    }
+}
@@ -0,0 +1,101 @@
+use std::{ffi::c_void, mem};
+use windows::Win32::Graphics::Direct3D12::*;
+
+/// Optional features supported by the GPU and driver, queried once when the [`crate::Gpu`] is created.
+///
+/// See [`crate::Gpu::capabilities`].
+#[derive(Clone, Copy, Debug)]
+pub struct GpuCapabilities {
+    /// Highest HLSL shader model supported.
+    pub shader_model: D3D_SHADER_MODEL,
+    pub resource_binding_tier: D3D12_RESOURCE_BINDING_TIER,
+    pub resource_heap_tier: D3D12_RESOURCE_HEAP_TIER,
+    pub raytracing_tier: D3D12_RAYTRACING_TIER,
+    pub mesh_shader_tier: D3D12_MESH_SHADER_TIER,
+    pub variable_shading_rate_tier: D3D12_VARIABLE_SHADING_RATE_TIER,
+    pub sampler_feedback_tier: D3D12_SAMPLER_FEEDBACK_TIER,
+    /// Whether `ID3D12GraphicsCommandList7::Barrier` and texture layouts can be used instead of resource states.
+    pub enhanced_barriers: bool,
+    /// Whether HLSL wave intrinsics (`WaveActiveSum()`, etc) are supported.
+    pub wave_ops: bool,
+    pub wave_lane_count_min: u32,
+    pub wave_lane_count_max: u32,
+    /// Whether typed UAV loads are supported for formats beyond R32_FLOAT, R32_UINT, and R32_SINT.
+    pub typed_uav_load_additional_formats: bool,
+}
+
+impl GpuCapabilities {
+    pub(crate) fn new(device: &ID3D12Device9) -> Self {
+        let options: D3D12_FEATURE_DATA_D3D12_OPTIONS =
+            check_feature_support(device, D3D12_FEATURE_D3D12_OPTIONS);
+        let options1: D3D12_FEATURE_DATA_D3D12_OPTIONS1 =
+            check_feature_support(device, D3D12_FEATURE_D3D12_OPTIONS1);
+        let options5: D3D12_FEATURE_DATA_D3D12_OPTIONS5 =
+            check_feature_support(device, D3D12_FEATURE_D3D12_OPTIONS5);
+        let options6: D3D12_FEATURE_DATA_D3D12_OPTIONS6 =
+            check_feature_support(device, D3D12_FEATURE_D3D12_OPTIONS6);
+        let options7: D3D12_FEATURE_DATA_D3D12_OPTIONS7 =
+            check_feature_support(device, D3D12_FEATURE_D3D12_OPTIONS7);
+        let options12: D3D12_FEATURE_DATA_D3D12_OPTIONS12 =
+            check_feature_support(device, D3D12_FEATURE_D3D12_OPTIONS12);
+
+        Self {
+            shader_model: highest_shader_model(device),
+            resource_binding_tier: options.ResourceBindingTier,
+            resource_heap_tier: options.ResourceHeapTier,
+            raytracing_tier: options5.RaytracingTier,
+            mesh_shader_tier: options7.MeshShaderTier,
+            variable_shading_rate_tier: options6.VariableShadingRateTier,
+            sampler_feedback_tier: options7.SamplerFeedbackTier,
+            enhanced_barriers: options12.EnhancedBarriersSupported.as_bool(),
+            wave_ops: options1.WaveOps.as_bool(),
+            wave_lane_count_min: options1.WaveLaneCountMin,
+            wave_lane_count_max: options1.WaveLaneCountMax,
+            typed_uav_load_additional_formats: options.TypedUAVLoadAdditionalFormats.as_bool(),
+        }
+    }
+}
+
+/// Query a `D3D12_FEATURE_DATA_*` struct, leaving it defaulted (unsupported) if the runtime doesn't know the feature.
+fn check_feature_support<T: Default>(device: &ID3D12Device9, feature: D3D12_FEATURE) -> T {
+    let mut data = T::default();
+    let _ = unsafe {
+        device.CheckFeatureSupport(
+            feature,
+            &mut data as *mut T as *mut c_void,
+            mem::size_of::<T>() as u32,
+        )
+    };
+    data
+}
+
+fn highest_shader_model(device: &ID3D12Device9) -> D3D_SHADER_MODEL {
+    // Older runtimes reject shader models they don't know about, so try each from newest to oldest
+    [
+        D3D_SHADER_MODEL_6_8,
+        D3D_SHADER_MODEL_6_7,
+        D3D_SHADER_MODEL_6_6,
+        D3D_SHADER_MODEL_6_5,
+        D3D_SHADER_MODEL_6_4,
+        D3D_SHADER_MODEL_6_3,
+        D3D_SHADER_MODEL_6_2,
+        D3D_SHADER_MODEL_6_1,
+        D3D_SHADER_MODEL_6_0,
+    ]
+    .into_iter()
+    .find_map(|shader_model| {
+        let mut data = D3D12_FEATURE_DATA_SHADER_MODEL {
+            HighestShaderModel: shader_model,
+        };
+        unsafe {
+            device.CheckFeatureSupport(
+                D3D12_FEATURE_SHADER_MODEL,
+                &mut data as *mut _ as *mut c_void,
+                mem::size_of_val(&data) as u32,
+            )
+        }
+        .ok()
+        .map(|()| data.HighestShaderModel)
+    })
+    .unwrap_or(D3D_SHADER_MODEL_5_1)
+}
@@ -0,0 +1,139 @@
+use crate::{dynamic_descriptors::DynamicDescriptorRing, error::DxError, gpu::Gpu};
+use windows::Win32::Graphics::{
+    Direct3D12::*,
+    Dxgi::Common::{DXGI_FORMAT_R32_FLOAT, DXGI_FORMAT_R32_TYPELESS},
+};
+
+/// Number of UAV clears that can be in flight on the GPU at once.
+const CLEAR_DESCRIPTOR_COUNT: u32 = 256;
+
+/// Descriptors for [`Gpu::clear_uav_uint`] and [`Gpu::clear_uav_float`], which need the same UAV in both a
+/// non-shader-visible heap and the bound shader-visible heap.
+pub(crate) struct UavClearDescriptors {
+    /// Non-shader-visible, only read while recording the clear, so one descriptor is reused for every clear.
+    cpu_heap: ID3D12DescriptorHeap,
+    gpu_descriptors: DynamicDescriptorRing,
+}
+
+impl UavClearDescriptors {
+    fn new(gpu: &Gpu) -> Result<Self, DxError> {
+        Ok(Self {
+            cpu_heap: gpu.create_descriptor_heap(
+                D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV,
+                1,
+                false,
+            )?,
+            gpu_descriptors: DynamicDescriptorRing::new(gpu, CLEAR_DESCRIPTOR_COUNT)?,
+        })
+    }
+}
+
+impl Gpu {
+    /// Clear every element of a UAV to `value`, each component converted to the resource's format. Buffers are
+    /// cleared as 32-bit elements, using only `value[0]`. For textures, only mip 0 is cleared.
+    ///
+    /// `resource` must have been created with `D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS`, and be in the
+    /// UNORDERED_ACCESS state. Binds an internal descriptor heap, so rebind any descriptor heaps afterwards.
+    pub fn clear_uav_uint(
+        &self,
+        command_list: &ID3D12GraphicsCommandList7,
+        resource: &ID3D12Resource,
+        value: [u32; 4],
+    ) -> Result<(), DxError> {
+        self.clear_uav(
+            command_list,
+            resource,
+            false,
+            |gpu_handle, cpu_handle| unsafe {
+                command_list.ClearUnorderedAccessViewUint(
+                    gpu_handle,
+                    cpu_handle,
+                    resource,
+                    &value,
+                    &[],
+                )
+            },
+        )
+    }
+
+    /// Clear every element of a UAV to `value`, see [`Gpu::clear_uav_uint`]. Buffers are cleared as `R32_FLOAT`
+    /// elements, using only `value[0]`. The resource's format must be a float, UNORM, or SNORM format.
+    pub fn clear_uav_float(
+        &self,
+        command_list: &ID3D12GraphicsCommandList7,
+        resource: &ID3D12Resource,
+        value: [f32; 4],
+    ) -> Result<(), DxError> {
+        self.clear_uav(
+            command_list,
+            resource,
+            true,
+            |gpu_handle, cpu_handle| unsafe {
+                command_list.ClearUnorderedAccessViewFloat(
+                    gpu_handle,
+                    cpu_handle,
+                    resource,
+                    &value,
+                    &[],
+                )
+            },
+        )
+    }
+
+    fn clear_uav(
+        &self,
+        command_list: &ID3D12GraphicsCommandList7,
+        resource: &ID3D12Resource,
+        float: bool,
+        clear: impl FnOnce(D3D12_GPU_DESCRIPTOR_HANDLE, D3D12_CPU_DESCRIPTOR_HANDLE),
+    ) -> Result<(), DxError> {
+        let mut descriptors = self.uav_clear_descriptors.lock().unwrap();
+        if descriptors.is_none() {
+            *descriptors = Some(UavClearDescriptors::new(self)?);
+        }
+        let descriptors = descriptors.as_mut().unwrap();
+
+        // Buffers have no format, so need an explicit view, while textures can use their own format
+        let resource_desc = unsafe { resource.GetDesc() };
+        let uav_desc = (resource_desc.Dimension == D3D12_RESOURCE_DIMENSION_BUFFER).then(|| {
+            D3D12_UNORDERED_ACCESS_VIEW_DESC {
+                Format: if float {
+                    DXGI_FORMAT_R32_FLOAT
+                } else {
+                    DXGI_FORMAT_R32_TYPELESS
+                },
+                ViewDimension: D3D12_UAV_DIMENSION_BUFFER,
+                Anonymous: D3D12_UNORDERED_ACCESS_VIEW_DESC_0 {
+                    Buffer: D3D12_BUFFER_UAV {
+                        NumElements: (resource_desc.Width / 4) as u32,
+                        Flags: if float {
+                            D3D12_BUFFER_UAV_FLAG_NONE
+                        } else {
+                            D3D12_BUFFER_UAV_FLAG_RAW
+                        },
+                        ..Default::default()
+                    },
+                },
+            }
+        });
+
+        let cpu_handle = unsafe { descriptors.cpu_heap.GetCPUDescriptorHandleForHeapStart() };
+        unsafe {
+            self.device.CreateUnorderedAccessView(
+                resource,
+                None,
+                uav_desc.as_ref().map(|desc| desc as *const _),
+                cpu_handle,
+            )
+        };
+        let gpu_handle = descriptors
+            .gpu_descriptors
+            .copy_descriptors(self, &[cpu_handle])?;
+
+        unsafe {
+            command_list.SetDescriptorHeaps(&[Some(descriptors.gpu_descriptors.heap().clone())])
+        };
+        clear(gpu_handle, cpu_handle);
+        Ok(())
+    }
+}
@@ -0,0 +1,264 @@
+use crate::{
+    error::DxError,
+    gpu::Gpu,
+    resource_tracker::{transition_barrier, uav_barrier},
+    shader::compile_shader,
+};
+use bevy::math::{Mat4, Vec4};
+use std::mem::{self, transmute_copy};
+use windows::Win32::Graphics::Direct3D12::*;
+
+/// Bounding sphere of an instance, in world space, as read by [`GpuCulling::cull`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C)]
+pub struct CullingInstance {
+    pub center: [f32; 3],
+    pub radius: f32,
+}
+
+/// Which part of the index buffer [`GpuCulling::draw`] draws for each visible instance.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CullingDrawRange {
+    pub index_count_per_instance: u32,
+    pub start_index_location: u32,
+    pub base_vertex_location: i32,
+}
+
+/// Compute pass that frustum culls instances on the GPU, for GPU-driven rendering. This is a reference implementation,
+/// meant to be copied and customized, e.g. with occlusion culling against a depth pyramid.
+///
+/// [`GpuCulling::cull`] tests each instance's [`CullingInstance`] bounding sphere against the camera frustum, and
+/// writes the indices of visible instances, compacted, to [`GpuCulling::visible_instances`], and a
+/// [`D3D12_DRAW_INDEXED_ARGUMENTS`] with their count to [`GpuCulling::draw_arguments`]. [`GpuCulling::draw`] then
+/// draws them with `ExecuteIndirect`, without the CPU reading the count back. The order of visible instances is
+/// nondeterministic.
+///
+/// The vertex shader must look up the real instance index with `SV_InstanceID`, e.g. with a
+/// `StructuredBuffer<uint>` SRV of [`GpuCulling::visible_instances`]:
+/// `uint instanceIndex = visibleInstances[instanceId];`.
+///
+/// Between passes, the visible instance buffer rests in `D3D12_RESOURCE_STATE_ALL_SHADER_RESOURCE`, and the draw
+/// arguments buffer in `D3D12_RESOURCE_STATE_INDIRECT_ARGUMENT`.
+pub struct GpuCulling {
+    root_signature: ID3D12RootSignature,
+    reset_pipeline: ID3D12PipelineState,
+    cull_pipeline: ID3D12PipelineState,
+    draw_signature: ID3D12CommandSignature,
+    visible_instances: ID3D12Resource,
+    draw_arguments: ID3D12Resource,
+    max_instances: u32,
+}
+
+impl GpuCulling {
+    /// Number of 32-bit root constants: 6 frustum planes, the instance count, and a [`CullingDrawRange`].
+    const ROOT_CONSTANT_COUNT: u32 = 6 * 4 + 4;
+
+    /// Create a culling pass for up to `max_instances` instances.
+    pub fn new(gpu: &Gpu, max_instances: u32) -> Result<Self, DxError> {
+        let source = include_str!("../assets/culling.hlsl");
+        let reset_shader = compile_shader(source, "ResetMain", "cs_5_1")?;
+        let cull_shader = compile_shader(source, "CullMain", "cs_5_1")?;
+
+        // Constants at b0, instances at t0, visible instances at u0, and draw arguments at u1
+        let root_descriptor = |parameter_type, shader_register| D3D12_ROOT_PARAMETER1 {
+            ParameterType: parameter_type,
+            Anonymous: D3D12_ROOT_PARAMETER1_0 {
+                Descriptor: D3D12_ROOT_DESCRIPTOR1 {
+                    ShaderRegister: shader_register,
+                    ..Default::default()
+                },
+            },
+            ShaderVisibility: D3D12_SHADER_VISIBILITY_ALL,
+        };
+        let root_signature = gpu.create_root_signature(
+            &[
+                D3D12_ROOT_PARAMETER1 {
+                    ParameterType: D3D12_ROOT_PARAMETER_TYPE_32BIT_CONSTANTS,
+                    Anonymous: D3D12_ROOT_PARAMETER1_0 {
+                        Constants: D3D12_ROOT_CONSTANTS {
+                            Num32BitValues: Self::ROOT_CONSTANT_COUNT,
+                            ..Default::default()
+                        },
+                    },
+                    ShaderVisibility: D3D12_SHADER_VISIBILITY_ALL,
+                },
+                root_descriptor(D3D12_ROOT_PARAMETER_TYPE_SRV, 0),
+                root_descriptor(D3D12_ROOT_PARAMETER_TYPE_UAV, 0),
+                root_descriptor(D3D12_ROOT_PARAMETER_TYPE_UAV, 1),
+            ],
+            &[],
+            D3D12_ROOT_SIGNATURE_FLAG_NONE,
+        )?;
+        let create_pipeline = |shader: &[u8]| {
+            gpu.pipeline_cache().create_compute_pipeline(
+                &gpu.device,
+                &D3D12_COMPUTE_PIPELINE_STATE_DESC {
+                    pRootSignature: unsafe { transmute_copy(&root_signature) },
+                    CS: D3D12_SHADER_BYTECODE {
+                        pShaderBytecode: shader.as_ptr() as _,
+                        BytecodeLength: shader.len(),
+                    },
+                    ..Default::default()
+                },
+            )
+        };
+        let reset_pipeline = create_pipeline(&reset_shader)?;
+        let cull_pipeline = create_pipeline(&cull_shader)?;
+
+        let visible_instances = gpu.create_buffer(
+            max_instances.max(1) as u64 * mem::size_of::<u32>() as u64,
+            D3D12_HEAP_TYPE_DEFAULT,
+            D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS,
+            D3D12_RESOURCE_STATE_ALL_SHADER_RESOURCE,
+        )?;
+        let draw_arguments = gpu.create_buffer(
+            mem::size_of::<D3D12_DRAW_INDEXED_ARGUMENTS>() as u64,
+            D3D12_HEAP_TYPE_DEFAULT,
+            D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS,
+            D3D12_RESOURCE_STATE_INDIRECT_ARGUMENT,
+        )?;
+
+        Ok(Self {
+            root_signature,
+            reset_pipeline,
+            cull_pipeline,
+            draw_signature: gpu.create_draw_indexed_indirect_signature()?,
+            visible_instances,
+            draw_arguments,
+            max_instances,
+        })
+    }
+
+    /// Cull the first `instance_count` [`CullingInstance`]s of `instances` against `frustum_planes` (see
+    /// [`frustum_planes`]), writing the visible ones and draw arguments for `draw_range`.
+    ///
+    /// `instances` must be in a non-pixel shader resource state, or be an upload heap buffer. Changes the command
+    /// list's pipeline and compute root signature.
+    pub fn cull(
+        &self,
+        command_list: &ID3D12GraphicsCommandList7,
+        instances: &ID3D12Resource,
+        instance_count: u32,
+        frustum_planes: [Vec4; 6],
+        draw_range: CullingDrawRange,
+    ) {
+        assert!(
+            instance_count <= self.max_instances,
+            "BevyDirectX: GpuCulling::cull() instance count {instance_count} exceeds the maximum of {}",
+            self.max_instances
+        );
+
+        let mut constants = [0u32; Self::ROOT_CONSTANT_COUNT as usize];
+        for (constant, value) in constants
+            .iter_mut()
+            .zip(frustum_planes.iter().flat_map(|plane| plane.to_array()))
+        {
+            *constant = value.to_bits();
+        }
+        constants[24..].copy_from_slice(&[
+            instance_count,
+            draw_range.index_count_per_instance,
+            draw_range.start_index_location,
+            draw_range.base_vertex_location as u32,
+        ]);
+
+        unsafe {
+            command_list.ResourceBarrier(&[
+                transition_barrier(
+                    &self.visible_instances,
+                    D3D12_RESOURCE_STATE_ALL_SHADER_RESOURCE,
+                    D3D12_RESOURCE_STATE_UNORDERED_ACCESS,
+                ),
+                transition_barrier(
+                    &self.draw_arguments,
+                    D3D12_RESOURCE_STATE_INDIRECT_ARGUMENT,
+                    D3D12_RESOURCE_STATE_UNORDERED_ACCESS,
+                ),
+            ]);
+
+            command_list.SetComputeRootSignature(&self.root_signature);
+            command_list.SetComputeRoot32BitConstants(
+                0,
+                constants.len() as u32,
+                constants.as_ptr() as _,
+                0,
+            );
+            command_list.SetComputeRootShaderResourceView(1, instances.GetGPUVirtualAddress());
+            command_list.SetComputeRootUnorderedAccessView(
+                2,
+                self.visible_instances.GetGPUVirtualAddress(),
+            );
+            command_list
+                .SetComputeRootUnorderedAccessView(3, self.draw_arguments.GetGPUVirtualAddress());
+
+            // Reset the instance count before culling appends to it
+            command_list.SetPipelineState(&self.reset_pipeline);
+            command_list.Dispatch(1, 1, 1);
+            command_list.ResourceBarrier(&[uav_barrier(Some(&self.draw_arguments))]);
+
+            command_list.SetPipelineState(&self.cull_pipeline);
+            command_list.Dispatch(instance_count.div_ceil(64), 1, 1);
+
+            command_list.ResourceBarrier(&[
+                transition_barrier(
+                    &self.visible_instances,
+                    D3D12_RESOURCE_STATE_UNORDERED_ACCESS,
+                    D3D12_RESOURCE_STATE_ALL_SHADER_RESOURCE,
+                ),
+                transition_barrier(
+                    &self.draw_arguments,
+                    D3D12_RESOURCE_STATE_UNORDERED_ACCESS,
+                    D3D12_RESOURCE_STATE_INDIRECT_ARGUMENT,
+                ),
+            ]);
+        }
+    }
+
+    /// Draw the visible instances from the last [`GpuCulling::cull`] with `ExecuteIndirect`.
+    ///
+    /// The graphics pipeline, root signature, index and vertex buffers, and render targets must already be bound.
+    pub fn draw(&self, command_list: &ID3D12GraphicsCommandList7) {
+        unsafe {
+            command_list.ExecuteIndirect(&self.draw_signature, 1, &self.draw_arguments, 0, None, 0)
+        };
+    }
+
+    /// Buffer of `u32` indices of visible instances, compacted to the start of the buffer.
+    pub fn visible_instances(&self) -> &ID3D12Resource {
+        &self.visible_instances
+    }
+
+    /// Buffer holding a single [`D3D12_DRAW_INDEXED_ARGUMENTS`], whose `InstanceCount` is the number of visible
+    /// instances.
+    pub fn draw_arguments(&self) -> &ID3D12Resource {
+        &self.draw_arguments
+    }
+
+    pub fn max_instances(&self) -> u32 {
+        self.max_instances
+    }
+}
+
+/// Planes of the frustum of `view_projection`, pointing inwards and normalized, for [`GpuCulling::cull`].
+///
+/// A point `p` is inside the frustum if `plane.xyz.dot(p) + plane.w >= 0` for every plane. Works for both standard
+/// and reverse-Z projections, with the far plane of an infinite projection always passing.
+pub fn frustum_planes(view_projection: Mat4) -> [Vec4; 6] {
+    let rows = [0, 1, 2, 3].map(|i| view_projection.row(i));
+    [
+        rows[3] + rows[0],
+        rows[3] - rows[0],
+        rows[3] + rows[1],
+        rows[3] - rows[1],
+        rows[2],
+        rows[3] - rows[2],
+    ]
+    .map(|plane| {
+        let length = plane.truncate().length();
+        if length > 0.0 {
+            plane / length
+        } else {
+            plane
+        }
+    })
+}
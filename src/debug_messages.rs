@@ -0,0 +1,87 @@
+use crate::gpu::{message_category_name, Gpu};
+use std::{fmt, slice};
+use windows::{core::Interface, Win32::Graphics::Direct3D12::*};
+
+/// A message reported by the D3D12 debug layer, see [`Gpu::drain_debug_messages`].
+#[derive(Clone, Debug)]
+pub struct DebugMessage {
+    pub category: D3D12_MESSAGE_CATEGORY,
+    pub severity: D3D12_MESSAGE_SEVERITY,
+    pub id: D3D12_MESSAGE_ID,
+    pub description: String,
+}
+
+impl DebugMessage {
+    /// Whether the message is an ERROR or CORRUPTION, i.e. the app used the API incorrectly.
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self.severity,
+            D3D12_MESSAGE_SEVERITY_ERROR | D3D12_MESSAGE_SEVERITY_CORRUPTION
+        )
+    }
+}
+
+impl fmt::Display for DebugMessage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            D3D12_MESSAGE_SEVERITY_CORRUPTION => "Corruption",
+            D3D12_MESSAGE_SEVERITY_ERROR => "Error",
+            D3D12_MESSAGE_SEVERITY_WARNING => "Warning",
+            D3D12_MESSAGE_SEVERITY_INFO => "Info",
+            _ => "Message",
+        };
+        let category = message_category_name(self.category);
+        write!(
+            f,
+            "D3D12 {severity} {category} ({}): {}",
+            self.id.0, self.description
+        )
+    }
+}
+
+impl Gpu {
+    /// Take every debug layer message stored since the last call, oldest first, e.g. to fail a test if any
+    /// [`DebugMessage::is_error`] after rendering a frame. Messages are still logged as they happen.
+    ///
+    /// Always returns nothing unless [`crate::GpuConfig::debug_layer`] is enabled, as it is in debug builds. The info
+    /// queue only stores a limited number of messages, see `ID3D12InfoQueue::SetMessageCountLimit`, so call this at
+    /// least once per frame for chatty workloads. Messages about work executed on the GPU, such as from GPU-based
+    /// validation, are only reported once that work completes, e.g. after [`Gpu::wait_for_fence`].
+    pub fn drain_debug_messages(&self) -> Vec<DebugMessage> {
+        let Ok(info_queue) = self.device.cast::<ID3D12InfoQueue>() else {
+            return Vec::new();
+        };
+
+        unsafe {
+            let count = info_queue.GetNumStoredMessages();
+            let mut messages = Vec::with_capacity(count as usize);
+            let mut buffer: Vec<u64> = Vec::new();
+            for i in 0..count {
+                let mut size = 0;
+                if info_queue.GetMessage(i, None, &mut size).is_err() {
+                    continue;
+                }
+                // D3D12_MESSAGE is followed by its description, and needs 8 byte alignment
+                buffer.resize(size.div_ceil(8), 0);
+                let message = buffer.as_mut_ptr() as *mut D3D12_MESSAGE;
+                if info_queue.GetMessage(i, Some(message), &mut size).is_err() {
+                    continue;
+                }
+
+                let message = &*message;
+                // The length includes the null terminator
+                let description =
+                    slice::from_raw_parts(message.pDescription, message.DescriptionByteLength);
+                let description = description.strip_suffix(&[0]).unwrap_or(description);
+                messages.push(DebugMessage {
+                    category: message.Category,
+                    severity: message.Severity,
+                    id: message.ID,
+                    description: String::from_utf8_lossy(description).into_owned(),
+                });
+            }
+            info_queue.ClearStoredMessages();
+            messages
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use crate::{
+    error::DxError,
+    gpu::Gpu,
+    offscreen::{full_scissor_rect, full_viewport},
+    resource_tracker::transition_barrier,
+};
+use bevy::math::UVec2;
+use std::mem::transmute_copy;
+use windows::Win32::{
+    Foundation::RECT,
+    Graphics::{Direct3D12::*, Dxgi::Common::*},
+};
+
+/// A depth texture to render to without any color targets, and then sample from in a later pass, e.g. a shadow map.
+///
+/// The texture is created with the typeless `DXGI_FORMAT_R32_TYPELESS` format, so that it can be viewed both as
+/// [`DepthTarget::DSV_FORMAT`] for depth testing, and as [`DepthTarget::SRV_FORMAT`] for sampling. The texture rests
+/// in the PIXEL_SHADER_RESOURCE state, and is transitioned to DEPTH_WRITE between [`DepthTarget::begin_render`] and
+/// [`DepthTarget::end_render`].
+pub struct DepthTarget {
+    texture: ID3D12Resource,
+    dsv_heap: ID3D12DescriptorHeap,
+    srv_heap: ID3D12DescriptorHeap,
+    size: UVec2,
+    /// State the texture is in outside of [`DepthTarget::begin_render`] and [`DepthTarget::end_render`].
+    resting_state: D3D12_RESOURCE_STATES,
+}
+
+impl DepthTarget {
+    /// Format to use for `DSVFormat` when creating pipelines that render to the texture.
+    pub const DSV_FORMAT: DXGI_FORMAT = DXGI_FORMAT_D32_FLOAT;
+    /// Format of [`DepthTarget::srv`]. Shaders see the depth as a `Texture2D<float>`.
+    pub const SRV_FORMAT: DXGI_FORMAT = DXGI_FORMAT_R32_FLOAT;
+
+    pub fn new(gpu: &Gpu, size: UVec2) -> Result<Self, DxError> {
+        Self::with_state(gpu, size, D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE)
+    }
+
+    /// [`DepthTarget::new`], with the texture resting in `resting_state` instead.
+    pub(crate) fn with_state(
+        gpu: &Gpu,
+        size: UVec2,
+        resting_state: D3D12_RESOURCE_STATES,
+    ) -> Result<Self, DxError> {
+        let texture = gpu.create_texture_2d(
+            size.x,
+            size.y,
+            DXGI_FORMAT_R32_TYPELESS,
+            D3D12_RESOURCE_FLAG_ALLOW_DEPTH_STENCIL,
+            resting_state,
+            Some(&D3D12_CLEAR_VALUE {
+                Format: Self::DSV_FORMAT,
+                Anonymous: D3D12_CLEAR_VALUE_0 {
+                    DepthStencil: D3D12_DEPTH_STENCIL_VALUE {
+                        Depth: 1.0,
+                        Stencil: 0,
+                    },
+                },
+            }),
+        )?;
+        let dsv_heap = gpu.create_descriptor_heap(D3D12_DESCRIPTOR_HEAP_TYPE_DSV, 1, false)?;
+        let srv_heap =
+            gpu.create_descriptor_heap(D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV, 1, true)?;
+        unsafe {
+            gpu.device.CreateDepthStencilView(
+                &texture,
+                Some(&D3D12_DEPTH_STENCIL_VIEW_DESC {
+                    Format: Self::DSV_FORMAT,
+                    ViewDimension: D3D12_DSV_DIMENSION_TEXTURE2D,
+                    Flags: D3D12_DSV_FLAG_NONE,
+                    Anonymous: D3D12_DEPTH_STENCIL_VIEW_DESC_0 {
+                        Texture2D: D3D12_TEX2D_DSV { MipSlice: 0 },
+                    },
+                }),
+                dsv_heap.GetCPUDescriptorHandleForHeapStart(),
+            );
+        }
+
+        let depth_target = Self {
+            texture,
+            dsv_heap,
+            srv_heap,
+            size,
+            resting_state,
+        };
+        depth_target.create_srv(gpu, unsafe {
+            depth_target.srv_heap.GetCPUDescriptorHandleForHeapStart()
+        });
+        Ok(depth_target)
+    }
+
+    /// Write an SRV of the texture, with [`DepthTarget::SRV_FORMAT`], to `handle`, e.g. to put it in the same
+    /// descriptor table as other resources.
+    pub fn create_srv(&self, gpu: &Gpu, handle: D3D12_CPU_DESCRIPTOR_HANDLE) {
+        unsafe {
+            gpu.device.CreateShaderResourceView(
+                &self.texture,
+                Some(&D3D12_SHADER_RESOURCE_VIEW_DESC {
+                    Format: Self::SRV_FORMAT,
+                    ViewDimension: D3D12_SRV_DIMENSION_TEXTURE2D,
+                    Shader4ComponentMapping: D3D12_DEFAULT_SHADER_4_COMPONENT_MAPPING,
+                    Anonymous: D3D12_SHADER_RESOURCE_VIEW_DESC_0 {
+                        Texture2D: D3D12_TEX2D_SRV {
+                            MipLevels: 1,
+                            ..Default::default()
+                        },
+                    },
+                }),
+                handle,
+            );
+        }
+    }
+
+    /// Graphics pipeline settings for rendering only to a depth target, with no pixel shader or render targets, and
+    /// depth testing with `D3D12_COMPARISON_FUNC_LESS`.
+    ///
+    /// The returned desc points to `root_signature` and `vertex_shader`, which must outlive it. Adjust
+    /// `RasterizerState.DepthBias` and `SlopeScaledDepthBias` to reduce shadow acne.
+    pub fn pipeline_desc(
+        root_signature: &ID3D12RootSignature,
+        vertex_shader: &[u8],
+    ) -> D3D12_GRAPHICS_PIPELINE_STATE_DESC {
+        D3D12_GRAPHICS_PIPELINE_STATE_DESC {
+            pRootSignature: unsafe { transmute_copy(root_signature) },
+            VS: D3D12_SHADER_BYTECODE {
+                pShaderBytecode: vertex_shader.as_ptr() as _,
+                BytecodeLength: vertex_shader.len(),
+            },
+            SampleMask: u32::MAX,
+            RasterizerState: D3D12_RASTERIZER_DESC {
+                FillMode: D3D12_FILL_MODE_SOLID,
+                CullMode: D3D12_CULL_MODE_NONE,
+                DepthClipEnable: true.into(),
+                ..Default::default()
+            },
+            DepthStencilState: D3D12_DEPTH_STENCIL_DESC {
+                DepthEnable: true.into(),
+                DepthWriteMask: D3D12_DEPTH_WRITE_MASK_ALL,
+                DepthFunc: D3D12_COMPARISON_FUNC_LESS,
+                ..Default::default()
+            },
+            PrimitiveTopologyType: D3D12_PRIMITIVE_TOPOLOGY_TYPE_TRIANGLE,
+            NumRenderTargets: 0,
+            DSVFormat: Self::DSV_FORMAT,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            ..Default::default()
+        }
+    }
+
+    pub fn texture(&self) -> &ID3D12Resource {
+        &self.texture
+    }
+
+    pub fn dsv(&self) -> D3D12_CPU_DESCRIPTOR_HANDLE {
+        unsafe { self.dsv_heap.GetCPUDescriptorHandleForHeapStart() }
+    }
+
+    /// Shader-visible descriptor heap containing only [`DepthTarget::srv`].
+    pub fn srv_heap(&self) -> &ID3D12DescriptorHeap {
+        &self.srv_heap
+    }
+
+    pub fn srv(&self) -> D3D12_GPU_DESCRIPTOR_HANDLE {
+        unsafe { self.srv_heap.GetGPUDescriptorHandleForHeapStart() }
+    }
+
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// Viewport covering the whole texture.
+    pub fn viewport(&self) -> D3D12_VIEWPORT {
+        full_viewport(self.size)
+    }
+
+    /// Scissor rect covering the whole texture.
+    pub fn scissor_rect(&self) -> RECT {
+        full_scissor_rect(self.size)
+    }
+
+    /// Transition the texture from PIXEL_SHADER_RESOURCE to DEPTH_WRITE, bind it as the depth target with no render
+    /// targets, and clear it to 1.0.
+    pub fn begin_render(&self, command_list: &ID3D12GraphicsCommandList7) {
+        let dsv = self.dsv();
+        unsafe {
+            command_list.ResourceBarrier(&[transition_barrier(
+                &self.texture,
+                self.resting_state,
+                D3D12_RESOURCE_STATE_DEPTH_WRITE,
+            )]);
+            command_list.OMSetRenderTargets(0, None, false, Some(&dsv));
+            command_list.ClearDepthStencilView(dsv, D3D12_CLEAR_FLAG_DEPTH, 1.0, 0, &[]);
+        }
+    }
+
+    /// Transition the texture from DEPTH_WRITE back to PIXEL_SHADER_RESOURCE, so that it can be sampled.
+    pub fn end_render(&self, command_list: &ID3D12GraphicsCommandList7) {
+        unsafe {
+            command_list.ResourceBarrier(&[transition_barrier(
+                &self.texture,
+                D3D12_RESOURCE_STATE_DEPTH_WRITE,
+                self.resting_state,
+            )]);
+        }
+    }
+}
@@ -0,0 +1,354 @@
+use crate::{
+    backbuffer::{begin_frame, end_frame, CurrentBackbuffer},
+    error::DxError,
+    gpu::Gpu,
+    mapped_buffer::MappedBuffer,
+    shader::compile_shader,
+    swapchain::{wait_for_ready_frame, WindowRenderTarget},
+    Render, RenderSet,
+};
+use bevy::{
+    app::{App, First, Plugin},
+    prelude::{resource_exists, warn, IntoSystemConfigs, Query, Res, ResMut, Resource, With},
+    window::PrimaryWindow,
+};
+use std::{
+    mem::{self, transmute_copy},
+    time::{Duration, Instant},
+};
+use windows::Win32::{
+    Foundation::RECT,
+    Graphics::{Direct3D::D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST, Direct3D12::*},
+};
+
+/// Characters the overlay font can draw, in the same order as the glyphs in `assets/diagnostics_overlay.hlsl`.
+const GLYPHS: &[u8] = b"0123456789. ACEFGMNPRSTU";
+const COLUMNS: usize = 16;
+const ROWS: usize = 4;
+/// Weight of the newest frame when smoothing the displayed timings.
+const SMOOTHING: f32 = 0.1;
+
+/// Draws FPS, CPU frame time, GPU frame time, and present count in the top left corner of the primary window.
+///
+/// Requires [`crate::BevyDirectXPlugin::manage_backbuffer`], and must be added after [`crate::BevyDirectXPlugin`].
+/// Does nothing if there is no [`Gpu`], see [`crate::BevyDirectXPlugin::allow_no_gpu`], or if the overlay's pipeline
+/// can't be created, in which case [`DiagnosticsOverlay`] isn't inserted. Only available with the `diagnostics_overlay`
+/// feature.
+pub struct DiagnosticsOverlayPlugin;
+
+impl Plugin for DiagnosticsOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(gpu) = app.world().get_resource::<Gpu>() else {
+            return;
+        };
+        let overlay = match DiagnosticsOverlay::new(gpu) {
+            Ok(overlay) => overlay,
+            Err(e) => {
+                warn!("BevyDirectX: Failed to create diagnostics overlay, skipping it: {e}");
+                return;
+            }
+        };
+
+        app.insert_resource(overlay)
+            .add_systems(First, begin_diagnostics_frame.after(wait_for_ready_frame))
+            .add_systems(
+                Render,
+                (
+                    begin_gpu_timer
+                        .after(begin_frame)
+                        .in_set(RenderSet::Prepare),
+                    draw_diagnostics_overlay
+                        .before(end_frame)
+                        .in_set(RenderSet::Present),
+                )
+                    .run_if(resource_exists::<Gpu>),
+            );
+    }
+}
+
+/// State and settings for [`DiagnosticsOverlayPlugin`].
+#[derive(Resource)]
+pub struct DiagnosticsOverlay {
+    /// Whether to draw the overlay. Timings are still measured while hidden. Defaults to true.
+    pub visible: bool,
+    /// Size of each font pixel in screen pixels. Defaults to 3.
+    pub scale: u32,
+    root_signature: ID3D12RootSignature,
+    pipeline: ID3D12PipelineState,
+    timestamp_heap: ID3D12QueryHeap,
+    timestamp_readback_buffer: ID3D12Resource,
+    timestamp_frequency: u64,
+    timestamps_pending: bool,
+    frame_start: Option<Instant>,
+    frame_time: f32,
+    cpu_frame_time: f32,
+    gpu_frame_time: f32,
+    present_count: u32,
+}
+
+impl DiagnosticsOverlay {
+    pub fn new(gpu: &Gpu) -> Result<Self, DxError> {
+        let source = include_str!("../assets/diagnostics_overlay.hlsl");
+        let shader_vs = compile_shader(source, "VSMain", "vs_5_1")?;
+        let shader_ps = compile_shader(source, "PSMain", "ps_5_1")?;
+
+        let root_signature = gpu.create_root_signature(
+            &[D3D12_ROOT_PARAMETER1 {
+                ParameterType: D3D12_ROOT_PARAMETER_TYPE_32BIT_CONSTANTS,
+                Anonymous: D3D12_ROOT_PARAMETER1_0 {
+                    Constants: D3D12_ROOT_CONSTANTS {
+                        ShaderRegister: 0,
+                        RegisterSpace: 0,
+                        Num32BitValues: mem::size_of::<OverlayConstants>() as u32 / 4,
+                    },
+                },
+                ShaderVisibility: D3D12_SHADER_VISIBILITY_PIXEL,
+            }],
+            &[],
+            D3D12_ROOT_SIGNATURE_FLAG_NONE,
+        )?;
+
+        let mut desc = D3D12_GRAPHICS_PIPELINE_STATE_DESC {
+            pRootSignature: unsafe { transmute_copy(&root_signature) },
+            VS: D3D12_SHADER_BYTECODE {
+                pShaderBytecode: shader_vs.as_ptr() as _,
+                BytecodeLength: shader_vs.len(),
+            },
+            PS: D3D12_SHADER_BYTECODE {
+                pShaderBytecode: shader_ps.as_ptr() as _,
+                BytecodeLength: shader_ps.len(),
+            },
+            SampleMask: u32::MAX,
+            RasterizerState: D3D12_RASTERIZER_DESC {
+                FillMode: D3D12_FILL_MODE_SOLID,
+                CullMode: D3D12_CULL_MODE_NONE,
+                ..Default::default()
+            },
+            PrimitiveTopologyType: D3D12_PRIMITIVE_TOPOLOGY_TYPE_TRIANGLE,
+            NumRenderTargets: 1,
+            ..Default::default()
+        };
+        desc.BlendState.RenderTarget[0] = D3D12_RENDER_TARGET_BLEND_DESC {
+            BlendEnable: true.into(),
+            SrcBlend: D3D12_BLEND_ONE,
+            DestBlend: D3D12_BLEND_INV_SRC_ALPHA,
+            BlendOp: D3D12_BLEND_OP_ADD,
+            SrcBlendAlpha: D3D12_BLEND_ONE,
+            DestBlendAlpha: D3D12_BLEND_INV_SRC_ALPHA,
+            BlendOpAlpha: D3D12_BLEND_OP_ADD,
+            RenderTargetWriteMask: D3D12_COLOR_WRITE_ENABLE_ALL.0 as u8,
+            ..Default::default()
+        };
+        desc.RTVFormats[0] = WindowRenderTarget::FORMAT;
+        desc.SampleDesc.Count = 1;
+        let pipeline = gpu
+            .pipeline_cache()
+            .create_graphics_pipeline(&gpu.device, &desc)?;
+
+        // Timestamps at the start and end of the frame's command list
+        let mut timestamp_heap = None;
+        unsafe {
+            gpu.device.CreateQueryHeap(
+                &D3D12_QUERY_HEAP_DESC {
+                    Type: D3D12_QUERY_HEAP_TYPE_TIMESTAMP,
+                    Count: 2,
+                    NodeMask: 0,
+                },
+                &mut timestamp_heap,
+            )?;
+        }
+        let timestamp_readback_buffer = gpu.create_buffer(
+            2 * mem::size_of::<u64>() as u64,
+            D3D12_HEAP_TYPE_READBACK,
+            D3D12_RESOURCE_FLAG_NONE,
+            D3D12_RESOURCE_STATE_COPY_DEST,
+        )?;
+        let timestamp_frequency = unsafe { gpu.queue.GetTimestampFrequency() }?;
+
+        Ok(Self {
+            visible: true,
+            scale: 3,
+            root_signature,
+            pipeline,
+            timestamp_heap: timestamp_heap.unwrap(),
+            timestamp_readback_buffer,
+            timestamp_frequency,
+            timestamps_pending: false,
+            frame_start: None,
+            frame_time: 0.0,
+            cpu_frame_time: 0.0,
+            gpu_frame_time: 0.0,
+            present_count: 0,
+        })
+    }
+
+    /// Frames per second, smoothed over recent frames.
+    pub fn fps(&self) -> f32 {
+        if self.frame_time > 0.0 {
+            1.0 / self.frame_time
+        } else {
+            0.0
+        }
+    }
+
+    /// Time from the end of [`wait_for_ready_frame`] to the end of recording rendering commands, smoothed over recent
+    /// frames.
+    pub fn cpu_frame_time(&self) -> Duration {
+        Duration::from_secs_f32(self.cpu_frame_time)
+    }
+
+    /// Time the GPU spent executing the frame's command list, smoothed over recent frames.
+    pub fn gpu_frame_time(&self) -> Duration {
+        Duration::from_secs_f32(self.gpu_frame_time)
+    }
+
+    fn text(&self) -> [String; ROWS] {
+        [
+            format!("FPS {:.1}", self.fps()),
+            format!("CPU {:.2} MS", self.cpu_frame_time * 1000.0),
+            format!("GPU {:.2} MS", self.gpu_frame_time * 1000.0),
+            format!("PRESENTS {}", self.present_count),
+        ]
+    }
+}
+
+/// Layout of the `Constants` cbuffer in `assets/diagnostics_overlay.hlsl`.
+#[repr(C)]
+struct OverlayConstants {
+    origin: [u32; 2],
+    scale: u32,
+    padding: u32,
+    text: [[u8; COLUMNS]; ROWS],
+}
+
+fn smooth(average: f32, value: f32) -> f32 {
+    if average == 0.0 {
+        value
+    } else {
+        average + (value - average) * SMOOTHING
+    }
+}
+
+/// Read back last frame's GPU timestamps, and start timing the CPU side of this frame.
+///
+/// Runs after [`wait_for_ready_frame`], so the GPU has finished last frame's command list.
+fn begin_diagnostics_frame(mut overlay: ResMut<DiagnosticsOverlay>) {
+    if overlay.timestamps_pending {
+        overlay.timestamps_pending = false;
+        let timestamps = MappedBuffer::<u64>::read_write(&overlay.timestamp_readback_buffer)
+            .map(|timestamps| timestamps.as_slice()[1].saturating_sub(timestamps.as_slice()[0]));
+        if let Ok(ticks) = timestamps {
+            let gpu_frame_time = ticks as f32 / overlay.timestamp_frequency as f32;
+            overlay.gpu_frame_time = smooth(overlay.gpu_frame_time, gpu_frame_time);
+        }
+    }
+
+    let now = Instant::now();
+    if let Some(frame_start) = overlay.frame_start {
+        let frame_time = (now - frame_start).as_secs_f32();
+        overlay.frame_time = smooth(overlay.frame_time, frame_time);
+    }
+    overlay.frame_start = Some(now);
+}
+
+fn begin_gpu_timer(
+    overlay: Res<DiagnosticsOverlay>,
+    backbuffer: Option<Res<CurrentBackbuffer>>,
+    gpu: Res<Gpu>,
+) {
+    if backbuffer.is_some() {
+        unsafe {
+            gpu.command_list()
+                .EndQuery(&overlay.timestamp_heap, D3D12_QUERY_TYPE_TIMESTAMP, 0)
+        };
+    }
+}
+
+fn draw_diagnostics_overlay(
+    mut overlay: ResMut<DiagnosticsOverlay>,
+    window: Query<&WindowRenderTarget, With<PrimaryWindow>>,
+    backbuffer: Option<Res<CurrentBackbuffer>>,
+    gpu: Res<Gpu>,
+) {
+    let (Some(backbuffer), Ok(render_target)) = (backbuffer, window.get_single()) else {
+        return;
+    };
+
+    if let Some(frame_start) = overlay.frame_start {
+        let cpu_frame_time = frame_start.elapsed().as_secs_f32();
+        overlay.cpu_frame_time = smooth(overlay.cpu_frame_time, cpu_frame_time);
+    }
+    if let Ok(statistics) = render_target.frame_statistics() {
+        overlay.present_count = statistics.PresentCount;
+    }
+
+    let command_list = gpu.command_list();
+
+    if overlay.visible {
+        let scale = overlay.scale.max(1);
+        let mut constants = OverlayConstants {
+            origin: [scale * 2; 2],
+            scale,
+            padding: 0,
+            text: [[glyph_index(b' '); COLUMNS]; ROWS],
+        };
+        for (row, line) in constants.text.iter_mut().zip(overlay.text()) {
+            for (glyph, character) in row.iter_mut().zip(line.bytes()) {
+                *glyph = glyph_index(character);
+            }
+        }
+
+        let [x, y] = constants.origin;
+        let (width, height) = (COLUMNS as u32 * 4 * scale, ROWS as u32 * 6 * scale);
+        unsafe {
+            command_list.SetPipelineState(&overlay.pipeline);
+            command_list.SetGraphicsRootSignature(&overlay.root_signature);
+            command_list.SetGraphicsRoot32BitConstants(
+                0,
+                mem::size_of::<OverlayConstants>() as u32 / 4,
+                &constants as *const _ as *const _,
+                0,
+            );
+            command_list.RSSetViewports(&[D3D12_VIEWPORT {
+                TopLeftX: x as f32,
+                TopLeftY: y as f32,
+                Width: width as f32,
+                Height: height as f32,
+                MinDepth: D3D12_MIN_DEPTH,
+                MaxDepth: D3D12_MAX_DEPTH,
+            }]);
+            command_list.RSSetScissorRects(&[RECT {
+                left: x as i32,
+                top: y as i32,
+                right: (x + width) as i32,
+                bottom: (y + height) as i32,
+            }]);
+            command_list.OMSetRenderTargets(1, Some(&backbuffer.rtv), false, None);
+            command_list.IASetPrimitiveTopology(D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
+            command_list.DrawInstanced(3, 1, 0, 0);
+        }
+    }
+
+    unsafe {
+        command_list.EndQuery(&overlay.timestamp_heap, D3D12_QUERY_TYPE_TIMESTAMP, 1);
+        command_list.ResolveQueryData(
+            &overlay.timestamp_heap,
+            D3D12_QUERY_TYPE_TIMESTAMP,
+            0,
+            2,
+            &overlay.timestamp_readback_buffer,
+            0,
+        );
+    }
+    overlay.timestamps_pending = true;
+}
+
+/// Index of `character` in [`GLYPHS`], drawing unsupported characters as spaces.
+fn glyph_index(character: u8) -> u8 {
+    let character = character.to_ascii_uppercase();
+    GLYPHS
+        .iter()
+        .position(|glyph| *glyph == character)
+        .or_else(|| GLYPHS.iter().position(|glyph| *glyph == b' '))
+        .unwrap() as u8
+}
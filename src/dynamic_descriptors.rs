@@ -0,0 +1,202 @@
+use crate::{error::DxError, gpu::Gpu};
+use bevy::prelude::{FromWorld, Resource, World};
+use std::collections::VecDeque;
+use windows::{
+    core::Error,
+    Win32::{Foundation::E_OUTOFMEMORY, Graphics::Direct3D12::*},
+};
+
+/// Default number of descriptors in a [`DynamicDescriptorRing`].
+pub const DEFAULT_DYNAMIC_DESCRIPTOR_COUNT: u32 = 16384;
+
+/// Shader-visible CBV/SRV/UAV descriptor heap, suballocated as a ring for descriptor tables built each frame, for the
+/// classic (non-bindless) binding model.
+///
+/// Descriptors are created ahead of time in non-shader-visible heaps, and [`DynamicDescriptorRing::copy_descriptors`]
+/// copies the ones a draw or dispatch needs into a contiguous range of this heap, returning the GPU handle to bind
+/// with `SetGraphicsRootDescriptorTable` or `SetComputeRootDescriptorTable`. Bind [`DynamicDescriptorRing::heap`]
+/// with `SetDescriptorHeaps` first.
+///
+/// There's no explicit reset at frame boundaries. Each range is tagged with [`Gpu::next_fence_value`] when
+/// allocated, and is reclaimed once [`Gpu::completed_fence_value`] reaches it, so a frame's descriptors are typically
+/// reclaimed after the frame wait of the frame after. The heap can't grow, as rebinding a new heap mid-frame would
+/// invalidate bound tables, so size it for the most descriptors copied per frame, times the number of frames that can
+/// be in flight plus one. Shader-visible heaps are limited to 1,000,000 descriptors on most hardware.
+///
+/// Not added by default; add it with `app.init_resource::<DynamicDescriptorRing>()` after
+/// [`crate::BevyDirectXPlugin`].
+#[derive(Resource)]
+pub struct DynamicDescriptorRing {
+    heap: ID3D12DescriptorHeap,
+    cpu_start: D3D12_CPU_DESCRIPTOR_HANDLE,
+    gpu_start: D3D12_GPU_DESCRIPTOR_HANDLE,
+    increment: u32,
+    capacity: u32,
+    /// Index the next allocation starts at.
+    head: u32,
+    /// Number of descriptors allocated and not yet reclaimed, including any skipped at the end when wrapping around.
+    used: u32,
+    /// Descriptors allocated before each fence value, oldest first.
+    in_flight: VecDeque<(u64, u32)>,
+}
+
+impl DynamicDescriptorRing {
+    pub fn new(gpu: &Gpu, capacity: u32) -> Result<Self, DxError> {
+        let heap =
+            gpu.create_descriptor_heap(D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV, capacity, true)?;
+        let increment = unsafe {
+            gpu.device
+                .GetDescriptorHandleIncrementSize(D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV)
+        };
+        let (cpu_start, gpu_start) = unsafe {
+            (
+                heap.GetCPUDescriptorHandleForHeapStart(),
+                heap.GetGPUDescriptorHandleForHeapStart(),
+            )
+        };
+
+        Ok(Self {
+            heap,
+            cpu_start,
+            gpu_start,
+            increment,
+            capacity,
+            head: 0,
+            used: 0,
+            in_flight: VecDeque::new(),
+        })
+    }
+
+    /// The shader-visible heap to bind with `SetDescriptorHeaps`.
+    pub fn heap(&self) -> &ID3D12DescriptorHeap {
+        &self.heap
+    }
+
+    /// Total number of descriptors in the heap.
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// Allocate `count` contiguous descriptors for commands recorded this frame, returning the CPU handle to write
+    /// them through and the GPU handle to bind.
+    ///
+    /// Returns `E_OUTOFMEMORY` if the free part of the ring is too small, see [`DynamicDescriptorRing`] for sizing.
+    pub fn allocate(
+        &mut self,
+        gpu: &Gpu,
+        count: u32,
+    ) -> Result<(D3D12_CPU_DESCRIPTOR_HANDLE, D3D12_GPU_DESCRIPTOR_HANDLE), DxError> {
+        self.reclaim(gpu.completed_fence_value());
+
+        // Ranges must be contiguous, so skip the end of the heap if the range doesn't fit before it
+        let tail = (self.head + self.capacity - self.used) % self.capacity;
+        let free_after_head = if self.used == self.capacity {
+            0
+        } else if self.head >= tail {
+            self.capacity - self.head
+        } else {
+            tail - self.head
+        };
+        let (start, skipped) = if count <= free_after_head {
+            (self.head, 0)
+        } else if self.head >= tail && self.used != self.capacity && count <= tail {
+            (0, free_after_head)
+        } else {
+            return Err(Error::new(
+                E_OUTOFMEMORY,
+                format!(
+                    "BevyDirectX: DynamicDescriptorRing is out of space allocating {count} descriptors, with {} of {} in use",
+                    self.used, self.capacity
+                ),
+            )
+            .into());
+        };
+
+        self.head = (start + count) % self.capacity;
+        self.used += skipped + count;
+        let fence_value = gpu.next_fence_value();
+        match self.in_flight.back_mut() {
+            Some((last_fence_value, allocated)) if *last_fence_value == fence_value => {
+                *allocated += skipped + count;
+            }
+            _ => self.in_flight.push_back((fence_value, skipped + count)),
+        }
+
+        Ok((
+            D3D12_CPU_DESCRIPTOR_HANDLE {
+                ptr: self.cpu_start.ptr + (start * self.increment) as usize,
+            },
+            D3D12_GPU_DESCRIPTOR_HANDLE {
+                ptr: self.gpu_start.ptr + (start * self.increment) as u64,
+            },
+        ))
+    }
+
+    /// Copy each of `source_descriptors` into a contiguous range of the ring, in order, returning the GPU handle of the
+    /// first to bind as a descriptor table.
+    ///
+    /// Source descriptors must be CBVs, SRVs, or UAVs in non-shader-visible heaps, as shader-visible heaps are slow or
+    /// impossible for the CPU to read from.
+    pub fn copy_descriptors(
+        &mut self,
+        gpu: &Gpu,
+        source_descriptors: &[D3D12_CPU_DESCRIPTOR_HANDLE],
+    ) -> Result<D3D12_GPU_DESCRIPTOR_HANDLE, DxError> {
+        let (mut destination, gpu_handle) = self.allocate(gpu, source_descriptors.len() as u32)?;
+        for source in source_descriptors {
+            unsafe {
+                gpu.device.CopyDescriptorsSimple(
+                    1,
+                    destination,
+                    *source,
+                    D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV,
+                )
+            };
+            destination.ptr += self.increment as usize;
+        }
+        Ok(gpu_handle)
+    }
+
+    /// Copy `count` descriptors starting at `source_start` into the ring in a single copy, see
+    /// [`DynamicDescriptorRing::copy_descriptors`].
+    pub fn copy_descriptor_range(
+        &mut self,
+        gpu: &Gpu,
+        source_start: D3D12_CPU_DESCRIPTOR_HANDLE,
+        count: u32,
+    ) -> Result<D3D12_GPU_DESCRIPTOR_HANDLE, DxError> {
+        let (destination, gpu_handle) = self.allocate(gpu, count)?;
+        unsafe {
+            gpu.device.CopyDescriptorsSimple(
+                count,
+                destination,
+                source_start,
+                D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV,
+            )
+        };
+        Ok(gpu_handle)
+    }
+
+    /// Free the ranges of every frame the GPU has finished.
+    fn reclaim(&mut self, completed_fence_value: u64) {
+        while let Some(&(fence_value, allocated)) = self.in_flight.front() {
+            if fence_value > completed_fence_value {
+                break;
+            }
+            self.used -= allocated;
+            self.in_flight.pop_front();
+        }
+
+        // Start from the beginning again once empty, to avoid needlessly splitting ranges around the end
+        if self.used == 0 {
+            self.head = 0;
+        }
+    }
+}
+
+impl FromWorld for DynamicDescriptorRing {
+    fn from_world(world: &mut World) -> Self {
+        Self::new(world.resource::<Gpu>(), DEFAULT_DYNAMIC_DESCRIPTOR_COUNT)
+            .expect("BevyDirectX: Failed to create dynamic descriptor ring")
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt;
+use windows::{
+    core::Error,
+    Win32::{
+        Foundation::{DXGI_STATUS_OCCLUDED, E_INVALIDARG, E_NOINTERFACE, E_NOTIMPL, E_OUTOFMEMORY},
+        Graphics::Dxgi::{
+            DXGI_ERROR_DEVICE_HUNG, DXGI_ERROR_DEVICE_REMOVED, DXGI_ERROR_DEVICE_RESET,
+            DXGI_ERROR_INVALID_CALL, DXGI_ERROR_UNSUPPORTED, DXGI_ERROR_WAS_STILL_DRAWING,
+        },
+    },
+};
+
+/// Error returned by the crate's public APIs, categorizing common D3D12 and DXGI `HRESULT`s.
+///
+/// Each variant keeps the original [`windows::core::Error`], accessible via [`DxError::windows_error`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DxError {
+    /// The GPU was physically removed, its driver was updated, or it was reset after an error.
+    DeviceRemoved(Error),
+    /// The GPU took too long to execute commands, or hit a fault, and was reset (TDR).
+    DeviceHung(Error),
+    /// Ran out of system or video memory.
+    OutOfMemory(Error),
+    /// A call was made with invalid parameters, or in an invalid state.
+    InvalidCall(Error),
+    /// The GPU is still using a resource, and the call would have blocked.
+    WasStillDrawing(Error),
+    /// The window is fully occluded, minimized, or otherwise not visible.
+    Occluded(Error),
+    /// The feature, format, or interface is not supported by this device, driver, or OS.
+    Unsupported(Error),
+    /// Any other error.
+    Other(Error),
+}
+
+impl DxError {
+    /// The original error, e.g. for logging.
+    pub fn windows_error(&self) -> &Error {
+        match self {
+            Self::DeviceRemoved(e)
+            | Self::DeviceHung(e)
+            | Self::OutOfMemory(e)
+            | Self::InvalidCall(e)
+            | Self::WasStillDrawing(e)
+            | Self::Occluded(e)
+            | Self::Unsupported(e)
+            | Self::Other(e) => e,
+        }
+    }
+}
+
+impl From<Error> for DxError {
+    fn from(error: Error) -> Self {
+        match error.code() {
+            DXGI_ERROR_DEVICE_REMOVED | DXGI_ERROR_DEVICE_RESET => Self::DeviceRemoved(error),
+            DXGI_ERROR_DEVICE_HUNG => Self::DeviceHung(error),
+            E_OUTOFMEMORY => Self::OutOfMemory(error),
+            DXGI_ERROR_INVALID_CALL | E_INVALIDARG => Self::InvalidCall(error),
+            DXGI_ERROR_WAS_STILL_DRAWING => Self::WasStillDrawing(error),
+            DXGI_STATUS_OCCLUDED => Self::Occluded(error),
+            DXGI_ERROR_UNSUPPORTED | E_NOTIMPL | E_NOINTERFACE => Self::Unsupported(error),
+            _ => Self::Other(error),
+        }
+    }
+}
+
+impl From<DxError> for Error {
+    fn from(error: DxError) -> Self {
+        match error {
+            DxError::DeviceRemoved(e)
+            | DxError::DeviceHung(e)
+            | DxError::OutOfMemory(e)
+            | DxError::InvalidCall(e)
+            | DxError::WasStillDrawing(e)
+            | DxError::Occluded(e)
+            | DxError::Unsupported(e)
+            | DxError::Other(e) => e,
+        }
+    }
+}
+
+impl fmt::Display for DxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.windows_error().fmt(f)
+    }
+}
+
+impl std::error::Error for DxError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.windows_error())
+    }
+}
@@ -0,0 +1,156 @@
+use crate::error::DxError;
+use bevy::prelude::error;
+use std::{
+    mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+use windows::Win32::{
+    Foundation::{CloseHandle, HANDLE},
+    Graphics::Direct3D12::ID3D12Fence,
+    System::Threading::{CreateEventW, SetEvent, WaitForMultipleObjects, INFINITE},
+};
+
+/// A [`crate::Gpu::on_fence_complete`] callback waiting for the fence to reach `value`.
+struct FenceCallback {
+    value: u64,
+    callback: Box<dyn FnOnce() + Send>,
+    /// When the GPU is assumed to have hung, and the callback is dropped, if the fence hasn't reached `value` by then.
+    deadline: Option<Instant>,
+}
+
+/// Thread that runs [`crate::Gpu::on_fence_complete`] callbacks once the fence reaches their values.
+///
+/// Pending callbacks are tracked together, and the thread waits for the lowest pending value, or a new callback,
+/// so a callback is never held up by an earlier one waiting on a higher value, or one that's never signaled.
+pub(crate) struct FenceWaiter {
+    sender: Sender<FenceCallback>,
+    /// Auto-reset event set to wake the thread for a new callback, or to shut down.
+    wake_event: HANDLE,
+    shutdown: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    timeout: u32,
+}
+
+impl FenceWaiter {
+    /// Start the thread, dropping callbacks that wait longer than `timeout` milliseconds, unless it's `INFINITE`.
+    pub(crate) fn new(fence: ID3D12Fence, timeout: u32) -> Result<Self, DxError> {
+        let (sender, receiver) = mpsc::channel();
+        let wake_event = unsafe { CreateEventW(None, false, false, None) }?;
+        let fence_event = match unsafe { CreateEventW(None, false, false, None) } {
+            Ok(fence_event) => fence_event,
+            Err(e) => {
+                let _ = unsafe { CloseHandle(wake_event) };
+                return Err(e.into());
+            }
+        };
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let thread_shutdown = shutdown.clone();
+        let thread = thread::Builder::new()
+            .name("BevyDirectX fence waiter".to_owned())
+            .spawn(move || {
+                run(&fence, &receiver, fence_event, wake_event, &thread_shutdown);
+                let _ = unsafe { CloseHandle(fence_event) };
+            })
+            .expect("BevyDirectX: Failed to spawn fence waiter thread");
+
+        Ok(Self {
+            sender,
+            wake_event,
+            shutdown,
+            thread: Some(thread),
+            timeout,
+        })
+    }
+
+    /// Queue `callback` to run once the fence reaches `value`.
+    pub(crate) fn push(&self, value: u64, callback: Box<dyn FnOnce() + Send>) {
+        let deadline = (self.timeout != INFINITE)
+            .then(|| Instant::now() + Duration::from_millis(self.timeout.into()));
+        self.sender
+            .send(FenceCallback {
+                value,
+                callback,
+                deadline,
+            })
+            .expect("BevyDirectX: Fence waiter thread exited");
+        let _ = unsafe { SetEvent(self.wake_event) };
+    }
+}
+
+impl Drop for FenceWaiter {
+    /// Stop the thread, running callbacks whose values the fence already reached, and dropping the rest.
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Release);
+        let _ = unsafe { SetEvent(self.wake_event) };
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        let _ = unsafe { CloseHandle(self.wake_event) };
+    }
+}
+
+fn run(
+    fence: &ID3D12Fence,
+    receiver: &Receiver<FenceCallback>,
+    fence_event: HANDLE,
+    wake_event: HANDLE,
+    shutdown: &AtomicBool,
+) {
+    let mut pending = Vec::new();
+    loop {
+        let shutting_down = shutdown.load(Ordering::Acquire);
+        pending.extend(receiver.try_iter());
+
+        let completed_value = unsafe { fence.GetCompletedValue() };
+        let now = Instant::now();
+        let (mut ready, waiting): (Vec<_>, Vec<_>) = mem::take(&mut pending)
+            .into_iter()
+            .partition(|callback: &FenceCallback| callback.value <= completed_value);
+        ready.sort_by_key(|callback| callback.value);
+        for FenceCallback { callback, .. } in ready {
+            callback();
+        }
+        for callback in waiting {
+            if callback.deadline.is_some_and(|deadline| deadline <= now) {
+                error!(
+                    "BevyDirectX: GPU wait for fence value {} timed out — possible hang",
+                    callback.value
+                );
+            } else {
+                pending.push(callback);
+            }
+        }
+
+        if shutting_down {
+            return;
+        }
+
+        let Some(next_value) = pending.iter().map(|callback| callback.value).min() else {
+            unsafe { WaitForMultipleObjects(&[wake_event], false, INFINITE) };
+            continue;
+        };
+        let timeout = pending
+            .iter()
+            .filter_map(|callback| callback.deadline)
+            .min()
+            .map_or(INFINITE, |deadline| {
+                // Round up, so that the deadline has passed when the wait times out
+                let remaining = deadline.saturating_duration_since(now);
+                u32::try_from(remaining.as_micros().div_ceil(1000)).unwrap_or(INFINITE - 1)
+            });
+        if unsafe { fence.SetEventOnCompletion(next_value, fence_event) }.is_err() {
+            // Can't wait for the fence, so poll it instead, while still waking up for new callbacks
+            unsafe { WaitForMultipleObjects(&[wake_event], false, timeout.min(1)) };
+            continue;
+        }
+        // Whichever of the fence, a new callback, the earliest deadline, or shutdown comes first, the loop rechecks all
+        unsafe { WaitForMultipleObjects(&[fence_event, wake_event], false, timeout) };
+    }
+}
@@ -0,0 +1,176 @@
+use crate::{error::DxError, gpu::Gpu};
+use std::{ffi::c_void, mem};
+use windows::{
+    core::Error,
+    Win32::Graphics::{
+        Direct3D12::*,
+        Dxgi::{
+            Common::{DXGI_FORMAT, DXGI_FORMAT_UNKNOWN},
+            DXGI_ERROR_UNSUPPORTED,
+        },
+    },
+};
+
+impl Gpu {
+    /// Query what `format` can be used for on this GPU, e.g. whether
+    /// `Support1.contains(D3D12_FORMAT_SUPPORT1_RENDER_TARGET)`.
+    ///
+    /// Resource and pipeline creation helpers consult this when creation fails, to return a
+    /// [`DxError::Unsupported`] naming the usage the format lacks, instead of a bare `E_INVALIDARG`.
+    pub fn check_format_support(&self, format: DXGI_FORMAT) -> D3D12_FEATURE_DATA_FORMAT_SUPPORT {
+        format_support(&self.device, format)
+    }
+
+    /// Number of quality levels available for `format` with `sample_count` samples per pixel, for
+    /// `DXGI_SAMPLE_DESC::Quality`, which must be less than this. 0 means the sample count isn't supported for the
+    /// format, e.g. for listing MSAA options in a settings menu by checking 2, 4, and 8 samples.
+    pub fn multisample_quality_levels(&self, format: DXGI_FORMAT, sample_count: u32) -> u32 {
+        let mut data = D3D12_FEATURE_DATA_MULTISAMPLE_QUALITY_LEVELS {
+            Format: format,
+            SampleCount: sample_count,
+            Flags: D3D12_MULTISAMPLE_QUALITY_LEVELS_FLAG_NONE,
+            NumQualityLevels: 0,
+        };
+        let result = unsafe {
+            self.device.CheckFeatureSupport(
+                D3D12_FEATURE_MULTISAMPLE_QUALITY_LEVELS,
+                &mut data as *mut _ as *mut c_void,
+                mem::size_of_val(&data) as u32,
+            )
+        };
+        if result.is_ok() {
+            data.NumQualityLevels
+        } else {
+            0
+        }
+    }
+}
+
+/// Formats unknown to the runtime report no support at all.
+pub(crate) fn format_support(
+    device: &ID3D12Device9,
+    format: DXGI_FORMAT,
+) -> D3D12_FEATURE_DATA_FORMAT_SUPPORT {
+    let mut data = D3D12_FEATURE_DATA_FORMAT_SUPPORT {
+        Format: format,
+        ..Default::default()
+    };
+    let _ = unsafe {
+        device.CheckFeatureSupport(
+            D3D12_FEATURE_FORMAT_SUPPORT,
+            &mut data as *mut _ as *mut c_void,
+            mem::size_of_val(&data) as u32,
+        )
+    };
+    data
+}
+
+/// Turn `error` from creating a resource with `desc` into a readable error if it's explained by the format not
+/// supporting a requested usage, otherwise return it as is.
+pub(crate) fn resource_creation_error(
+    device: &ID3D12Device9,
+    desc: &D3D12_RESOURCE_DESC,
+    error: Error,
+) -> DxError {
+    if desc.Format == DXGI_FORMAT_UNKNOWN {
+        return error.into();
+    }
+
+    let mut usages = vec![match desc.Dimension {
+        D3D12_RESOURCE_DIMENSION_TEXTURE1D => (D3D12_FORMAT_SUPPORT1_TEXTURE1D, "1D texture"),
+        D3D12_RESOURCE_DIMENSION_TEXTURE3D => (D3D12_FORMAT_SUPPORT1_TEXTURE3D, "3D texture"),
+        D3D12_RESOURCE_DIMENSION_BUFFER => (D3D12_FORMAT_SUPPORT1_BUFFER, "buffer"),
+        _ => (D3D12_FORMAT_SUPPORT1_TEXTURE2D, "2D texture"),
+    }];
+    for (flag, support, usage) in [
+        (
+            D3D12_RESOURCE_FLAG_ALLOW_RENDER_TARGET,
+            D3D12_FORMAT_SUPPORT1_RENDER_TARGET,
+            "render target",
+        ),
+        (
+            D3D12_RESOURCE_FLAG_ALLOW_DEPTH_STENCIL,
+            D3D12_FORMAT_SUPPORT1_DEPTH_STENCIL,
+            "depth stencil",
+        ),
+        (
+            D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS,
+            D3D12_FORMAT_SUPPORT1_TYPED_UNORDERED_ACCESS_VIEW,
+            "unordered access",
+        ),
+    ] {
+        if desc.Flags.contains(flag) {
+            usages.push((support, usage));
+        }
+    }
+    if desc.SampleDesc.Count > 1 {
+        usages.push((
+            D3D12_FORMAT_SUPPORT1_MULTISAMPLE_RENDERTARGET,
+            "multisampled render target",
+        ));
+    }
+
+    unsupported_usage_error(device, desc.Format, &usages).unwrap_or(error.into())
+}
+
+/// Turn `error` from creating a pipeline with `desc` into a readable error if it's explained by a render target or
+/// depth stencil format not supporting how the pipeline uses it, otherwise return it as is.
+pub(crate) fn pipeline_creation_error(
+    device: &ID3D12Device9,
+    desc: &D3D12_GRAPHICS_PIPELINE_STATE_DESC,
+    error: Error,
+) -> DxError {
+    for (i, format) in desc.RTVFormats[..(desc.NumRenderTargets as usize).min(8)]
+        .iter()
+        .enumerate()
+    {
+        let blend = if desc.BlendState.IndependentBlendEnable.as_bool() {
+            desc.BlendState.RenderTarget[i]
+        } else {
+            desc.BlendState.RenderTarget[0]
+        };
+        let mut usages = vec![(D3D12_FORMAT_SUPPORT1_RENDER_TARGET, "render target")];
+        if blend.BlendEnable.as_bool() {
+            usages.push((D3D12_FORMAT_SUPPORT1_BLENDABLE, "blending"));
+        }
+        if desc.SampleDesc.Count > 1 {
+            usages.push((
+                D3D12_FORMAT_SUPPORT1_MULTISAMPLE_RENDERTARGET,
+                "multisampled render target",
+            ));
+        }
+        if let Some(error) = unsupported_usage_error(device, *format, &usages) {
+            return error;
+        }
+    }
+
+    if desc.DSVFormat != DXGI_FORMAT_UNKNOWN {
+        if let Some(error) = unsupported_usage_error(
+            device,
+            desc.DSVFormat,
+            &[(D3D12_FORMAT_SUPPORT1_DEPTH_STENCIL, "depth stencil")],
+        ) {
+            return error;
+        }
+    }
+
+    error.into()
+}
+
+fn unsupported_usage_error(
+    device: &ID3D12Device9,
+    format: DXGI_FORMAT,
+    usages: &[(D3D12_FORMAT_SUPPORT1, &str)],
+) -> Option<DxError> {
+    let support = format_support(device, format);
+    let (_, usage) = usages
+        .iter()
+        .find(|(required, _)| !support.Support1.contains(*required))?;
+    Some(
+        Error::new(
+            DXGI_ERROR_UNSUPPORTED,
+            format!("BevyDirectX: Format {format:?} does not support {usage} usage on this GPU"),
+        )
+        .into(),
+    )
+}
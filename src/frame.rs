@@ -0,0 +1,97 @@
+use crate::{gpu::FRAMES_IN_FLIGHT, swapchain::WindowRenderTarget};
+use bevy::{
+    math::{Mat4, UVec2, Vec2},
+    prelude::{Query, Res, ResMut, Resource, With},
+    window::PrimaryWindow,
+};
+
+/// Monotonically increasing count of frames rendered, incremented once per run of the [`crate::Render`] schedule.
+///
+/// Useful for temporal effects, jitter sequences, and indexing per-frame resources.
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FrameCount(pub u64);
+
+impl FrameCount {
+    /// Index of the current frame in flight, for selecting per-frame resources.
+    pub fn frame_in_flight_index(&self) -> usize {
+        (self.0 % FRAMES_IN_FLIGHT as u64) as usize
+    }
+
+    /// Sub-pixel jitter offset for this frame, from a Halton(2, 3) sequence repeating every `sequence_length` frames.
+    ///
+    /// The offset is in pixels, in the range [-0.5, 0.5], with Y pointing down.
+    pub fn jitter(&self, sequence_length: u32) -> Vec2 {
+        let index = (self.0 % sequence_length.max(1) as u64) as u32 + 1;
+        Vec2::new(halton(index, 2), halton(index, 3)) - 0.5
+    }
+}
+
+fn halton(mut index: u32, base: u32) -> f32 {
+    let mut fraction = 1.0;
+    let mut result = 0.0;
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+    result
+}
+
+/// Increment [`FrameCount`] at the start of the [`crate::Render`] schedule, so that every
+/// render system sees the same value for a given frame.
+pub fn increment_frame_count(mut frame_count: ResMut<FrameCount>) {
+    frame_count.0 = frame_count.0.wrapping_add(1);
+}
+
+/// Sub-pixel jitter offset for the current frame, for temporal anti-aliasing, upscaling, and accumulation.
+///
+/// Updated by [`update_temporal_jitter`] from [`FrameCount::jitter`] and the primary window's
+/// [`WindowRenderTarget::render_size`], after [`crate::update_render_target`] in [`crate::RenderSet::Prepare`].
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct TemporalJitter {
+    /// Number of frames before the Halton sequence repeats. Defaults to 8.
+    pub sequence_length: u32,
+    offset: Vec2,
+    render_size: UVec2,
+}
+
+impl Default for TemporalJitter {
+    fn default() -> Self {
+        Self {
+            sequence_length: 8,
+            offset: Vec2::ZERO,
+            render_size: UVec2::ONE,
+        }
+    }
+}
+
+impl TemporalJitter {
+    /// This frame's offset in pixels, in the range [-0.5, 0.5], with Y pointing down, e.g. for
+    /// [`crate::UpscalerInputs`].
+    pub fn offset(&self) -> Vec2 {
+        self.offset
+    }
+
+    /// This frame's offset in normalized device coordinates, with Y pointing up.
+    pub fn ndc_offset(&self) -> Vec2 {
+        Vec2::new(2.0, -2.0) * self.offset / self.render_size.max(UVec2::ONE).as_vec2()
+    }
+
+    /// `projection` with [`TemporalJitter::ndc_offset`] applied, for perspective or orthographic projections.
+    pub fn jitter_projection(&self, projection: Mat4) -> Mat4 {
+        // Offsets clip space XY by the offset times W, which becomes a constant offset after the perspective divide
+        Mat4::from_translation(self.ndc_offset().extend(0.0)) * projection
+    }
+}
+
+/// Update [`TemporalJitter`] for the current frame.
+pub fn update_temporal_jitter(
+    window: Query<&WindowRenderTarget, With<PrimaryWindow>>,
+    frame_count: Res<FrameCount>,
+    mut jitter: ResMut<TemporalJitter>,
+) {
+    if let Ok(render_target) = window.get_single() {
+        jitter.render_size = render_target.render_size();
+    }
+    jitter.offset = frame_count.jitter(jitter.sequence_length);
+}
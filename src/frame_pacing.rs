@@ -0,0 +1,193 @@
+use crate::swapchain::WindowRenderTarget;
+use bevy::{
+    prelude::{Query, ResMut, Resource, With},
+    window::PrimaryWindow,
+};
+use std::{
+    hint, thread,
+    time::{Duration, Instant},
+};
+
+/// How [`crate::wait_for_ready_frame`] schedules the start of each frame.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FramePacing {
+    /// Start each frame as soon as the swapchain can accept another one, for the lowest input latency. Frame intervals
+    /// vary with CPU and GPU load, which can show up as micro-stutter.
+    #[default]
+    LowLatency,
+    /// Additionally delay the start of frames that would begin early, so that frames start, and therefore present,
+    /// at evenly spaced intervals. Trades a little latency, up to the variance in frame times, for consistency.
+    ///
+    /// The target interval is a moving average of recent frame intervals, measured with `QueryPerformanceCounter`
+    /// (via [`Instant`]), so pacing adapts when the frame rate changes. Frames that take longer than the average
+    /// can't be sped up, and start late as usual.
+    Smooth,
+}
+
+/// Frame timing history for [`FramePacing::Smooth`]. Only needed directly by custom frame loops, see
+/// [`crate::wait_for_ready_frame`].
+#[derive(Default)]
+pub struct SmoothFramePacer {
+    last_frame_start: Option<Instant>,
+    /// Moving average of frame intervals, excluding time spent pacing, in seconds.
+    average_interval: f64,
+}
+
+impl SmoothFramePacer {
+    /// Weight of the latest frame interval in the moving average.
+    const SMOOTHING: f64 = 0.1;
+    /// Sleeping is only accurate to around a millisecond, so spin for the rest.
+    const SPIN_DURATION: Duration = Duration::from_millis(1);
+
+    /// Sleep until an average frame interval has passed since the last frame started, then start a new frame.
+    pub fn pace(&mut self) {
+        let now = Instant::now();
+        let Some(last_frame_start) = self.last_frame_start else {
+            self.last_frame_start = Some(now);
+            return;
+        };
+
+        let interval = (now - last_frame_start).as_secs_f64();
+        self.average_interval = if self.average_interval == 0.0 {
+            interval
+        } else {
+            self.average_interval + (interval - self.average_interval) * Self::SMOOTHING
+        };
+
+        let target = last_frame_start + Duration::from_secs_f64(self.average_interval);
+        if let Some(remaining) = target.checked_duration_since(now) {
+            if let Some(sleep) = remaining.checked_sub(Self::SPIN_DURATION) {
+                thread::sleep(sleep);
+            }
+            while Instant::now() < target {
+                hint::spin_loop();
+            }
+        }
+
+        self.last_frame_start = Some(Instant::now());
+    }
+}
+
+/// Dropped and duplicated frames of the primary window, from its swapchain's [`WindowRenderTarget::frame_statistics`],
+/// updated each frame in [`crate::RenderSet::Prepare`].
+///
+/// Counts are totals since the app started. Statistics are unavailable in some situations, such as before the first
+/// present, or while the window is minimized or occluded, in which case nothing is counted. If they're never
+/// available, every field stays zero.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct FramePacingStats {
+    /// Frames that were presented but never shown, because a newer frame replaced them before the next refresh, as
+    /// happens with tearing or when rendering faster than the refresh rate.
+    pub dropped: u64,
+    /// Refreshes that showed the previous frame again because no new frame was ready, i.e. missed vsyncs.
+    pub duplicated: u64,
+    /// Moving average of the time between presents, in milliseconds.
+    pub avg_interval_ms: f32,
+    last_sample: Option<(u32, u32, Instant)>,
+}
+
+/// Update [`FramePacingStats`] from the primary window's frame statistics.
+pub fn update_frame_pacing_stats(
+    window: Query<&WindowRenderTarget, With<PrimaryWindow>>,
+    mut stats: ResMut<FramePacingStats>,
+) {
+    let Ok(render_target) = window.get_single() else {
+        return;
+    };
+    if !render_target.has_swapchain() {
+        return;
+    }
+    // Statistics are disjoint across e.g. mode changes, so start over rather than counting across the gap
+    let Ok(statistics) = render_target.frame_statistics() else {
+        stats.last_sample = None;
+        return;
+    };
+
+    stats.record(
+        statistics.PresentCount,
+        statistics.SyncRefreshCount,
+        Instant::now(),
+    );
+}
+
+impl FramePacingStats {
+    /// Count the presents and refreshes since the previous sample, given the swapchain's cumulative counts at `now`.
+    fn record(&mut self, present_count: u32, sync_refresh_count: u32, now: Instant) {
+        let sample = (present_count, sync_refresh_count, now);
+        let Some((last_present_count, last_refresh_count, last_time)) =
+            self.last_sample.replace(sample)
+        else {
+            return;
+        };
+
+        let presents = present_count.wrapping_sub(last_present_count) as u64;
+        let refreshes = sync_refresh_count.wrapping_sub(last_refresh_count) as u64;
+        if presents == 0 {
+            return;
+        }
+        self.dropped += presents.saturating_sub(refreshes);
+        self.duplicated += refreshes.saturating_sub(presents);
+
+        let interval_ms = (now - last_time).as_secs_f32() * 1000.0 / presents as f32;
+        self.avg_interval_ms = if self.avg_interval_ms == 0.0 {
+            interval_ms
+        } else {
+            self.avg_interval_ms
+                + (interval_ms - self.avg_interval_ms) * SmoothFramePacer::SMOOTHING as f32
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const FRAME: Duration = Duration::from_millis(16);
+
+    #[test]
+    fn first_sample_only_sets_baseline() {
+        let mut stats = FramePacingStats::default();
+        stats.record(100, 100, Instant::now());
+        assert_eq!((stats.dropped, stats.duplicated), (0, 0));
+        assert_eq!(stats.avg_interval_ms, 0.0);
+    }
+
+    #[test]
+    fn counts_dropped_and_duplicated_frames() {
+        let start = Instant::now();
+        let mut stats = FramePacingStats::default();
+        stats.record(10, 10, start);
+        // 4 presents over 2 refreshes, so 2 frames were never shown
+        stats.record(14, 12, start + FRAME * 2);
+        assert_eq!((stats.dropped, stats.duplicated), (2, 0));
+        // 1 present over 3 refreshes, so the previous frame was shown 2 extra times
+        stats.record(15, 15, start + FRAME * 5);
+        assert_eq!((stats.dropped, stats.duplicated), (2, 2));
+        // No presents, e.g. while paused, counts nothing
+        stats.record(15, 20, start + FRAME * 10);
+        assert_eq!((stats.dropped, stats.duplicated), (2, 2));
+    }
+
+    #[test]
+    fn handles_counter_wraparound() {
+        let start = Instant::now();
+        let mut stats = FramePacingStats::default();
+        stats.record(u32::MAX, u32::MAX, start);
+        stats.record(1, 0, start + FRAME * 2);
+        assert_eq!((stats.dropped, stats.duplicated), (1, 0));
+    }
+
+    #[test]
+    fn averages_interval_between_presents() {
+        let start = Instant::now();
+        let mut stats = FramePacingStats::default();
+        stats.record(0, 0, start);
+        stats.record(2, 2, start + FRAME * 2);
+        assert!((stats.avg_interval_ms - 16.0).abs() < 0.01);
+
+        // Moves towards a slower interval, without jumping straight to it
+        stats.record(3, 4, start + FRAME * 4);
+        assert!(stats.avg_interval_ms > 16.0 && stats.avg_interval_ms < 32.0);
+    }
+}
@@ -0,0 +1,31 @@
+use crate::gpu::Gpu;
+use windows::Win32::Graphics::{
+    Direct3D::D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST, Direct3D12::ID3D12GraphicsCommandList7,
+};
+
+/// HLSL source of the vertex shader for [`Gpu::draw_fullscreen_triangle`], with entry point `FullscreenVSMain`.
+///
+/// Prepend or `#include` it in a pixel shader's source to take `FullscreenVertexOutput`, with `clipPosition` and a `uv`
+/// from (0, 0) at the top left to (1, 1) at the bottom right, as input.
+pub const FULLSCREEN_TRIANGLE_HLSL: &str = include_str!("../assets/fullscreen_triangle.hlsl");
+
+/// [`FULLSCREEN_TRIANGLE_HLSL`] compiled to DXIL, for the `VS` of a pipeline whose pixel shader is compiled with DXC.
+///
+/// Shaders compiled with FXC and DXC can't be mixed within a pipeline, so pixel shaders compiled with
+/// [`crate::compile_shader`] need the vertex shader compiled from [`FULLSCREEN_TRIANGLE_HLSL`] with it too.
+pub const FULLSCREEN_TRIANGLE_VS: &[u8] =
+    include_bytes!(concat!(env!("OUT_DIR"), "/fullscreen_triangle_vs.dxil"));
+
+impl Gpu {
+    /// Draw a single triangle covering the whole viewport, for post-processing and lighting passes, using a pipeline
+    /// with [`FULLSCREEN_TRIANGLE_VS`] as its vertex shader.
+    ///
+    /// No vertex or index buffers are needed, as the vertex shader derives positions from `SV_VertexID`. A single
+    /// triangle avoids the redundant pixel shading along the diagonal seam of a two-triangle quad.
+    pub fn draw_fullscreen_triangle(&self, command_list: &ID3D12GraphicsCommandList7) {
+        unsafe {
+            command_list.IASetPrimitiveTopology(D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
+            command_list.DrawInstanced(3, 1, 0, 0);
+        }
+    }
+}
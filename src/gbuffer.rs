@@ -0,0 +1,183 @@
+use crate::{depth::DepthTarget, error::DxError, gpu::Gpu, offscreen::OffscreenTargetGroup};
+use bevy::math::UVec2;
+use windows::Win32::{
+    Foundation::RECT,
+    Graphics::{Direct3D12::*, Dxgi::Common::*},
+};
+
+/// Render targets for deferred rendering: color attachments written by a geometry pass, plus a depth buffer, all read
+/// by a later lighting pass. The color attachments are an [`OffscreenTargetGroup`], and depth is a [`DepthTarget`].
+///
+/// Every attachment has an SRV in one shader-visible descriptor heap, color attachments first, in order, then depth,
+/// so that a single descriptor table starting at [`GBuffer::srv_table`] covers them all. Use
+/// [`GBuffer::color_srv_index`] and [`GBuffer::depth_srv_index`] to find each one within the table.
+///
+/// Attachments rest in `D3D12_RESOURCE_STATE_ALL_SHADER_RESOURCE`, so that the lighting pass can be either a pixel or
+/// compute shader, and are transitioned to RENDER_TARGET and DEPTH_WRITE between [`GBuffer::begin_geometry_pass`] and
+/// [`GBuffer::end_geometry_pass`].
+pub struct GBuffer {
+    colors: OffscreenTargetGroup,
+    depth: DepthTarget,
+    srv_heap: ID3D12DescriptorHeap,
+    srv_increment: u32,
+}
+
+impl GBuffer {
+    /// Albedo (RGBA8), world-space normal (RG16F, octahedral encoded), and motion vector (RG16F) attachments.
+    pub const DEFAULT_COLOR_FORMATS: [DXGI_FORMAT; 3] = [
+        DXGI_FORMAT_R8G8B8A8_UNORM,
+        DXGI_FORMAT_R16G16_FLOAT,
+        DXGI_FORMAT_R16G16_FLOAT,
+    ];
+    /// Format to use for `DSVFormat` when creating pipelines that render to the G-buffer.
+    pub const DSV_FORMAT: DXGI_FORMAT = DepthTarget::DSV_FORMAT;
+    /// Format of the depth SRV. Shaders see the depth as a `Texture2D<float>`.
+    pub const DEPTH_SRV_FORMAT: DXGI_FORMAT = DepthTarget::SRV_FORMAT;
+
+    /// Create a G-buffer with one color attachment for each of `color_formats`, in order, plus a depth attachment.
+    /// Between 1 and 8 color attachments are supported. See [`GBuffer::DEFAULT_COLOR_FORMATS`] for a typical set.
+    pub fn new(gpu: &Gpu, size: UVec2, color_formats: &[DXGI_FORMAT]) -> Result<Self, DxError> {
+        let colors = OffscreenTargetGroup::with_state(
+            gpu,
+            size,
+            color_formats,
+            D3D12_RESOURCE_STATE_ALL_SHADER_RESOURCE,
+            Some([0.0; 4]),
+        )?;
+        let depth = DepthTarget::with_state(gpu, size, D3D12_RESOURCE_STATE_ALL_SHADER_RESOURCE)?;
+
+        // The group and the depth target each have their own SRV heap, so write all of their SRVs to one more heap
+        let color_count = color_formats.len() as u32;
+        let srv_heap = gpu.create_descriptor_heap(
+            D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV,
+            color_count + 1,
+            true,
+        )?;
+        let srv_increment = unsafe {
+            gpu.device
+                .GetDescriptorHandleIncrementSize(D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV)
+        };
+        let mut srv = unsafe { srv_heap.GetCPUDescriptorHandleForHeapStart() };
+        colors.create_srvs(gpu, srv);
+        srv.ptr += (color_count * srv_increment) as usize;
+        depth.create_srv(gpu, srv);
+
+        Ok(Self {
+            colors,
+            depth,
+            srv_heap,
+            srv_increment,
+        })
+    }
+
+    /// Recreate every attachment if `size` differs from the current size, e.g. when the render resolution changes.
+    ///
+    /// The GPU must have finished using the old attachments.
+    pub fn resize(&mut self, gpu: &Gpu, size: UVec2) -> Result<(), DxError> {
+        if size != self.size() {
+            *self = Self::new(gpu, size, self.colors.formats())?;
+        }
+        Ok(())
+    }
+
+    /// Transition every attachment to be written to, bind them, and clear them to zero (and depth to 1.0).
+    pub fn begin_geometry_pass(&self, command_list: &ID3D12GraphicsCommandList7) {
+        self.depth.begin_render(command_list);
+        self.colors
+            .begin_render(command_list, Some(self.depth.dsv()));
+        for &rtv in self.colors.rtvs() {
+            unsafe { command_list.ClearRenderTargetView(rtv, &[0.0; 4], None) };
+        }
+    }
+
+    /// Transition every attachment back to be read by the lighting pass.
+    pub fn end_geometry_pass(&self, command_list: &ID3D12GraphicsCommandList7) {
+        self.colors.end_render(command_list);
+        self.depth.end_render(command_list);
+    }
+
+    /// Set `NumRenderTargets`, `RTVFormats`, and `DSVFormat` of a pipeline for the geometry pass.
+    pub fn set_pipeline_formats(&self, desc: &mut D3D12_GRAPHICS_PIPELINE_STATE_DESC) {
+        self.colors.set_pipeline_formats(desc);
+        desc.DSVFormat = Self::DSV_FORMAT;
+    }
+
+    /// Log an error if a pipeline for the geometry pass doesn't match [`GBuffer::set_pipeline_formats`], see
+    /// [`OffscreenTargetGroup::check_pipeline_formats`].
+    pub fn check_pipeline_formats(&self, desc: &D3D12_GRAPHICS_PIPELINE_STATE_DESC) {
+        self.colors.check_pipeline_formats(desc, Self::DSV_FORMAT);
+    }
+
+    /// The color attachments.
+    pub fn colors(&self) -> &OffscreenTargetGroup {
+        &self.colors
+    }
+
+    /// The depth attachment.
+    pub fn depth(&self) -> &DepthTarget {
+        &self.depth
+    }
+
+    pub fn color_textures(&self) -> &[ID3D12Resource] {
+        self.colors.textures()
+    }
+
+    pub fn depth_texture(&self) -> &ID3D12Resource {
+        self.depth.texture()
+    }
+
+    pub fn color_formats(&self) -> &[DXGI_FORMAT] {
+        self.colors.formats()
+    }
+
+    /// RTVs of the color attachments, in order. They're contiguous in an RTV heap.
+    pub fn rtvs(&self) -> &[D3D12_CPU_DESCRIPTOR_HANDLE] {
+        self.colors.rtvs()
+    }
+
+    pub fn dsv(&self) -> D3D12_CPU_DESCRIPTOR_HANDLE {
+        self.depth.dsv()
+    }
+
+    /// Shader-visible descriptor heap containing only the attachments' SRVs.
+    pub fn srv_heap(&self) -> &ID3D12DescriptorHeap {
+        &self.srv_heap
+    }
+
+    /// Start of the SRV descriptor table, for binding every attachment at once.
+    pub fn srv_table(&self) -> D3D12_GPU_DESCRIPTOR_HANDLE {
+        unsafe { self.srv_heap.GetGPUDescriptorHandleForHeapStart() }
+    }
+
+    /// Index of color attachment `attachment`'s SRV within [`GBuffer::srv_table`], e.g. its `t` register offset.
+    pub fn color_srv_index(&self, attachment: usize) -> u32 {
+        assert!(attachment < self.colors.textures().len());
+        attachment as u32
+    }
+
+    /// Index of the depth attachment's SRV within [`GBuffer::srv_table`], after every color attachment.
+    pub fn depth_srv_index(&self) -> u32 {
+        self.colors.textures().len() as u32
+    }
+
+    /// GPU handle of the SRV at `index` within [`GBuffer::srv_table`].
+    pub fn srv(&self, index: u32) -> D3D12_GPU_DESCRIPTOR_HANDLE {
+        let mut srv = self.srv_table();
+        srv.ptr += (index * self.srv_increment) as u64;
+        srv
+    }
+
+    pub fn size(&self) -> UVec2 {
+        self.colors.size()
+    }
+
+    /// Viewport covering every attachment.
+    pub fn viewport(&self) -> D3D12_VIEWPORT {
+        self.colors.viewport()
+    }
+
+    /// Scissor rect covering every attachment.
+    pub fn scissor_rect(&self) -> RECT {
+        self.colors.scissor_rect()
+    }
+}
@@ -1,45 +1,174 @@
+use crate::{
+    capabilities::GpuCapabilities,
+    clear_uav::UavClearDescriptors,
+    error::DxError,
+    fence_waiter::FenceWaiter,
+    format_support::resource_creation_error,
+    mapped_buffer::MappedBuffer,
+    mips::MipGenerator,
+    pipeline_cache::PipelineCache,
+    resource_tracker::{transition_barrier, uav_barrier},
+    swapchain::WindowRenderTarget,
+};
 use bevy::prelude::{error, info, warn, Resource};
 use std::{
     backtrace::{Backtrace, BacktraceStatus},
+    mem,
     os::raw::c_void,
-    ptr, slice, str,
+    path::PathBuf,
+    slice, str,
+    sync::{Arc, Mutex, OnceLock},
+    time::Duration,
 };
 use windows::{
-    core::{Error, Interface, PCSTR, PWSTR},
+    core::{w, Error, Interface, HSTRING, PCSTR, PWSTR},
     Win32::{
-        Foundation::HANDLE,
+        Foundation::{CloseHandle, ERROR_SUCCESS, E_INVALIDARG, HANDLE, WAIT_TIMEOUT},
         Graphics::{
             Direct3D::D3D_FEATURE_LEVEL_12_2,
             Direct3D12::*,
             Dxgi::{
+                Common::{DXGI_FORMAT, DXGI_SAMPLE_DESC},
                 CreateDXGIFactory2, IDXGIAdapter4, IDXGIDevice, IDXGIFactory7,
-                DXGI_CREATE_FACTORY_DEBUG, DXGI_GPU_PREFERENCE_HIGH_PERFORMANCE,
+                DXGI_CREATE_FACTORY_DEBUG, DXGI_ERROR_WAIT_TIMEOUT,
+                DXGI_FEATURE_PRESENT_ALLOW_TEARING, DXGI_GPU_PREFERENCE,
+                DXGI_GPU_PREFERENCE_HIGH_PERFORMANCE,
             },
         },
-        System::Threading::{CreateEventW, WaitForSingleObjectEx, INFINITE},
+        System::{
+            Registry::{RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_DWORD},
+            Threading::{CreateEventW, WaitForSingleObjectEx, INFINITE},
+        },
     },
 };
 
+/// Number of frames the CPU may record ahead of the GPU.
+pub const FRAMES_IN_FLIGHT: usize = 1; // TODO: More than 1 frame in flight
+
+/// Settings used when creating the [`Gpu`].
+#[derive(Clone, Debug)]
+pub struct GpuConfig {
+    /// Which adapter to prefer when multiple GPUs are available.
+    ///
+    /// Use `DXGI_GPU_PREFERENCE_MINIMUM_POWER` to prefer an integrated GPU on laptops. Defaults to
+    /// `DXGI_GPU_PREFERENCE_HIGH_PERFORMANCE`.
+    pub gpu_preference: DXGI_GPU_PREFERENCE,
+    /// Scheduling priority of the command queue. Defaults to `D3D12_COMMAND_QUEUE_PRIORITY_NORMAL`.
+    ///
+    /// `D3D12_COMMAND_QUEUE_PRIORITY_GLOBAL_REALTIME` requires elevated privileges, and falls back to
+    /// `D3D12_COMMAND_QUEUE_PRIORITY_HIGH` with a warning if unavailable.
+    pub queue_priority: D3D12_COMMAND_QUEUE_PRIORITY,
+    /// Disable the GPU timeout (TDR) for the command queue, allowing long-running work such as bake passes
+    /// to exceed the default 2 second limit. Defaults to false.
+    pub disable_gpu_timeout: bool,
+    /// File to persist compiled pipelines to across launches, see [`Gpu::pipeline_cache`]. Defaults to `None`,
+    /// in which case pipelines are only cached in memory.
+    pub pipeline_cache_path: Option<PathBuf>,
+    /// Maximum time to block waiting on the GPU or swapchain before assuming the GPU has hung, see
+    /// [`Gpu::wait_for_fence`]. Defaults to `None`, which waits forever.
+    ///
+    /// Waits take whole milliseconds, so the timeout is rounded up to the next millisecond, and is at least 1 ms, as a
+    /// 0 ms wait would only poll, and time out even when the GPU is keeping up.
+    pub fence_timeout: Option<Duration>,
+    /// Enable the D3D12 debug layer, with GPU-based validation, and log its messages. Also enables this crate's own
+    /// validation, such as [`crate::WindowRenderTarget::check_pipeline_formats`]. Defaults to true in debug builds,
+    /// and false in release builds, as validation slows down rendering considerably.
+    pub debug_layer: bool,
+    /// Least severe debug layer message severity to capture a backtrace for when logging, with
+    /// [`GpuConfig::debug_layer`]. Defaults to `Some(D3D12_MESSAGE_SEVERITY_ERROR)`, i.e. errors and corruption.
+    ///
+    /// Capturing a backtrace is slow, so including warnings or info messages can make frames with many messages
+    /// crawl. `None` disables backtraces entirely. Backtraces are also only captured with `RUST_BACKTRACE=1` set.
+    pub debug_message_backtrace_severity: Option<D3D12_MESSAGE_SEVERITY>,
+    /// Debug layer message severities to break into an attached debugger on, with [`GpuConfig::debug_layer`], e.g.
+    /// `vec![D3D12_MESSAGE_SEVERITY_CORRUPTION, D3D12_MESSAGE_SEVERITY_ERROR]`. Defaults to none, as messages are
+    /// already logged, and breaking without a debugger attached crashes the process.
+    pub debug_break_severities: Vec<D3D12_MESSAGE_SEVERITY>,
+    /// Record the barriers this crate makes for swapchain backbuffers, e.g. in [`crate::begin_frame`] and
+    /// [`crate::WindowRenderTarget::transition_rtv`], with enhanced barriers and texture layouts instead of legacy
+    /// resource states. Falls back to legacy barriers with a warning if
+    /// [`GpuCapabilities::enhanced_barriers`] is false. Defaults to false.
+    ///
+    /// Enable when migrating rendering code to enhanced barriers, see [`crate::WindowRenderTarget::transition_rtv`]
+    /// for the rules on mixing the two models on backbuffers.
+    pub use_enhanced_barriers: bool,
+    /// Make [`Gpu::create_buffer`], [`Gpu::create_texture_2d`], and [`Gpu::create_texture_with_data`] fail with
+    /// `E_OUTOFMEMORY` instead of creating a resource that would take this process's memory usage over this fraction
+    /// of its video memory budget, see [`Gpu::video_memory_info`]. Defaults to `None`, which doesn't check.
+    ///
+    /// Going over budget makes the OS page resources out to system memory, which can slow rendering to a crawl, and
+    /// oversized allocations can fail outright. The check catches e.g. a render target sized for the wrong DPI
+    /// scale, with an error naming the resource's size, at the cost of querying the budget on each creation.
+    pub max_memory_budget_fraction: Option<f32>,
+    /// Name objects with [`Gpu::name_object`] in release builds too, where it is otherwise a no-op. Defaults to false.
+    ///
+    /// Enable to find resources by name in PIX captures of optimized builds, at the cost of a string allocation and
+    /// driver call per named object.
+    pub name_objects_in_release: bool,
+}
+
+impl Default for GpuConfig {
+    fn default() -> Self {
+        Self {
+            gpu_preference: DXGI_GPU_PREFERENCE_HIGH_PERFORMANCE,
+            queue_priority: D3D12_COMMAND_QUEUE_PRIORITY_NORMAL,
+            disable_gpu_timeout: false,
+            pipeline_cache_path: None,
+            fence_timeout: None,
+            debug_layer: cfg!(debug_assertions),
+            debug_message_backtrace_severity: Some(D3D12_MESSAGE_SEVERITY_ERROR),
+            debug_break_severities: Vec::new(),
+            use_enhanced_barriers: false,
+            max_memory_budget_fraction: None,
+            name_objects_in_release: false,
+        }
+    }
+}
+
 /// Central interface for managing GPU resources and rendering work.
+///
+/// Rendering work is recorded into a single command list, which each frame goes through:
+/// 1. [`Gpu::reset_commands`], once the GPU has finished the previous frame's commands (see [`Gpu::wait_for_fence`]).
+/// 2. Recording, via the list returned by [`Gpu::reset_commands`] or [`Gpu::command_list`].
+/// 3. [`Gpu::execute_command_list`], which closes the list and submits it. No more commands can be recorded.
+/// 4. [`Gpu::signal_fence`], after any presents, so that the next frame can wait on it.
+///
+/// Recording into a closed list, or resetting it while the GPU is still executing it, is an error. Command lists
+/// recorded elsewhere, e.g. for dependent passes, can be submitted with [`Gpu::submit`].
 #[derive(Resource)]
 pub struct Gpu {
     pub factory: IDXGIFactory7,
     pub device: ID3D12Device9,
     pub queue: ID3D12CommandQueue,
+    pub(crate) adapter: IDXGIAdapter4,
     // TODO: More than 1 frame in flight
     command_allocator: ID3D12CommandAllocator,
     command_list: ID3D12GraphicsCommandList7,
-    fence: ID3D12Fence,
+    pub(crate) fence: ID3D12Fence,
     fence_event: HANDLE,
     fence_counter: u64,
+    supports_tearing: bool,
+    capabilities: GpuCapabilities,
+    enhanced_barriers: bool,
+    pub(crate) max_memory_budget_fraction: Option<f32>,
+    fence_timeout: u32,
+    debug_layer: bool,
+    name_objects: bool,
+    /// Identifies the debug layer message callback, to unregister it on drop.
+    debug_callback_cookie: Option<u32>,
+    pub(crate) pipeline_cache: Arc<PipelineCache>,
+    pub(crate) mip_generator: Option<MipGenerator>,
+    pub(crate) uav_clear_descriptors: Mutex<Option<UavClearDescriptors>>,
+    /// Runs [`Gpu::on_fence_complete`] callbacks, started by the first callback that has to wait.
+    fence_waiter: OnceLock<FenceWaiter>,
 }
 
 impl Gpu {
-    pub fn new() -> Result<Self, Error> {
+    pub fn new(config: &GpuConfig) -> Result<Self, DxError> {
         unsafe {
             // Debug layers
             let mut factory_flags = 0;
-            if cfg!(debug_assertions) {
+            if config.debug_layer {
                 let mut debug_interface: Option<ID3D12Debug3> = None;
                 D3D12GetDebugInterface(&mut debug_interface)?;
                 let debug_interface = debug_interface.unwrap();
@@ -52,9 +181,20 @@ impl Gpu {
             // Factory
             let factory: IDXGIFactory7 = CreateDXGIFactory2(factory_flags)?;
 
+            // Tearing support
+            let mut allow_tearing = 0i32;
+            let supports_tearing = factory
+                .CheckFeatureSupport(
+                    DXGI_FEATURE_PRESENT_ALLOW_TEARING,
+                    &mut allow_tearing as *mut _ as *mut c_void,
+                    mem::size_of_val(&allow_tearing) as u32,
+                )
+                .is_ok()
+                && allow_tearing != 0;
+
             // Adapter
             let adapter: IDXGIAdapter4 =
-                factory.EnumAdapterByGpuPreference(0, DXGI_GPU_PREFERENCE_HIGH_PERFORMANCE)?;
+                factory.EnumAdapterByGpuPreference(0, config.gpu_preference)?;
 
             // Device
             let mut device: Option<ID3D12Device9> = None;
@@ -62,30 +202,51 @@ impl Gpu {
             let device = device.unwrap();
 
             // Debug layer callback
-            if cfg!(debug_assertions) {
+            let mut debug_callback_cookie = None;
+            if config.debug_layer {
                 let info_queue = device.cast::<ID3D12InfoQueue1>()?;
-                info_queue.SetBreakOnSeverity(D3D12_MESSAGE_SEVERITY_ERROR, true)?;
-                info_queue.SetBreakOnSeverity(D3D12_MESSAGE_SEVERITY_CORRUPTION, true)?;
+                for severity in &config.debug_break_severities {
+                    info_queue.SetBreakOnSeverity(*severity, true)?;
+                }
+                // Severities are ordered from most to least severe, so pass the first one not to capture backtraces for
+                // as the callback's context, to avoid needing to keep an allocation alive
+                let no_backtrace_severity = config
+                    .debug_message_backtrace_severity
+                    .map_or(0, |severity| severity.0 + 1);
                 let mut cookie = 0;
                 info_queue.RegisterMessageCallback(
                     Some(log_debug_layer_message),
                     D3D12_MESSAGE_CALLBACK_FLAG_NONE,
-                    ptr::null_mut(),
+                    no_backtrace_severity as usize as *mut c_void,
                     &mut cookie,
                 )?;
                 if cookie == 0 {
                     panic!("BevyDirectX: Failed to register debug layer callback");
                 }
+                debug_callback_cookie = Some(cookie);
             }
 
             // TODO: DXGI debug layers
 
             // Queue
-            let queue: ID3D12CommandQueue =
-                device.CreateCommandQueue(&D3D12_COMMAND_QUEUE_DESC {
-                    Type: D3D12_COMMAND_LIST_TYPE_DIRECT,
-                    ..Default::default()
-                })?;
+            let mut queue_desc = D3D12_COMMAND_QUEUE_DESC {
+                Type: D3D12_COMMAND_LIST_TYPE_DIRECT,
+                Priority: config.queue_priority.0,
+                Flags: if config.disable_gpu_timeout {
+                    D3D12_COMMAND_QUEUE_FLAG_DISABLE_GPU_TIMEOUT
+                } else {
+                    D3D12_COMMAND_QUEUE_FLAG_NONE
+                },
+                NodeMask: 0,
+            };
+            let queue: ID3D12CommandQueue = match device.CreateCommandQueue(&queue_desc) {
+                Err(e) if config.queue_priority == D3D12_COMMAND_QUEUE_PRIORITY_GLOBAL_REALTIME => {
+                    warn!("BevyDirectX: Failed to create command queue with global realtime priority, falling back to high priority: {e}");
+                    queue_desc.Priority = D3D12_COMMAND_QUEUE_PRIORITY_HIGH.0;
+                    device.CreateCommandQueue(&queue_desc)?
+                }
+                queue => queue?,
+            };
 
             // Command allocator and list
             let command_allocator =
@@ -98,6 +259,12 @@ impl Gpu {
             )?;
             command_list.Close()?;
 
+            // Pipeline cache
+            let pipeline_cache = Arc::new(PipelineCache::new(
+                &device,
+                config.pipeline_cache_path.clone(),
+            )?);
+
             // Fence
             let fence = device.CreateFence(0, D3D12_FENCE_FLAG_NONE)?;
             let fence_event = CreateEventW(None, false, false, None)?;
@@ -124,23 +291,139 @@ impl Gpu {
                 adapter_info.SharedSystemMemory / 1_000_000,
             );
 
+            // Query and log capabilities
+            let capabilities = GpuCapabilities::new(&device);
+            info!("{capabilities:?}");
+            if config.use_enhanced_barriers && !capabilities.enhanced_barriers {
+                warn!(
+                    "BevyDirectX: Enhanced barriers are unsupported, using legacy barriers instead"
+                );
+            }
+
             Ok(Self {
                 factory,
                 device,
                 queue,
+                adapter,
                 command_allocator,
                 command_list,
                 fence,
                 fence_event,
                 fence_counter: 0,
+                supports_tearing,
+                capabilities,
+                enhanced_barriers: config.use_enhanced_barriers && capabilities.enhanced_barriers,
+                max_memory_budget_fraction: config.max_memory_budget_fraction,
+                fence_timeout: fence_timeout_millis(config.fence_timeout),
+                debug_layer: config.debug_layer,
+                name_objects: cfg!(debug_assertions) || config.name_objects_in_release,
+                debug_callback_cookie,
+                pipeline_cache,
+                mip_generator: None,
+                uav_clear_descriptors: Mutex::new(None),
+                fence_waiter: OnceLock::new(),
             })
         }
     }
 
+    /// Whether the display hardware and driver support tearing (presenting with vsync off).
+    pub fn supports_tearing(&self) -> bool {
+        self.supports_tearing
+    }
+
+    /// Optional features supported by the GPU and driver, for choosing between rendering code paths.
+    pub fn capabilities(&self) -> &GpuCapabilities {
+        &self.capabilities
+    }
+
+    /// Whether barriers for swapchain backbuffers are recorded with enhanced barriers, see
+    /// [`GpuConfig::use_enhanced_barriers`].
+    pub fn uses_enhanced_barriers(&self) -> bool {
+        self.enhanced_barriers
+    }
+
+    /// Hint to the driver whether to save power by disabling background work, such as shader recompilation
+    /// and optimization.
+    pub fn set_power_saving(&self, enabled: bool) -> Result<(), DxError> {
+        let mode = if enabled {
+            D3D12_BACKGROUND_PROCESSING_MODE_DISABLE_BACKGROUND_WORK
+        } else {
+            D3D12_BACKGROUND_PROCESSING_MODE_ALLOWED
+        };
+
+        unsafe {
+            self.device.SetBackgroundProcessingMode(
+                mode,
+                D3D12_MEASUREMENTS_ACTION_KEEP_ALL,
+                None,
+                None,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Lock the GPU's clocks to a fixed rate, or unlock them again, so that GPU timings are reproducible between runs
+    /// when profiling. Stable clocks are typically lower than boost clocks, so never enable this outside of profiling.
+    ///
+    /// Requires Windows developer mode (Settings > System > For developers). Without it, `SetStablePowerState`
+    /// removes the device, so this instead does nothing and logs a warning. The setting applies to the whole GPU, not
+    /// just this process, until disabled or the device is destroyed.
+    pub fn set_stable_power_state(&self, enable: bool) {
+        if !developer_mode_enabled() {
+            warn!("BevyDirectX: Setting a stable power state requires Windows developer mode to be enabled");
+            return;
+        }
+
+        if let Err(e) = unsafe { self.device.SetStablePowerState(enable) } {
+            warn!("BevyDirectX: Failed to set stable power state: {e}");
+        }
+    }
+
+    /// Name `object`, e.g. a resource from [`Gpu::create_buffer`], so that PIX captures and debug layer messages refer
+    /// to it by `name`. Does nothing in release builds, unless [`GpuConfig::name_objects_in_release`] is set.
+    pub fn name_object(&self, object: &impl Interface, name: &str) {
+        if !self.name_objects {
+            return;
+        }
+        if let Ok(object) = object.cast::<ID3D12Object>() {
+            let _ = unsafe { object.SetName(&HSTRING::from(name)) };
+        }
+    }
+
+    /// [`Gpu::name_object`] with a consistent `prefix[index]` name, for one of many similar objects, e.g.
+    /// `"PerFrameConstants"` and the frame index, or `"MaterialAlbedo"` and the material index, so that they're easy to
+    /// search for and tell apart in captures.
+    pub fn name_resource_indexed(&self, object: &impl Interface, prefix: &str, index: usize) {
+        // Skip formatting the name when it would be thrown away
+        if !self.name_objects {
+            return;
+        }
+        self.name_object(object, &format!("{prefix}[{index}]"));
+    }
+
+    /// Whether [`GpuConfig::debug_layer`] is enabled.
+    pub fn debug_layer_enabled(&self) -> bool {
+        self.debug_layer
+    }
+
+    /// Cache for creating pipelines, persisted to [`GpuConfig::pipeline_cache_path`] if set.
+    pub fn pipeline_cache(&self) -> &PipelineCache {
+        &self.pipeline_cache
+    }
+
+    /// The command list, without resetting it. Only valid to record into between [`Gpu::reset_commands`] and
+    /// [`Gpu::execute_command_list`], but can be used at any time to query it or cast it to other interfaces.
+    pub fn command_list(&self) -> &ID3D12GraphicsCommandList7 {
+        &self.command_list
+    }
+
+    /// Reset the command list for recording a new frame, discarding previously recorded commands.
+    ///
+    /// The GPU must have finished executing the previous commands, e.g. via [`Gpu::wait_for_fence`].
     pub fn reset_commands(
         &self,
         pipeline: Option<&ID3D12PipelineState>,
-    ) -> Result<&ID3D12GraphicsCommandList7, Error> {
+    ) -> Result<&ID3D12GraphicsCommandList7, DxError> {
         unsafe {
             self.command_allocator.Reset()?;
             self.command_list.Reset(&self.command_allocator, pipeline)?;
@@ -149,25 +432,238 @@ impl Gpu {
         Ok(&self.command_list)
     }
 
-    pub fn signal_fence(&mut self) -> Result<(), Error> {
+    /// Reset the command list to record more commands into the same allocator, keeping the commands recorded since
+    /// the last [`Gpu::reset_commands`], e.g. to submit a frame as several command lists so the GPU can start on the
+    /// first while the rest are recorded.
+    ///
+    /// The list must have been closed, e.g. by [`Gpu::execute_command_list`]. Unlike [`Gpu::reset_commands`], the GPU
+    /// doesn't need to have finished the earlier commands, as the allocator still holds them. The allocator's memory
+    /// grows until the next [`Gpu::reset_commands`], so call that once per frame.
+    pub fn reset_command_list_only(
+        &self,
+        pipeline: Option<&ID3D12PipelineState>,
+    ) -> Result<&ID3D12GraphicsCommandList7, DxError> {
+        unsafe { self.command_list.Reset(&self.command_allocator, pipeline) }?;
+        Ok(&self.command_list)
+    }
+
+    /// Signal the fence on the queue once all previously submitted work completes, for [`Gpu::wait_for_fence`].
+    pub fn signal_fence(&mut self) -> Result<(), DxError> {
         self.fence_counter += 1;
 
         unsafe {
             self.queue.Signal(&self.fence, self.fence_counter)?;
             self.fence
-                .SetEventOnCompletion(self.fence_counter, self.fence_event)
+                .SetEventOnCompletion(self.fence_counter, self.fence_event)?;
         }
+        Ok(())
     }
 
-    pub fn wait_for_fence(&self) {
+    /// Block until the GPU has finished all work submitted before the last [`Gpu::signal_fence`].
+    ///
+    /// If [`GpuConfig::fence_timeout`] elapses first, the GPU is assumed to have hung and an error is returned,
+    /// which is the device removed reason if the device was lost.
+    pub fn wait_for_fence(&self) -> Result<(), DxError> {
         unsafe {
-            if self.fence.GetCompletedValue() < self.fence_counter {
-                WaitForSingleObjectEx(self.fence_event, INFINITE, true);
+            // The event is signaled once per signaled value, so may be set by an earlier value, e.g. from Gpu::submit
+            while self.fence.GetCompletedValue() < self.fence_counter {
+                if WaitForSingleObjectEx(self.fence_event, self.fence_timeout, true) == WAIT_TIMEOUT
+                {
+                    error!("BevyDirectX: GPU wait timed out — possible hang");
+                    self.device.GetDeviceRemovedReason()?;
+                    return Err(Error::from(DXGI_ERROR_WAIT_TIMEOUT).into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Block until the GPU has finished all work submitted so far, e.g. before teardown, or before releasing
+    /// resources that may still be in use.
+    ///
+    /// This stalls the CPU until the GPU's queue is empty, losing any CPU/GPU overlap, so use it sparingly and never
+    /// every frame. Prefer [`Gpu::wait_for_frame`] within the frame loop.
+    pub fn flush(&mut self) -> Result<(), DxError> {
+        self.signal_fence()?;
+        self.wait_for_fence()
+    }
+
+    /// Block until the GPU has finished the previous frame's commands, so that its per-frame resources can be reused
+    /// and the command list can be reset. See [`crate::wait_for_ready_frame`] for where this fits into the frame.
+    pub fn wait_for_frame(&self) -> Result<(), DxError> {
+        // TODO: Wait on the fence value of FRAMES_IN_FLIGHT frames ago, once there's more than 1 frame in flight
+        self.wait_for_fence()
+    }
+
+    /// Value the fence will be signaled with by the next [`Gpu::signal_fence`]. Work submitted before then is
+    /// complete once [`Gpu::completed_fence_value`] reaches this value.
+    pub fn next_fence_value(&self) -> u64 {
+        self.fence_counter + 1
+    }
+
+    /// Latest fence value the GPU has finished executing up to.
+    pub fn completed_fence_value(&self) -> u64 {
+        unsafe { self.fence.GetCompletedValue() }
+    }
+
+    /// Run `callback` once the fence reaches `value`, e.g. [`Gpu::next_fence_value`] before submitting an upload, to
+    /// mark an asset ready without blocking on or polling the fence.
+    ///
+    /// If the fence already reached `value`, `callback` runs immediately on the calling thread. Otherwise it's queued
+    /// for a single waiter thread shared by every callback, which runs each once the fence reaches its value, in order
+    /// of value, regardless of other callbacks still waiting. So `callback` must be `Send`, and should be short, e.g.
+    /// sending the asset's ID over a channel to a system that updates the world or sends a Bevy `Event`.
+    ///
+    /// If [`GpuConfig::fence_timeout`] elapses first, the GPU is assumed to have hung, and `callback` is dropped
+    /// without running. Callbacks still waiting when the Gpu is dropped are also dropped without running.
+    pub fn on_fence_complete(
+        &self,
+        value: u64,
+        callback: impl FnOnce() + Send + 'static,
+    ) -> Result<(), DxError> {
+        if self.completed_fence_value() >= value {
+            callback();
+            return Ok(());
+        }
+
+        let fence_waiter = match self.fence_waiter.get() {
+            Some(fence_waiter) => fence_waiter,
+            None => {
+                let fence_waiter = FenceWaiter::new(self.fence.clone(), self.fence_timeout)?;
+                // Another thread may have started one first, in which case this one is dropped and stops
+                let _ = self.fence_waiter.set(fence_waiter);
+                self.fence_waiter.get().unwrap()
             }
+        };
+        fence_waiter.push(value, Box::new(callback));
+        Ok(())
+    }
+
+    /// Timeout in milliseconds to use when blocking on GPU work, from [`GpuConfig::fence_timeout`].
+    pub fn fence_timeout(&self) -> u32 {
+        self.fence_timeout
+    }
+
+    /// Make `queue` wait on the GPU until `fence` reaches `value`, without blocking the CPU.
+    ///
+    /// Work submitted to `queue` after this call will not start until the fence is signaled, e.g. by another queue.
+    pub fn queue_wait(
+        &self,
+        queue: &ID3D12CommandQueue,
+        fence: &ID3D12Fence,
+        value: u64,
+    ) -> Result<(), DxError> {
+        unsafe { queue.Wait(fence, value)? };
+        Ok(())
+    }
+
+    /// Record a UAV barrier, ensuring all prior unordered access writes to `resource` complete before subsequent
+    /// unordered access reads or writes to it. If `resource` is `None`, waits for all unordered access instead.
+    ///
+    /// Needed between consecutive dispatches or draws that access the same UAV, as a transition barrier can't be
+    /// used when the resource stays in the unordered access state.
+    pub fn uav_barrier(
+        &self,
+        command_list: &ID3D12GraphicsCommandList7,
+        resource: Option<&ID3D12Resource>,
+    ) {
+        unsafe { command_list.ResourceBarrier(&[uav_barrier(resource)]) };
+    }
+
+    /// Record that the contents of `resource`, or of `region` of it, are no longer needed, leaving them undefined.
+    ///
+    /// Usually a performance hint before fully overwriting a render target, depth stencil, or UAV texture, letting the
+    /// GPU skip loading or decompressing the old contents. It's required for correctness when a render target or
+    /// depth stencil texture sharing memory with other resources is first used after an aliasing barrier, e.g. in a
+    /// [`crate::TransientResourcePool`], or created with `D3D12_HEAP_FLAG_CREATE_NOT_ZEROED`, unless it's cleared or
+    /// fully copied to instead, as its compression metadata is otherwise garbage.
+    ///
+    /// Render targets must be in the RENDER_TARGET state, depth stencils in DEPTH_WRITE, and other textures in
+    /// UNORDERED_ACCESS. `region` must be `None` for buffers.
+    pub fn discard_resource(
+        &self,
+        command_list: &ID3D12GraphicsCommandList7,
+        resource: &ID3D12Resource,
+        region: Option<&D3D12_DISCARD_REGION>,
+    ) {
+        unsafe { command_list.DiscardResource(resource, region.map(|region| region as *const _)) };
+    }
+
+    /// Set the graphics root constants parameter at `root_parameter_index` to `data`, which must be a multiple of 4
+    /// bytes, matching the root signature's `Num32BitValues`.
+    pub fn set_root_constants<T: Copy>(
+        &self,
+        command_list: &ID3D12GraphicsCommandList7,
+        root_parameter_index: u32,
+        data: &T,
+    ) {
+        let count = root_constant_count::<T>();
+        unsafe {
+            command_list.SetGraphicsRoot32BitConstants(
+                root_parameter_index,
+                count,
+                data as *const T as *const c_void,
+                0,
+            )
+        };
+    }
+
+    /// Set the compute root constants parameter at `root_parameter_index` to `data`, see
+    /// [`Gpu::set_root_constants`].
+    pub fn set_compute_root_constants<T: Copy>(
+        &self,
+        command_list: &ID3D12GraphicsCommandList7,
+        root_parameter_index: u32,
+        data: &T,
+    ) {
+        let count = root_constant_count::<T>();
+        unsafe {
+            command_list.SetComputeRoot32BitConstants(
+                root_parameter_index,
+                count,
+                data as *const T as *const c_void,
+                0,
+            )
+        };
+    }
+
+    /// Write 32-bit `(gpu_virtual_address, value)` pairs directly from the command list, without an upload buffer,
+    /// e.g. for a single counter or flag updated each frame. Writes happen in order with the surrounding commands.
+    ///
+    /// Addresses must be 4-byte aligned. The destination buffers must be in the COPY_DEST state, or COMMON, which
+    /// buffers are implicitly promoted from. For more than a few values, copying from an
+    /// [`crate::UploadArena`] allocation is faster.
+    pub fn write_buffer_immediate(
+        &self,
+        command_list: &ID3D12GraphicsCommandList7,
+        writes: &[(u64, u32)],
+    ) {
+        let parameters: Vec<_> = writes
+            .iter()
+            .map(|&(address, value)| {
+                assert!(
+                    address % 4 == 0,
+                    "BevyDirectX: WriteBufferImmediate address must be 4-byte aligned, was {address:#x}"
+                );
+                D3D12_WRITEBUFFERIMMEDIATE_PARAMETER {
+                    Dest: address,
+                    Value: value,
+                }
+            })
+            .collect();
+        if !parameters.is_empty() {
+            unsafe {
+                command_list.WriteBufferImmediate(
+                    parameters.len() as u32,
+                    parameters.as_ptr(),
+                    None,
+                )
+            };
         }
     }
 
-    pub fn execute_command_list(&self) -> Result<(), Error> {
+    /// Close the command list and submit it to the queue. It must be reset before recording again.
+    pub fn execute_command_list(&self) -> Result<(), DxError> {
         unsafe {
             self.command_list.Close()?;
             self.queue
@@ -176,12 +672,307 @@ impl Gpu {
         Ok(())
     }
 
+    /// Submit the command list and present `render_target`, then signal the fence, in that order.
+    ///
+    /// Presenting before signaling means [`Gpu::wait_for_fence`] next frame also covers any GPU work the present
+    /// queued, such as composition of the backbuffer. The individual steps can still be called separately.
+    pub fn submit_and_present(
+        &mut self,
+        render_target: &WindowRenderTarget,
+    ) -> Result<(), DxError> {
+        self.submit_and_present_all([render_target])
+    }
+
+    /// Submit the command list once, present each of `render_targets`, then signal the fence once, so that the CPU
+    /// cost of ending a frame stays flat as the number of windows grows.
+    ///
+    /// The command list must hold the whole frame for every window, recorded in this order:
+    /// 1. Transition every window's [`WindowRenderTarget::rtv`] from PRESENT to RENDER_TARGET.
+    /// 2. Draw to each window.
+    /// 3. Transition every render target back to PRESENT, and call [`WindowRenderTarget::upscale_to_backbuffer`] on
+    ///    each.
+    ///
+    /// All windows are presented even if one fails, and the fence is always signaled afterwards, so that the next
+    /// [`Gpu::wait_for_fence`] covers the submitted work. The first present error is returned.
+    pub fn submit_and_present_all<'a>(
+        &mut self,
+        render_targets: impl IntoIterator<Item = &'a WindowRenderTarget>,
+    ) -> Result<(), DxError> {
+        self.execute_command_list()?;
+        let mut result = Ok(());
+        for render_target in render_targets {
+            if let Err(e) = render_target.present() {
+                result = result.and(Err(e));
+            }
+        }
+        self.signal_fence()?;
+        result
+    }
+
+    /// Create a buffer in its own implicit heap. Name it with [`Gpu::name_object`] to find it in captures, or use
+    /// [`Gpu::create_buffer_named`].
+    pub fn create_buffer(
+        &self,
+        size: u64,
+        heap_type: D3D12_HEAP_TYPE,
+        flags: D3D12_RESOURCE_FLAGS,
+        initial_state: D3D12_RESOURCE_STATES,
+    ) -> Result<ID3D12Resource, DxError> {
+        self.create_buffer_with_heap_flags(
+            size,
+            heap_type,
+            flags,
+            D3D12_HEAP_FLAG_NONE,
+            initial_state,
+        )
+    }
+
+    /// [`Gpu::create_buffer`], named `label` with [`Gpu::name_object`].
+    pub fn create_buffer_named(
+        &self,
+        label: &str,
+        size: u64,
+        heap_type: D3D12_HEAP_TYPE,
+        flags: D3D12_RESOURCE_FLAGS,
+        initial_state: D3D12_RESOURCE_STATES,
+    ) -> Result<ID3D12Resource, DxError> {
+        let buffer = self.create_buffer(size, heap_type, flags, initial_state)?;
+        self.name_object(&buffer, label);
+        Ok(buffer)
+    }
+
+    /// [`Gpu::create_buffer`], with flags for the implicit heap, e.g.:
+    /// * `D3D12_HEAP_FLAG_CREATE_NOT_ZEROED` skips zeroing the memory, which makes allocating large scratch or
+    ///   transient buffers much faster. The contents start undefined. Requires Windows 10 2004 or newer.
+    /// * `D3D12_HEAP_FLAG_ALLOW_SHADER_ATOMICS` allows atomic operations on the resource from shaders running on other
+    ///   adapters, together with `D3D12_HEAP_FLAG_SHARED_CROSS_ADAPTER`.
+    /// * `D3D12_HEAP_FLAG_SHARED` allows opening the resource on other devices, see [`crate::SharedResource`].
+    ///
+    /// The `D3D12_HEAP_FLAG_DENY_*` flags are implied by the resource type for committed resources, so aren't needed.
+    /// They're only required for explicit heaps on `D3D12_RESOURCE_HEAP_TIER_1` hardware, see
+    /// [`crate::GpuCapabilities::resource_heap_tier`], where each heap can only hold one category of resource.
+    pub fn create_buffer_with_heap_flags(
+        &self,
+        size: u64,
+        heap_type: D3D12_HEAP_TYPE,
+        flags: D3D12_RESOURCE_FLAGS,
+        heap_flags: D3D12_HEAP_FLAGS,
+        initial_state: D3D12_RESOURCE_STATES,
+    ) -> Result<ID3D12Resource, DxError> {
+        let heap_properties = D3D12_HEAP_PROPERTIES {
+            Type: heap_type,
+            ..Default::default()
+        };
+        let desc = D3D12_RESOURCE_DESC {
+            Dimension: D3D12_RESOURCE_DIMENSION_BUFFER,
+            Width: size,
+            Height: 1,
+            DepthOrArraySize: 1,
+            MipLevels: 1,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            Layout: D3D12_TEXTURE_LAYOUT_ROW_MAJOR,
+            Flags: flags,
+            ..Default::default()
+        };
+        self.check_memory_budget(&desc, heap_type)?;
+
+        let mut buffer = None;
+        unsafe {
+            self.device.CreateCommittedResource(
+                &heap_properties,
+                heap_flags,
+                &desc,
+                initial_state,
+                None,
+                &mut buffer,
+            )?;
+        }
+        Ok(buffer.unwrap())
+    }
+
+    /// Create a buffer in GPU memory holding `data`, copied from a temporary upload buffer, and transitioned to
+    /// `state`, e.g. `D3D12_RESOURCE_STATE_INDEX_BUFFER` for static mesh data.
+    ///
+    /// This records, executes, and waits for its own commands using [`Gpu::reset_commands`], so it must not be called
+    /// while recording a frame. For data that changes every frame, copy from an [`crate::UploadArena`] allocation
+    /// instead.
+    pub fn create_buffer_with_data(
+        &mut self,
+        data: &[u8],
+        state: D3D12_RESOURCE_STATES,
+    ) -> Result<ID3D12Resource, DxError> {
+        if data.is_empty() {
+            return Err(Error::new(
+                E_INVALIDARG,
+                "BevyDirectX: create_buffer_with_data() needs non-empty data",
+            )
+            .into());
+        }
+
+        let size = data.len() as u64;
+        let buffer = self.create_buffer(
+            size,
+            D3D12_HEAP_TYPE_DEFAULT,
+            D3D12_RESOURCE_FLAG_NONE,
+            D3D12_RESOURCE_STATE_COPY_DEST,
+        )?;
+        let upload_buffer = self.create_buffer(
+            size,
+            D3D12_HEAP_TYPE_UPLOAD,
+            D3D12_RESOURCE_FLAG_NONE,
+            D3D12_RESOURCE_STATE_GENERIC_READ,
+        )?;
+        MappedBuffer::<u8>::write_only(&upload_buffer)?
+            .as_mut_slice()
+            .copy_from_slice(data);
+
+        let command_list = self.reset_commands(None)?;
+        unsafe {
+            command_list.CopyBufferRegion(&buffer, 0, &upload_buffer, 0, size);
+            if state != D3D12_RESOURCE_STATE_COPY_DEST {
+                command_list.ResourceBarrier(&[transition_barrier(
+                    &buffer,
+                    D3D12_RESOURCE_STATE_COPY_DEST,
+                    state,
+                )]);
+            }
+        }
+
+        // Wait for the GPU before the upload buffer is dropped
+        self.execute_command_list()?;
+        self.signal_fence()?;
+        self.wait_for_fence()?;
+        Ok(buffer)
+    }
+
+    /// Create a single-mip 2D texture in its own implicit heap in GPU memory. Name it with [`Gpu::name_object`] to find
+    /// it in captures, or use [`Gpu::create_texture_2d_named`].
+    pub fn create_texture_2d(
+        &self,
+        width: u32,
+        height: u32,
+        format: DXGI_FORMAT,
+        flags: D3D12_RESOURCE_FLAGS,
+        initial_state: D3D12_RESOURCE_STATES,
+        optimized_clear_value: Option<&D3D12_CLEAR_VALUE>,
+    ) -> Result<ID3D12Resource, DxError> {
+        self.create_texture_2d_with_heap_flags(
+            width,
+            height,
+            format,
+            flags,
+            D3D12_HEAP_FLAG_NONE,
+            initial_state,
+            optimized_clear_value,
+        )
+    }
+
+    /// [`Gpu::create_texture_2d`], named `label` with [`Gpu::name_object`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_texture_2d_named(
+        &self,
+        label: &str,
+        width: u32,
+        height: u32,
+        format: DXGI_FORMAT,
+        flags: D3D12_RESOURCE_FLAGS,
+        initial_state: D3D12_RESOURCE_STATES,
+        optimized_clear_value: Option<&D3D12_CLEAR_VALUE>,
+    ) -> Result<ID3D12Resource, DxError> {
+        let texture = self.create_texture_2d(
+            width,
+            height,
+            format,
+            flags,
+            initial_state,
+            optimized_clear_value,
+        )?;
+        self.name_object(&texture, label);
+        Ok(texture)
+    }
+
+    /// [`Gpu::create_texture_2d`], with flags for the implicit heap, see [`Gpu::create_buffer_with_heap_flags`].
+    ///
+    /// Render targets and depth stencils created with `D3D12_HEAP_FLAG_CREATE_NOT_ZEROED` must be cleared, discarded,
+    /// or fully copied to before any other use.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_texture_2d_with_heap_flags(
+        &self,
+        width: u32,
+        height: u32,
+        format: DXGI_FORMAT,
+        flags: D3D12_RESOURCE_FLAGS,
+        heap_flags: D3D12_HEAP_FLAGS,
+        initial_state: D3D12_RESOURCE_STATES,
+        optimized_clear_value: Option<&D3D12_CLEAR_VALUE>,
+    ) -> Result<ID3D12Resource, DxError> {
+        let heap_properties = D3D12_HEAP_PROPERTIES {
+            Type: D3D12_HEAP_TYPE_DEFAULT,
+            ..Default::default()
+        };
+        let desc = D3D12_RESOURCE_DESC {
+            Dimension: D3D12_RESOURCE_DIMENSION_TEXTURE2D,
+            Width: width as u64,
+            Height: height,
+            DepthOrArraySize: 1,
+            MipLevels: 1,
+            Format: format,
+            SampleDesc: DXGI_SAMPLE_DESC {
+                Count: 1,
+                Quality: 0,
+            },
+            Layout: D3D12_TEXTURE_LAYOUT_UNKNOWN,
+            Flags: flags,
+            ..Default::default()
+        };
+        self.check_memory_budget(&desc, D3D12_HEAP_TYPE_DEFAULT)?;
+
+        let mut texture = None;
+        unsafe {
+            self.device.CreateCommittedResource(
+                &heap_properties,
+                heap_flags,
+                &desc,
+                initial_state,
+                optimized_clear_value.map(|v| v as *const _),
+                &mut texture,
+            )
+        }
+        .map_err(|e| resource_creation_error(&self.device, &desc, e))?;
+        Ok(texture.unwrap())
+    }
+
+    pub fn create_descriptor_heap(
+        &self,
+        heap_type: D3D12_DESCRIPTOR_HEAP_TYPE,
+        count: u32,
+        shader_visible: bool,
+    ) -> Result<ID3D12DescriptorHeap, DxError> {
+        unsafe {
+            self.device
+                .CreateDescriptorHeap(&D3D12_DESCRIPTOR_HEAP_DESC {
+                    Type: heap_type,
+                    NumDescriptors: count,
+                    Flags: if shader_visible {
+                        D3D12_DESCRIPTOR_HEAP_FLAG_SHADER_VISIBLE
+                    } else {
+                        D3D12_DESCRIPTOR_HEAP_FLAG_NONE
+                    },
+                    NodeMask: 0,
+                })
+                .map_err(DxError::from)
+        }
+    }
+
     pub fn create_root_signature(
         &self,
         parameters: &[D3D12_ROOT_PARAMETER1],
         static_samplers: &[D3D12_STATIC_SAMPLER_DESC],
         flags: D3D12_ROOT_SIGNATURE_FLAGS,
-    ) -> Result<ID3D12RootSignature, Error> {
+    ) -> Result<ID3D12RootSignature, DxError> {
         let desc = D3D12_VERSIONED_ROOT_SIGNATURE_DESC {
             Version: D3D_ROOT_SIGNATURE_VERSION_1_1,
             Anonymous: D3D12_VERSIONED_ROOT_SIGNATURE_DESC_0 {
@@ -215,9 +1006,82 @@ impl Gpu {
                 root_signature.GetBufferPointer() as *const u8,
                 root_signature.GetBufferSize(),
             );
-            self.device.CreateRootSignature(0, root_signature)
+            self.device
+                .CreateRootSignature(0, root_signature)
+                .map_err(DxError::from)
+        }
+    }
+}
+
+impl Drop for Gpu {
+    fn drop(&mut self) {
+        // Stop and join the fence waiter thread before the fence goes away
+        drop(self.fence_waiter.take());
+
+        // The device may outlive the Gpu if other objects still reference it, so stop logging its messages, e.g.
+        // before a new Gpu is created to recover from device loss
+        if let Some(cookie) = self.debug_callback_cookie {
+            if let Ok(info_queue) = self.device.cast::<ID3D12InfoQueue1>() {
+                let _ = unsafe { info_queue.UnregisterMessageCallback(cookie) };
+            }
+        }
+
+        unsafe {
+            let _ = CloseHandle(self.fence_event);
+        }
+    }
+}
+
+/// Block until `fence` reaches `value`, waiting on a new event, so that multiple threads can wait on different values
+/// at once without consuming each other's events.
+///
+/// If `timeout` milliseconds elapse first, the GPU is assumed to have hung, and an error is returned, which is the
+/// device removed reason if the device was lost.
+pub(crate) fn wait_for_fence_value(
+    fence: &ID3D12Fence,
+    value: u64,
+    timeout: u32,
+) -> Result<(), DxError> {
+    unsafe {
+        if fence.GetCompletedValue() >= value {
+            return Ok(());
+        }
+
+        let event = CreateEventW(None, false, false, None)?;
+        let result = fence
+            .SetEventOnCompletion(value, event)
+            .map(|_| WaitForSingleObjectEx(event, timeout, false));
+        CloseHandle(event)?;
+
+        if result? == WAIT_TIMEOUT {
+            error!("BevyDirectX: GPU wait for fence value {value} timed out — possible hang");
+            let mut device: Option<ID3D12Device> = None;
+            fence.GetDevice(&mut device)?;
+            if let Some(device) = device {
+                device.GetDeviceRemovedReason()?;
+            }
+            return Err(Error::from(DXGI_ERROR_WAIT_TIMEOUT).into());
         }
     }
+    Ok(())
+}
+
+/// Convert [`GpuConfig::fence_timeout`] to milliseconds for Win32 waits, rounding up to at least 1 ms, and keeping
+/// very long timeouts finite, as `INFINITE` is `u32::MAX`.
+fn fence_timeout_millis(timeout: Option<Duration>) -> u32 {
+    timeout.map_or(INFINITE, |timeout| {
+        let millis = timeout.as_nanos().div_ceil(1_000_000).max(1);
+        u32::try_from(millis).map_or(INFINITE - 1, |millis| millis.min(INFINITE - 1))
+    })
+}
+
+fn root_constant_count<T>() -> u32 {
+    let size = mem::size_of::<T>();
+    assert!(
+        size % 4 == 0,
+        "BevyDirectX: Root constants must be a multiple of 4 bytes, were {size} bytes"
+    );
+    (size / 4) as u32
 }
 
 pub unsafe extern "system" fn log_debug_layer_message(
@@ -225,19 +1089,61 @@ pub unsafe extern "system" fn log_debug_layer_message(
     severity: D3D12_MESSAGE_SEVERITY,
     id: D3D12_MESSAGE_ID,
     description: PCSTR,
-    _: *mut c_void,
+    context: *mut c_void,
 ) {
     let id = id.0;
     let description = description.to_string().unwrap();
 
-    let backtrace = Backtrace::capture();
-    let backtrace = if let BacktraceStatus::Disabled = backtrace.status() {
-        "note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace".to_owned()
+    // See GpuConfig::debug_message_backtrace_severity
+    let backtrace = if severity.0 < context as usize as i32 {
+        let backtrace = Backtrace::capture();
+        if let BacktraceStatus::Disabled = backtrace.status() {
+            "\nnote: run with `RUST_BACKTRACE=1` environment variable to display a backtrace"
+                .to_owned()
+        } else {
+            format!("\n{backtrace}")
+        }
     } else {
-        format!("{backtrace}")
+        String::new()
+    };
+
+    let category = message_category_name(category);
+
+    match severity {
+        D3D12_MESSAGE_SEVERITY_CORRUPTION => {
+            error!("D3D12 Corruption {category} ({id}): {description}{backtrace}");
+        }
+        D3D12_MESSAGE_SEVERITY_ERROR => {
+            error!("D3D12 {category} ({id}): {description}{backtrace}");
+        }
+        D3D12_MESSAGE_SEVERITY_WARNING => {
+            warn!("D3D12 {category} ({id}): {description}{backtrace}");
+        }
+        _ => info!("D3D12 {category} ({id}): {description}{backtrace}"),
+    }
+}
+
+/// Whether Windows developer mode is enabled, which is stored in the registry as `AllowDevelopmentWithoutDevLicense`.
+fn developer_mode_enabled() -> bool {
+    let mut value = 0u32;
+    let mut size = mem::size_of_val(&value) as u32;
+    let result = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            w!("SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\AppModelUnlock"),
+            w!("AllowDevelopmentWithoutDevLicense"),
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut value as *mut _ as *mut c_void),
+            Some(&mut size),
+        )
     };
+    result == ERROR_SUCCESS && value != 0
+}
 
-    let category = match category {
+/// Readable name of a debug layer message category.
+pub(crate) fn message_category_name(category: D3D12_MESSAGE_CATEGORY) -> &'static str {
+    match category {
         D3D12_MESSAGE_CATEGORY_APPLICATION_DEFINED => "Application Defined",
         D3D12_MESSAGE_CATEGORY_MISCELLANEOUS => "Miscellaneous",
         D3D12_MESSAGE_CATEGORY_INITIALIZATION => "Initialization",
@@ -250,18 +1156,28 @@ pub unsafe extern "system" fn log_debug_layer_message(
         D3D12_MESSAGE_CATEGORY_EXECUTION => "Execution",
         D3D12_MESSAGE_CATEGORY_SHADER => "Shader",
         _ => "Unknown",
-    };
+    }
+}
 
-    match severity {
-        D3D12_MESSAGE_SEVERITY_CORRUPTION => {
-            error!("D3D12 Corruption {category} ({id}): {description}\n{backtrace}");
-        }
-        D3D12_MESSAGE_SEVERITY_ERROR => {
-            error!("D3D12 {category} ({id}): {description}\n{backtrace}");
-        }
-        D3D12_MESSAGE_SEVERITY_WARNING => {
-            warn!("D3D12 {category} ({id}): {description}\n{backtrace}");
-        }
-        _ => info!("D3D12 {category} ({id}): {description}\n{backtrace}"),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fence_timeout_rounds_up_to_whole_milliseconds() {
+        assert_eq!(fence_timeout_millis(None), INFINITE);
+        assert_eq!(fence_timeout_millis(Some(Duration::ZERO)), 1);
+        assert_eq!(fence_timeout_millis(Some(Duration::from_nanos(1))), 1);
+        assert_eq!(fence_timeout_millis(Some(Duration::from_micros(999))), 1);
+        assert_eq!(fence_timeout_millis(Some(Duration::from_millis(1))), 1);
+        assert_eq!(fence_timeout_millis(Some(Duration::from_micros(1001))), 2);
+        assert_eq!(fence_timeout_millis(Some(Duration::from_secs(2))), 2000);
+    }
+
+    #[test]
+    fn long_fence_timeouts_stay_finite() {
+        let max = Duration::from_millis(u64::from(INFINITE));
+        assert_eq!(fence_timeout_millis(Some(max)), INFINITE - 1);
+        assert_eq!(fence_timeout_millis(Some(Duration::MAX)), INFINITE - 1);
     }
 }
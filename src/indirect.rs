@@ -0,0 +1,76 @@
+use crate::{error::DxError, gpu::Gpu};
+use std::mem;
+use windows::Win32::Graphics::Direct3D12::*;
+
+impl Gpu {
+    /// Create a command signature for [`Gpu::dispatch_indirect`], where each argument is a
+    /// [`D3D12_DISPATCH_ARGUMENTS`] (three `u32` thread group counts).
+    pub fn create_dispatch_indirect_signature(&self) -> Result<ID3D12CommandSignature, DxError> {
+        self.create_indirect_signature(
+            D3D12_INDIRECT_ARGUMENT_TYPE_DISPATCH,
+            mem::size_of::<D3D12_DISPATCH_ARGUMENTS>(),
+        )
+    }
+
+    /// Create a command signature for `ExecuteIndirect` drawing indexed instances, where each argument is a
+    /// [`D3D12_DRAW_INDEXED_ARGUMENTS`], as written by [`crate::GpuCulling`].
+    pub fn create_draw_indexed_indirect_signature(
+        &self,
+    ) -> Result<ID3D12CommandSignature, DxError> {
+        self.create_indirect_signature(
+            D3D12_INDIRECT_ARGUMENT_TYPE_DRAW_INDEXED,
+            mem::size_of::<D3D12_DRAW_INDEXED_ARGUMENTS>(),
+        )
+    }
+
+    fn create_indirect_signature(
+        &self,
+        argument_type: D3D12_INDIRECT_ARGUMENT_TYPE,
+        argument_size: usize,
+    ) -> Result<ID3D12CommandSignature, DxError> {
+        let argument = D3D12_INDIRECT_ARGUMENT_DESC {
+            Type: argument_type,
+            ..Default::default()
+        };
+        let desc = D3D12_COMMAND_SIGNATURE_DESC {
+            ByteStride: argument_size as u32,
+            NumArgumentDescs: 1,
+            pArgumentDescs: &argument,
+            NodeMask: 0,
+        };
+
+        let mut signature = None;
+        unsafe {
+            self.device
+                .CreateCommandSignature(&desc, None, &mut signature)?;
+        }
+        Ok(signature.unwrap())
+    }
+
+    /// Dispatch a compute shader, reading the thread group counts from `argument_buffer` at `offset` on the GPU.
+    ///
+    /// `signature` must come from [`Gpu::create_dispatch_indirect_signature`]. `argument_buffer` must be in the
+    /// `D3D12_RESOURCE_STATE_INDIRECT_ARGUMENT` state, and `offset` must be 4-byte aligned.
+    pub fn dispatch_indirect(
+        &self,
+        command_list: &ID3D12GraphicsCommandList7,
+        signature: &ID3D12CommandSignature,
+        argument_buffer: &ID3D12Resource,
+        offset: u64,
+    ) {
+        let argument_size = mem::size_of::<D3D12_DISPATCH_ARGUMENTS>() as u64;
+        let buffer_size = unsafe { argument_buffer.GetDesc() }.Width;
+        assert!(
+            offset % 4 == 0,
+            "BevyDirectX: dispatch_indirect() offset must be 4-byte aligned, was {offset}"
+        );
+        assert!(
+            offset + argument_size <= buffer_size,
+            "BevyDirectX: dispatch_indirect() arguments at offset {offset} exceed the argument buffer size of {buffer_size}"
+        );
+
+        unsafe {
+            command_list.ExecuteIndirect(signature, 1, argument_buffer, offset, None, 0);
+        }
+    }
+}
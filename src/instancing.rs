@@ -0,0 +1,171 @@
+use std::ops::Range;
+use windows::{
+    core::PCSTR,
+    Win32::Graphics::{Direct3D12::*, Dxgi::Common::DXGI_FORMAT},
+};
+
+/// Builder for the `InputLayout` of a [`D3D12_GRAPHICS_PIPELINE_STATE_DESC`], with per-vertex and per-instance
+/// attributes.
+///
+/// Attributes are packed in the order they're added within each input slot. Each slot holds either per-vertex or
+/// per-instance data, bound with [`set_vertex_buffer`]. The pipeline's root signature must be created with
+/// `D3D12_ROOT_SIGNATURE_FLAG_ALLOW_INPUT_ASSEMBLER_INPUT_LAYOUT`.
+#[derive(Clone, Default)]
+pub struct InputLayout {
+    elements: Vec<D3D12_INPUT_ELEMENT_DESC>,
+}
+
+// Safety: Semantic names are required to be static strings
+unsafe impl Send for InputLayout {}
+unsafe impl Sync for InputLayout {}
+
+impl InputLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an attribute read once per vertex from `slot`.
+    ///
+    /// `semantic_name` must be a static string, e.g. from `windows::core::s!`.
+    pub fn vertex_attribute(
+        self,
+        semantic_name: PCSTR,
+        semantic_index: u32,
+        format: DXGI_FORMAT,
+        slot: u32,
+    ) -> Self {
+        self.attribute(
+            semantic_name,
+            semantic_index,
+            format,
+            slot,
+            D3D12_INPUT_CLASSIFICATION_PER_VERTEX_DATA,
+            0,
+        )
+    }
+
+    /// Add an attribute read from `slot` once per `step_rate` instances, usually 1.
+    ///
+    /// `semantic_name` must be a static string, e.g. from `windows::core::s!`.
+    pub fn instance_attribute(
+        self,
+        semantic_name: PCSTR,
+        semantic_index: u32,
+        format: DXGI_FORMAT,
+        slot: u32,
+        step_rate: u32,
+    ) -> Self {
+        self.attribute(
+            semantic_name,
+            semantic_index,
+            format,
+            slot,
+            D3D12_INPUT_CLASSIFICATION_PER_INSTANCE_DATA,
+            step_rate,
+        )
+    }
+
+    fn attribute(
+        mut self,
+        semantic_name: PCSTR,
+        semantic_index: u32,
+        format: DXGI_FORMAT,
+        slot: u32,
+        classification: D3D12_INPUT_CLASSIFICATION,
+        step_rate: u32,
+    ) -> Self {
+        assert!(
+            slot < D3D12_IA_VERTEX_INPUT_RESOURCE_SLOT_COUNT,
+            "BevyDirectX: Input slot must be less than {D3D12_IA_VERTEX_INPUT_RESOURCE_SLOT_COUNT}, was {slot}"
+        );
+        assert!(
+            self.elements
+                .iter()
+                .filter(|element| element.InputSlot == slot)
+                .all(|element| element.InputSlotClass == classification
+                    && element.InstanceDataStepRate == step_rate),
+            "BevyDirectX: Input slot {slot} can't mix per-vertex and per-instance attributes, or instance step rates"
+        );
+
+        self.elements.push(D3D12_INPUT_ELEMENT_DESC {
+            SemanticName: semantic_name,
+            SemanticIndex: semantic_index,
+            Format: format,
+            InputSlot: slot,
+            AlignedByteOffset: D3D12_APPEND_ALIGNED_ELEMENT,
+            InputSlotClass: classification,
+            InstanceDataStepRate: step_rate,
+        });
+        self
+    }
+
+    pub fn elements(&self) -> &[D3D12_INPUT_ELEMENT_DESC] {
+        &self.elements
+    }
+
+    /// Layout to assign to `D3D12_GRAPHICS_PIPELINE_STATE_DESC::InputLayout`. Points into `self`, which must outlive
+    /// pipeline creation.
+    pub fn desc(&self) -> D3D12_INPUT_LAYOUT_DESC {
+        D3D12_INPUT_LAYOUT_DESC {
+            pInputElementDescs: self.elements.as_ptr(),
+            NumElements: self.elements.len() as u32,
+        }
+    }
+}
+
+/// View of the whole of `buffer` as elements of `stride` bytes, for [`set_vertex_buffer`].
+pub fn vertex_buffer_view(buffer: &ID3D12Resource, stride: u32) -> D3D12_VERTEX_BUFFER_VIEW {
+    D3D12_VERTEX_BUFFER_VIEW {
+        BufferLocation: unsafe { buffer.GetGPUVirtualAddress() },
+        SizeInBytes: unsafe { buffer.GetDesc() }.Width as u32,
+        StrideInBytes: stride,
+    }
+}
+
+/// Bind the whole of `buffer`, holding per-vertex or per-instance data of `stride` bytes each, to input `slot`.
+///
+/// The buffer must be in the `D3D12_RESOURCE_STATE_VERTEX_AND_CONSTANT_BUFFER` state, or be an upload heap buffer.
+pub fn set_vertex_buffer(
+    command_list: &ID3D12GraphicsCommandList7,
+    slot: u32,
+    buffer: &ID3D12Resource,
+    stride: u32,
+) {
+    unsafe { command_list.IASetVertexBuffers(slot, Some(&[vertex_buffer_view(buffer, stride)])) };
+}
+
+/// Draw `vertices` of each of `instances`. Per-instance attributes are read starting from element `instances.start`
+/// of their buffers, and `SV_InstanceID` counts from 0 regardless.
+pub fn draw_instanced(
+    command_list: &ID3D12GraphicsCommandList7,
+    vertices: Range<u32>,
+    instances: Range<u32>,
+) {
+    unsafe {
+        command_list.DrawInstanced(
+            vertices.len() as u32,
+            instances.len() as u32,
+            vertices.start,
+            instances.start,
+        )
+    };
+}
+
+/// Draw `indices` of the bound index buffer for each of `instances`, adding `base_vertex` to each index. See
+/// [`draw_instanced`] for how `instances` is interpreted.
+pub fn draw_indexed_instanced(
+    command_list: &ID3D12GraphicsCommandList7,
+    indices: Range<u32>,
+    base_vertex: i32,
+    instances: Range<u32>,
+) {
+    unsafe {
+        command_list.DrawIndexedInstanced(
+            indices.len() as u32,
+            instances.len() as u32,
+            indices.start,
+            base_vertex,
+            instances.start,
+        )
+    };
+}
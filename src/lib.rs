@@ -1,19 +1,179 @@
+mod async_pipeline;
+mod backbuffer;
+mod blit;
+mod buffer_view;
+mod bundle;
+mod capabilities;
+mod clear_uav;
+mod culling;
+mod debug_messages;
+mod depth;
+#[cfg(feature = "diagnostics_overlay")]
+mod diagnostics_overlay;
+mod dynamic_descriptors;
+mod error;
+mod fence_waiter;
+mod format_support;
+mod frame;
+mod frame_pacing;
+mod fullscreen_triangle;
+mod gbuffer;
 mod gpu;
+mod indirect;
+mod instancing;
+mod loading_screen;
+mod mapped_buffer;
+mod memory_budget;
+mod mips;
+mod offscreen;
+mod parallel_recorder;
+mod pipeline_cache;
+mod query;
+mod render_graph;
+mod resource_tracker;
+mod sampler;
+mod sampler_feedback;
+mod semaphore;
+mod shader;
+mod shared_render_target;
+mod shared_resource;
+mod submission;
 mod swapchain;
+mod texture;
+#[cfg(feature = "texture_loading")]
+mod texture_loader;
+mod tonemap;
+mod transient_pool;
+mod upload_arena;
+mod upscaler;
+mod visibility_buffer;
 
+use crate::loading_screen::render_ready;
 use bevy::{
-    app::{First, Last, MainScheduleOrder, Plugin},
-    ecs::schedule::ScheduleLabel,
-    prelude::App,
+    app::{AppExit, First, Last, MainScheduleOrder, Plugin},
+    ecs::schedule::{ScheduleLabel, SystemSet},
+    prelude::{
+        any_with_component, error, not, on_event, warn, App, IntoSystemConfigs,
+        IntoSystemSetConfigs, Query, Res, With,
+    },
+    window::{PrimaryWindow, Window},
 };
 
+#[cfg(feature = "diagnostics_overlay")]
+pub use crate::diagnostics_overlay::{DiagnosticsOverlay, DiagnosticsOverlayPlugin};
+#[cfg(feature = "texture_loading")]
+pub use crate::texture_loader::TextureFileFormat;
 pub use crate::{
-    gpu::Gpu,
-    swapchain::{update_render_target, wait_for_ready_frame, WindowRenderTarget},
+    async_pipeline::PipelineHandle,
+    backbuffer::{begin_frame, end_frame, CurrentBackbuffer},
+    blit::BlitPipeline,
+    buffer_view::UavCounter,
+    bundle::Bundle,
+    capabilities::GpuCapabilities,
+    culling::{frustum_planes, CullingDrawRange, CullingInstance, GpuCulling},
+    debug_messages::DebugMessage,
+    depth::DepthTarget,
+    dynamic_descriptors::{DynamicDescriptorRing, DEFAULT_DYNAMIC_DESCRIPTOR_COUNT},
+    error::DxError,
+    frame::{increment_frame_count, update_temporal_jitter, FrameCount, TemporalJitter},
+    frame_pacing::{update_frame_pacing_stats, FramePacing, FramePacingStats, SmoothFramePacer},
+    fullscreen_triangle::{FULLSCREEN_TRIANGLE_HLSL, FULLSCREEN_TRIANGLE_VS},
+    gbuffer::GBuffer,
+    gpu::{Gpu, GpuConfig, FRAMES_IN_FLIGHT},
+    instancing::{
+        draw_indexed_instanced, draw_instanced, set_vertex_buffer, vertex_buffer_view, InputLayout,
+    },
+    loading_screen::{render_loading_screen, LoadingScreen, RenderReady},
+    mapped_buffer::MappedBuffer,
+    offscreen::{
+        set_pipeline_rtv_formats, set_render_targets, OffscreenTarget, OffscreenTargetGroup,
+    },
+    parallel_recorder::ParallelRecorder,
+    pipeline_cache::PipelineCache,
+    query::OcclusionQueryHeap,
+    render_graph::{RenderGraph, RenderGraphPass},
+    resource_tracker::{
+        aliasing_barrier, subresource_transition_barrier, texture_layout_barrier,
+        transition_barrier, uav_barrier, ResourceTracker,
+    },
+    sampler::{anisotropic_static_sampler, static_sampler},
+    sampler_feedback::SamplerFeedbackMap,
+    semaphore::GpuSemaphore,
+    shader::compile_shader,
+    shared_render_target::SharedRenderTarget,
+    shared_resource::{KeyedMutex, SharedResource},
+    submission::Submission,
+    swapchain::{
+        set_scissor_rects, update_render_target, wait_for_ready_frame, FixedResolution,
+        FixedResolutionScaling, PresentMode, RenderScale, SwapchainConfig, SwapchainSurface,
+        WindowRenderTarget,
+    },
+    texture::{block_compressed_block_size, SubresourceLayout},
+    tonemap::{TonemapOperator, TonemapPass, TonemapSettings},
+    transient_pool::{TransientResourceDesc, TransientResourcePool},
+    upload_arena::{UploadAllocation, UploadArena, DEFAULT_UPLOAD_PAGE_SIZE},
+    upscaler::{Upscaler, UpscalerInputs, UpscalerTargets},
+    visibility_buffer::VisibilityBuffer,
 };
 pub use windows;
 
-pub struct BevyDirectXPlugin;
+pub struct BevyDirectXPlugin {
+    pub gpu_config: GpuConfig,
+    /// Let the plugin reset the command list, transition the backbuffer, submit, and present each frame, exposing
+    /// the backbuffer as [`CurrentBackbuffer`] to systems in [`RenderSet::Draw`]. Defaults to false, in which case
+    /// systems must do this themselves.
+    pub manage_backbuffer: bool,
+    /// Add [`wait_for_ready_frame`] to the start of each frame. Defaults to true. Disable to wait manually as part
+    /// of a custom frame loop, in the order documented on [`wait_for_ready_frame`].
+    pub manage_frame_loop: bool,
+    /// If creating the [`Gpu`] fails, log a warning and continue without rendering, instead of panicking. Defaults to
+    /// false.
+    ///
+    /// Without a GPU, the [`Gpu`] resource is not inserted and none of the plugin's systems are added, but the
+    /// [`Render`] schedule and [`RenderSet`]s still exist, so the app's non-rendering logic keeps running, e.g. in CI
+    /// or over remote desktop. Systems that render should check for the resource with `Option<Res<Gpu>>` or
+    /// `run_if(resource_exists::<Gpu>)`.
+    pub allow_no_gpu: bool,
+    /// Also run [`update_render_target`] in [`First`] until the primary window has a [`WindowRenderTarget`], so that
+    /// the swapchain, its RTVs, and any [`RenderScale`] texture are created at the start of the frame the window's
+    /// handle becomes available, rather than just before rendering it. Defaults to false.
+    ///
+    /// Winit creates windows before the first frame on desktop, so the swapchain then exists before any `Startup`
+    /// systems' effects are rendered, and systems in [`First`] onwards can rely on it. If the handle arrives later,
+    /// e.g. on resume, it's created at the start of the following frame instead. Runs after [`wait_for_ready_frame`],
+    /// as creating the swapchain already waits for it to be ready.
+    pub create_swapchain_early: bool,
+    /// Keep rendering while the primary window is unfocused or minimized. Defaults to true.
+    ///
+    /// When false, [`wait_for_ready_frame`] and every system in the [`RenderSet`]s are skipped while the window lacks
+    /// focus, so nothing is rendered or presented, and GPU usage drops to near zero. The swapchain is kept, and
+    /// rendering resumes on the first frame the window is focused again, resizing the swapchain then if needed. Bevy
+    /// still runs the rest of the app at full speed, so pair this with a `WinitSettings::unfocused_mode` that limits
+    /// the update rate, such as `UpdateMode::reactive_low_power`, to also save CPU time.
+    pub render_when_unfocused: bool,
+    /// Show a loading screen instead of running the app's rendering until [`RenderReady`] is set, to avoid a black or
+    /// flickering window while pipelines and assets load. Defaults to `None`.
+    ///
+    /// Until then, [`render_loading_screen`] clears the primary window to the screen's color and presents it each
+    /// frame, and [`begin_frame`] and the [`RenderSet::Draw`] and [`RenderSet::Present`] sets are skipped, so render
+    /// systems in them don't need to check whether their resources exist yet. The [`LoadingScreen`] resource can be
+    /// changed while it's shown.
+    pub loading_screen: Option<LoadingScreen>,
+}
+
+impl Default for BevyDirectXPlugin {
+    fn default() -> Self {
+        Self {
+            gpu_config: GpuConfig::default(),
+            manage_backbuffer: false,
+            manage_frame_loop: true,
+            allow_no_gpu: false,
+            create_swapchain_early: false,
+            render_when_unfocused: true,
+            loading_screen: None,
+        }
+    }
+}
 
 impl Plugin for BevyDirectXPlugin {
     fn build(&self, app: &mut App) {
@@ -21,14 +181,123 @@ impl Plugin for BevyDirectXPlugin {
         app.world_mut()
             .resource_mut::<MainScheduleOrder>()
             .insert_after(Last, Render);
+        app.init_resource::<FrameCount>()
+            .init_resource::<TemporalJitter>()
+            .init_resource::<RenderScale>()
+            .init_resource::<FramePacing>()
+            .configure_sets(
+                Render,
+                (RenderSet::Prepare, RenderSet::Draw, RenderSet::Present).chain(),
+            );
+        if let Some(loading_screen) = self.loading_screen {
+            app.insert_resource(loading_screen)
+                .init_resource::<RenderReady>()
+                .configure_sets(
+                    Render,
+                    (RenderSet::Draw, RenderSet::Present).run_if(render_ready),
+                );
+        }
+        if !self.render_when_unfocused {
+            app.configure_sets(
+                Render,
+                (RenderSet::Prepare, RenderSet::Draw, RenderSet::Present)
+                    .run_if(primary_window_focused),
+            );
+        }
 
-        let gpu = Gpu::new().expect("BevyDirectX: Failed to initialize renderer");
+        let gpu = match Gpu::new(&self.gpu_config) {
+            Ok(gpu) => gpu,
+            Err(e) if self.allow_no_gpu => {
+                warn!(
+                    "BevyDirectX: Failed to initialize renderer, continuing without rendering: {e}"
+                );
+                return;
+            }
+            Err(e) => panic!("BevyDirectX: Failed to initialize renderer: {e}"),
+        };
 
         app.insert_resource(gpu)
-            .add_systems(First, wait_for_ready_frame) // TODO: Should probably be it's own schedule before First
-            .add_systems(Render, update_render_target);
+            .init_resource::<FramePacingStats>()
+            .add_systems(
+                Render,
+                (
+                    increment_frame_count,
+                    update_render_target,
+                    update_frame_pacing_stats,
+                    update_temporal_jitter,
+                )
+                    .chain()
+                    .in_set(RenderSet::Prepare),
+            )
+            .add_systems(Last, save_pipeline_cache.run_if(on_event::<AppExit>()));
+
+        if self.loading_screen.is_some() {
+            app.add_systems(
+                Render,
+                render_loading_screen
+                    .after(update_render_target)
+                    .in_set(RenderSet::Prepare)
+                    .run_if(not(render_ready)),
+            );
+        }
+
+        if self.manage_frame_loop {
+            // TODO: Should probably be it's own schedule before First
+            if self.render_when_unfocused {
+                app.add_systems(First, wait_for_ready_frame);
+            } else {
+                // Must match the render sets, as waiting for a frame without presenting one would block the next wait
+                app.add_systems(First, wait_for_ready_frame.run_if(primary_window_focused));
+            }
+        }
+
+        if self.create_swapchain_early {
+            app.add_systems(
+                First,
+                update_render_target
+                    .after(wait_for_ready_frame)
+                    .run_if(not(any_with_component::<WindowRenderTarget>)),
+            );
+        }
+
+        if self.manage_backbuffer {
+            app.add_systems(
+                Render,
+                (
+                    begin_frame
+                        .after(update_render_target)
+                        .in_set(RenderSet::Prepare)
+                        .run_if(render_ready),
+                    end_frame.in_set(RenderSet::Present),
+                ),
+            );
+        }
+    }
+}
+
+/// Whether the primary window is focused and not minimized, or there's no primary window.
+fn primary_window_focused(window: Query<&Window, With<PrimaryWindow>>) -> bool {
+    window.get_single().map_or(true, |window| {
+        window.focused && window.physical_width() > 0 && window.physical_height() > 0
+    })
+}
+
+fn save_pipeline_cache(gpu: Res<Gpu>) {
+    if let Err(e) = gpu.pipeline_cache().save() {
+        error!("BevyDirectX: Failed to save pipeline cache: {e}");
     }
 }
 
 #[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Render;
+
+/// Ordered stages of the [`Render`] schedule.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RenderSet {
+    /// Update swapchains, and begin the frame if [`BevyDirectXPlugin::manage_backbuffer`] is enabled.
+    Prepare,
+    /// Record rendering commands.
+    Draw,
+    /// Submit and present, if [`BevyDirectXPlugin::manage_backbuffer`] is enabled.
+    Present,
+}
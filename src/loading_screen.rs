@@ -0,0 +1,68 @@
+use crate::{gpu::Gpu, swapchain::WindowRenderTarget};
+use bevy::{
+    prelude::{Query, Res, ResMut, Resource, With},
+    window::PrimaryWindow,
+};
+use windows::Win32::Graphics::Direct3D12::{
+    D3D12_RESOURCE_STATE_PRESENT, D3D12_RESOURCE_STATE_RENDER_TARGET,
+};
+
+/// Screen shown by [`crate::BevyDirectXPlugin::loading_screen`] until [`RenderReady`] is set.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct LoadingScreen {
+    /// Color to clear the window to.
+    pub color: [f32; 4],
+}
+
+impl Default for LoadingScreen {
+    fn default() -> Self {
+        Self {
+            color: [0.0, 0.0, 0.0, 1.0],
+        }
+    }
+}
+
+/// Whether the app's own rendering is ready to run, when [`crate::BevyDirectXPlugin::loading_screen`] is set.
+///
+/// Starts as false, in which case [`render_loading_screen`] clears and presents the primary window each frame, and
+/// [`crate::begin_frame`] and the [`crate::RenderSet::Draw`] and [`crate::RenderSet::Present`] sets are skipped.
+/// Set it to true once pipelines and assets are loaded, e.g. `ready.0 = true` from a system in `Update`, to switch to
+/// the main render path from that frame on. Setting it from systems in the [`crate::Render`] schedule takes effect
+/// the frame after.
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RenderReady(pub bool);
+
+/// Run condition for the main render path, true unless a [`LoadingScreen`] is shown.
+pub(crate) fn render_ready(
+    loading_screen: Option<Res<LoadingScreen>>,
+    ready: Option<Res<RenderReady>>,
+) -> bool {
+    loading_screen.is_none() || ready.map(|ready| ready.0) != Some(false)
+}
+
+/// Clear the primary window to [`LoadingScreen::color`], and submit and present the frame, while [`RenderReady`] is
+/// false. Skipped while the window has no swapchain or is occluded.
+pub fn render_loading_screen(
+    mut window: Query<&mut WindowRenderTarget, With<PrimaryWindow>>,
+    loading_screen: Res<LoadingScreen>,
+    mut gpu: ResMut<Gpu>,
+) {
+    let Ok(mut render_target) = window.get_single_mut() else {
+        return;
+    };
+    if !render_target.has_swapchain() || render_target.is_occluded() {
+        return;
+    }
+
+    let command_list = gpu
+        .reset_commands(None)
+        .expect("BevyDirectX: Failed to reset command list");
+    render_target.transition_rtv(command_list, D3D12_RESOURCE_STATE_RENDER_TARGET);
+    let (_, rtv) = render_target.rtv();
+    unsafe { command_list.ClearRenderTargetView(rtv, &loading_screen.color, None) };
+    render_target.transition_rtv(command_list, D3D12_RESOURCE_STATE_PRESENT);
+    render_target.upscale_to_backbuffer(command_list);
+
+    gpu.submit_and_present(&render_target)
+        .expect("BevyDirectX: Failed to submit and present loading screen");
+}
@@ -0,0 +1,94 @@
+use crate::error::DxError;
+use std::{marker::PhantomData, mem, os::raw::c_void, ptr, slice};
+use windows::Win32::Graphics::Direct3D12::*;
+
+/// A CPU mapping of a buffer, viewed as a slice of `T`. The buffer is unmapped on drop.
+///
+/// Use [`MappedBuffer::write_only`] for upload heap buffers, and [`MappedBuffer::read_write`] for readback
+/// heap buffers. The GPU must not be accessing the mapped region while the CPU reads or writes it.
+pub struct MappedBuffer<'a, T: Copy> {
+    resource: &'a ID3D12Resource,
+    data: *mut T,
+    len: usize,
+    written: bool,
+    _phantom: PhantomData<&'a mut [T]>,
+}
+
+impl<'a, T: Copy> MappedBuffer<'a, T> {
+    /// Map a buffer that the CPU will only write to, such as an upload heap buffer.
+    pub fn write_only(resource: &'a ID3D12Resource) -> Result<Self, DxError> {
+        Self::map(resource, false)
+    }
+
+    /// Map a buffer that the CPU will read from, such as a readback heap buffer.
+    pub fn read_write(resource: &'a ID3D12Resource) -> Result<Self, DxError> {
+        Self::map(resource, true)
+    }
+
+    fn map(resource: &'a ID3D12Resource, read: bool) -> Result<Self, DxError> {
+        assert!(
+            mem::size_of::<T>() != 0,
+            "BevyDirectX: MappedBuffer does not support zero-sized types"
+        );
+
+        let desc = unsafe { resource.GetDesc() };
+        assert_eq!(
+            desc.Dimension, D3D12_RESOURCE_DIMENSION_BUFFER,
+            "BevyDirectX: MappedBuffer resource must be a buffer"
+        );
+        let size = desc.Width as usize;
+
+        // An empty read range tells the driver the CPU won't read the data
+        let read_range = if read {
+            D3D12_RANGE {
+                Begin: 0,
+                End: size,
+            }
+        } else {
+            D3D12_RANGE { Begin: 0, End: 0 }
+        };
+
+        let mut data = ptr::null_mut::<c_void>();
+        unsafe { resource.Map(0, Some(&read_range), Some(&mut data))? };
+        assert!(
+            data as usize % mem::align_of::<T>() == 0,
+            "BevyDirectX: MappedBuffer data is not sufficiently aligned for T"
+        );
+
+        Ok(Self {
+            resource,
+            data: data as *mut T,
+            len: size / mem::size_of::<T>(),
+            written: false,
+            _phantom: PhantomData,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// View the mapped data. Reading from a write-only mapping is slow, as upload heaps are write-combined.
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.data, self.len) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        self.written = true;
+        unsafe { slice::from_raw_parts_mut(self.data, self.len) }
+    }
+}
+
+impl<'a, T: Copy> Drop for MappedBuffer<'a, T> {
+    fn drop(&mut self) {
+        // An empty written range tells the driver the CPU didn't modify the data
+        let empty_range = D3D12_RANGE { Begin: 0, End: 0 };
+        let written_range = (!self.written).then_some(&empty_range as *const _);
+
+        unsafe { self.resource.Unmap(0, written_range) };
+    }
+}
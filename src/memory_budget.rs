@@ -0,0 +1,117 @@
+use crate::{error::DxError, gpu::Gpu};
+use windows::{
+    core::Error,
+    Win32::{
+        Foundation::E_OUTOFMEMORY,
+        Graphics::{
+            Direct3D12::*,
+            Dxgi::{
+                DXGI_MEMORY_SEGMENT_GROUP, DXGI_MEMORY_SEGMENT_GROUP_LOCAL,
+                DXGI_MEMORY_SEGMENT_GROUP_NON_LOCAL, DXGI_QUERY_VIDEO_MEMORY_INFO,
+            },
+        },
+    },
+};
+
+impl Gpu {
+    /// This process's video memory budget and current usage for `segment_group`, where
+    /// `DXGI_MEMORY_SEGMENT_GROUP_LOCAL` is GPU memory, and `DXGI_MEMORY_SEGMENT_GROUP_NON_LOCAL` is system memory
+    /// accessible by the GPU. Integrated GPUs only have local memory.
+    ///
+    /// The budget changes as other apps use the GPU, so query it at most once a frame rather than caching it.
+    pub fn video_memory_info(
+        &self,
+        segment_group: DXGI_MEMORY_SEGMENT_GROUP,
+    ) -> Result<DXGI_QUERY_VIDEO_MEMORY_INFO, DxError> {
+        let mut info = DXGI_QUERY_VIDEO_MEMORY_INFO::default();
+        unsafe {
+            self.adapter
+                .QueryVideoMemoryInfo(0, segment_group, &mut info)
+        }?;
+        Ok(info)
+    }
+
+    /// Bytes of GPU memory this process uses over its local video memory budget, or 0 if within it, e.g. to decide
+    /// how much to [`Gpu::evict`] each frame when streaming.
+    pub fn bytes_over_budget(&self) -> Result<u64, DxError> {
+        let info = self.video_memory_info(DXGI_MEMORY_SEGMENT_GROUP_LOCAL)?;
+        Ok(info.CurrentUsage.saturating_sub(info.Budget))
+    }
+
+    /// Page previously evicted resources or heaps back into GPU memory, blocking until they're resident. Resources are
+    /// resident when created, so this is only needed after [`Gpu::evict`].
+    ///
+    /// Fails with `E_OUTOFMEMORY` if there isn't enough memory even after the OS pages out other resources, in which
+    /// case evict more first, see [`Gpu::bytes_over_budget`]. Making resources resident while over budget succeeds,
+    /// but the OS may then page out resources the GPU is about to use, stalling rendering.
+    pub fn make_resident(&self, objects: &[&ID3D12Pageable]) -> Result<(), DxError> {
+        let objects: Vec<_> = objects
+            .iter()
+            .map(|object| Some((*object).clone()))
+            .collect();
+        unsafe { self.device.MakeResident(&objects) }?;
+        Ok(())
+    }
+
+    /// Allow the OS to page resources or heaps out of GPU memory, lowering this process's usage towards
+    /// [`Gpu::video_memory_info`]'s budget, e.g. for streamed data that's out of view. Their contents are kept, and
+    /// are restored by [`Gpu::make_resident`].
+    ///
+    /// Evicted objects must not be used by the GPU until made resident again, including by commands already submitted,
+    /// so wait for the fence of the last frame using them first.
+    pub fn evict(&self, objects: &[&ID3D12Pageable]) -> Result<(), DxError> {
+        let objects: Vec<_> = objects
+            .iter()
+            .map(|object| Some((*object).clone()))
+            .collect();
+        unsafe { self.device.Evict(&objects) }?;
+        Ok(())
+    }
+
+    /// Reject creating a resource with `desc` in a `heap_type` heap if it would take this process's memory usage over
+    /// [`crate::GpuConfig::max_memory_budget_fraction`] of the budget.
+    pub(crate) fn check_memory_budget(
+        &self,
+        desc: &D3D12_RESOURCE_DESC,
+        heap_type: D3D12_HEAP_TYPE,
+    ) -> Result<(), DxError> {
+        let Some(fraction) = self.max_memory_budget_fraction else {
+            return Ok(());
+        };
+
+        let size = unsafe { self.device.GetResourceAllocationInfo(0, &[*desc]) }.SizeInBytes;
+        // Invalid descs are left for resource creation to report
+        if size == u64::MAX {
+            return Ok(());
+        }
+
+        let segment_group = if heap_type == D3D12_HEAP_TYPE_DEFAULT {
+            DXGI_MEMORY_SEGMENT_GROUP_LOCAL
+        } else {
+            DXGI_MEMORY_SEGMENT_GROUP_NON_LOCAL
+        };
+        let mut info = self.video_memory_info(segment_group)?;
+        if info.Budget == 0 {
+            info = self.video_memory_info(DXGI_MEMORY_SEGMENT_GROUP_LOCAL)?;
+        }
+
+        let limit = (info.Budget as f64 * fraction as f64) as u64;
+        if info.CurrentUsage + size > limit {
+            let resource = match desc.Dimension {
+                D3D12_RESOURCE_DIMENSION_BUFFER => "buffer".to_owned(),
+                _ => format!("{}x{} texture", desc.Width, desc.Height),
+            };
+            return Err(Error::new(
+                E_OUTOFMEMORY,
+                format!(
+                    "BevyDirectX: Allocating {size} bytes for a {resource} would exceed {}% of the {} byte video memory budget, with {} bytes already in use",
+                    fraction * 100.0,
+                    info.Budget,
+                    info.CurrentUsage
+                ),
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
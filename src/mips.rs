@@ -0,0 +1,259 @@
+use crate::{
+    error::DxError,
+    gpu::Gpu,
+    resource_tracker::{subresource_transition_barrier, transition_barrier},
+    shader::compile_shader,
+};
+use std::{borrow::Cow, mem::transmute_copy};
+use windows::Win32::Graphics::{Direct3D12::*, Dxgi::Common::*};
+
+const GENERATE_MIPS_HLSL: &str = include_str!("../assets/generate_mips.hlsl");
+/// [`GENERATE_MIPS_HLSL`], compiled to DXIL by the build script, or empty if DXC wasn't available.
+const GENERATE_MIPS_CS: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/generate_mips_cs.dxil"));
+
+/// Compute pipeline used by [`Gpu::generate_mips`], created on first use.
+pub(crate) struct MipGenerator {
+    root_signature: ID3D12RootSignature,
+    pipeline: ID3D12PipelineState,
+}
+
+impl MipGenerator {
+    fn new(gpu: &Gpu) -> Result<Self, DxError> {
+        let srv_range = D3D12_DESCRIPTOR_RANGE1 {
+            RangeType: D3D12_DESCRIPTOR_RANGE_TYPE_SRV,
+            NumDescriptors: 1,
+            BaseShaderRegister: 0,
+            RegisterSpace: 0,
+            Flags: D3D12_DESCRIPTOR_RANGE_FLAG_NONE,
+            OffsetInDescriptorsFromTableStart: 0,
+        };
+        let uav_range = D3D12_DESCRIPTOR_RANGE1 {
+            RangeType: D3D12_DESCRIPTOR_RANGE_TYPE_UAV,
+            ..srv_range
+        };
+        let descriptor_table = |range| D3D12_ROOT_PARAMETER1 {
+            ParameterType: D3D12_ROOT_PARAMETER_TYPE_DESCRIPTOR_TABLE,
+            Anonymous: D3D12_ROOT_PARAMETER1_0 {
+                DescriptorTable: D3D12_ROOT_DESCRIPTOR_TABLE1 {
+                    NumDescriptorRanges: 1,
+                    pDescriptorRanges: range,
+                },
+            },
+            ShaderVisibility: D3D12_SHADER_VISIBILITY_ALL,
+        };
+        let root_signature = gpu.create_root_signature(
+            &[
+                D3D12_ROOT_PARAMETER1 {
+                    ParameterType: D3D12_ROOT_PARAMETER_TYPE_32BIT_CONSTANTS,
+                    Anonymous: D3D12_ROOT_PARAMETER1_0 {
+                        Constants: D3D12_ROOT_CONSTANTS {
+                            ShaderRegister: 0,
+                            RegisterSpace: 0,
+                            Num32BitValues: 4,
+                        },
+                    },
+                    ShaderVisibility: D3D12_SHADER_VISIBILITY_ALL,
+                },
+                descriptor_table(&srv_range),
+                descriptor_table(&uav_range),
+            ],
+            &[D3D12_STATIC_SAMPLER_DESC {
+                Filter: D3D12_FILTER_MIN_MAG_MIP_LINEAR,
+                AddressU: D3D12_TEXTURE_ADDRESS_MODE_CLAMP,
+                AddressV: D3D12_TEXTURE_ADDRESS_MODE_CLAMP,
+                AddressW: D3D12_TEXTURE_ADDRESS_MODE_CLAMP,
+                MaxLOD: D3D12_FLOAT32_MAX,
+                ShaderVisibility: D3D12_SHADER_VISIBILITY_ALL,
+                ..Default::default()
+            }],
+            D3D12_ROOT_SIGNATURE_FLAG_NONE,
+        )?;
+
+        let shader = if GENERATE_MIPS_CS.is_empty() {
+            Cow::Owned(compile_shader(GENERATE_MIPS_HLSL, "CSMain", "cs_5_1")?)
+        } else {
+            Cow::Borrowed(GENERATE_MIPS_CS)
+        };
+        let desc = D3D12_COMPUTE_PIPELINE_STATE_DESC {
+            pRootSignature: unsafe { transmute_copy(&root_signature) },
+            CS: D3D12_SHADER_BYTECODE {
+                pShaderBytecode: shader.as_ptr() as _,
+                BytecodeLength: shader.len(),
+            },
+            ..Default::default()
+        };
+        let pipeline = gpu
+            .pipeline_cache()
+            .create_compute_pipeline(&gpu.device, &desc)?;
+
+        Ok(Self {
+            root_signature,
+            pipeline,
+        })
+    }
+}
+
+impl Gpu {
+    /// Fill in mip levels 1 and up of `texture` by repeatedly downsampling the previous level with a bilinear filter,
+    /// handling non-power-of-two sizes.
+    ///
+    /// `texture` must be a non-array 2D texture created with `D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS`, in a format
+    /// supporting typed UAV stores, and in the COMMON state, which it is left in. As UAVs can't be sRGB, textures
+    /// holding sRGB data must be created as `DXGI_FORMAT_R8G8B8A8_TYPELESS` or `DXGI_FORMAT_B8G8R8A8_TYPELESS`, and are
+    /// filtered in linear space.
+    ///
+    /// This records, executes, and waits for its own commands using [`Gpu::reset_commands`], so it must not be called
+    /// while recording a frame. Intended for use after loading a texture.
+    pub fn generate_mips(&mut self, texture: &ID3D12Resource) -> Result<(), DxError> {
+        let desc = unsafe { texture.GetDesc() };
+        assert!(
+            desc.Dimension == D3D12_RESOURCE_DIMENSION_TEXTURE2D && desc.DepthOrArraySize == 1,
+            "BevyDirectX: generate_mips() texture must be a non-array 2D texture"
+        );
+        assert!(
+            desc.Flags
+                .contains(D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS),
+            "BevyDirectX: generate_mips() texture must allow unordered access"
+        );
+        let mip_levels = desc.MipLevels as u32;
+        if mip_levels <= 1 {
+            return Ok(());
+        }
+
+        let (srv_format, uav_format, is_srgb) = match desc.Format {
+            DXGI_FORMAT_R8G8B8A8_TYPELESS => (
+                DXGI_FORMAT_R8G8B8A8_UNORM_SRGB,
+                DXGI_FORMAT_R8G8B8A8_UNORM,
+                true,
+            ),
+            DXGI_FORMAT_B8G8R8A8_TYPELESS => (
+                DXGI_FORMAT_B8G8R8A8_UNORM_SRGB,
+                DXGI_FORMAT_B8G8R8A8_UNORM,
+                true,
+            ),
+            format => (format, format, false),
+        };
+
+        if self.mip_generator.is_none() {
+            self.mip_generator = Some(MipGenerator::new(self)?);
+        }
+
+        // Source SRV and destination UAV for each level
+        let descriptor_heap = self.create_descriptor_heap(
+            D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV,
+            (mip_levels - 1) * 2,
+            true,
+        )?;
+        let descriptor_size = unsafe {
+            self.device
+                .GetDescriptorHandleIncrementSize(D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV)
+        };
+        let mut cpu_descriptor = unsafe { descriptor_heap.GetCPUDescriptorHandleForHeapStart() };
+        for mip in 0..mip_levels - 1 {
+            unsafe {
+                self.device.CreateShaderResourceView(
+                    texture,
+                    Some(&D3D12_SHADER_RESOURCE_VIEW_DESC {
+                        Format: srv_format,
+                        ViewDimension: D3D12_SRV_DIMENSION_TEXTURE2D,
+                        Shader4ComponentMapping: D3D12_DEFAULT_SHADER_4_COMPONENT_MAPPING,
+                        Anonymous: D3D12_SHADER_RESOURCE_VIEW_DESC_0 {
+                            Texture2D: D3D12_TEX2D_SRV {
+                                MostDetailedMip: mip,
+                                MipLevels: 1,
+                                ..Default::default()
+                            },
+                        },
+                    }),
+                    cpu_descriptor,
+                );
+                cpu_descriptor.ptr += descriptor_size as usize;
+                self.device.CreateUnorderedAccessView(
+                    texture,
+                    None,
+                    Some(&D3D12_UNORDERED_ACCESS_VIEW_DESC {
+                        Format: uav_format,
+                        ViewDimension: D3D12_UAV_DIMENSION_TEXTURE2D,
+                        Anonymous: D3D12_UNORDERED_ACCESS_VIEW_DESC_0 {
+                            Texture2D: D3D12_TEX2D_UAV {
+                                MipSlice: mip + 1,
+                                PlaneSlice: 0,
+                            },
+                        },
+                    }),
+                    cpu_descriptor,
+                );
+                cpu_descriptor.ptr += descriptor_size as usize;
+            }
+        }
+
+        let mip_generator = self.mip_generator.as_ref().unwrap();
+        let command_list = self.reset_commands(Some(&mip_generator.pipeline))?;
+        unsafe {
+            command_list.SetComputeRootSignature(&mip_generator.root_signature);
+            command_list.SetDescriptorHeaps(&[Some(descriptor_heap.clone())]);
+            command_list.ResourceBarrier(&[transition_barrier(
+                texture,
+                D3D12_RESOURCE_STATE_COMMON,
+                D3D12_RESOURCE_STATE_UNORDERED_ACCESS,
+            )]);
+
+            let mut gpu_descriptor = descriptor_heap.GetGPUDescriptorHandleForHeapStart();
+            let (mut width, mut height) = (desc.Width as u32, desc.Height);
+            for mip in 0..mip_levels - 1 {
+                // Finish writing the source level before reading from it
+                command_list.ResourceBarrier(&[subresource_transition_barrier(
+                    texture,
+                    mip,
+                    D3D12_RESOURCE_STATE_UNORDERED_ACCESS,
+                    D3D12_RESOURCE_STATE_NON_PIXEL_SHADER_RESOURCE,
+                )]);
+
+                let odd_dimensions = (width & 1) | ((height & 1) << 1);
+                width = (width / 2).max(1);
+                height = (height / 2).max(1);
+                let constants = [
+                    (1.0 / width as f32).to_bits(),
+                    (1.0 / height as f32).to_bits(),
+                    odd_dimensions,
+                    is_srgb as u32,
+                ];
+                command_list.SetComputeRoot32BitConstants(
+                    0,
+                    constants.len() as u32,
+                    constants.as_ptr() as _,
+                    0,
+                );
+                command_list.SetComputeRootDescriptorTable(1, gpu_descriptor);
+                gpu_descriptor.ptr += descriptor_size as u64;
+                command_list.SetComputeRootDescriptorTable(2, gpu_descriptor);
+                gpu_descriptor.ptr += descriptor_size as u64;
+
+                command_list.Dispatch(width.div_ceil(8), height.div_ceil(8), 1);
+            }
+
+            let mut barriers = (0..mip_levels - 1)
+                .map(|mip| {
+                    subresource_transition_barrier(
+                        texture,
+                        mip,
+                        D3D12_RESOURCE_STATE_NON_PIXEL_SHADER_RESOURCE,
+                        D3D12_RESOURCE_STATE_COMMON,
+                    )
+                })
+                .collect::<Vec<_>>();
+            barriers.push(subresource_transition_barrier(
+                texture,
+                mip_levels - 1,
+                D3D12_RESOURCE_STATE_UNORDERED_ACCESS,
+                D3D12_RESOURCE_STATE_COMMON,
+            ));
+            command_list.ResourceBarrier(&barriers);
+        }
+
+        // Wait for the GPU before the descriptor heap is dropped
+        self.execute_command_list()?;
+        self.signal_fence()?;
+        self.wait_for_fence()
+    }
+}
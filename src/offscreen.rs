@@ -0,0 +1,414 @@
+use crate::{error::DxError, gpu::Gpu, resource_tracker::transition_barrier};
+use bevy::{math::UVec2, prelude::error};
+use windows::Win32::{
+    Foundation::RECT,
+    Graphics::{Direct3D12::*, Dxgi::Common::DXGI_FORMAT},
+};
+
+/// Viewport covering the whole of a render target of the given size.
+pub(crate) fn full_viewport(size: UVec2) -> D3D12_VIEWPORT {
+    D3D12_VIEWPORT {
+        TopLeftX: 0.0,
+        TopLeftY: 0.0,
+        Width: size.x as f32,
+        Height: size.y as f32,
+        MinDepth: D3D12_MIN_DEPTH,
+        MaxDepth: D3D12_MAX_DEPTH,
+    }
+}
+
+/// Scissor rect covering the whole of a render target of the given size.
+pub(crate) fn full_scissor_rect(size: UVec2) -> RECT {
+    RECT {
+        left: 0,
+        top: 0,
+        right: size.x as i32,
+        bottom: size.y as i32,
+    }
+}
+
+/// An off-screen color texture to render to, and then sample from in a later pass, e.g. for post-processing.
+///
+/// Bundles the texture with an RTV, and an SRV in its own shader-visible descriptor heap. The texture rests in the
+/// PIXEL_SHADER_RESOURCE state, and is transitioned to RENDER_TARGET between [`OffscreenTarget::begin_render`] and
+/// [`OffscreenTarget::end_render`].
+pub struct OffscreenTarget {
+    texture: ID3D12Resource,
+    rtv_heap: ID3D12DescriptorHeap,
+    srv_heap: ID3D12DescriptorHeap,
+    size: UVec2,
+    format: DXGI_FORMAT,
+}
+
+impl OffscreenTarget {
+    pub fn new(gpu: &Gpu, size: UVec2, format: DXGI_FORMAT) -> Result<Self, DxError> {
+        let texture = gpu.create_texture_2d(
+            size.x,
+            size.y,
+            format,
+            D3D12_RESOURCE_FLAG_ALLOW_RENDER_TARGET,
+            D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE,
+            None,
+        )?;
+        let rtv_heap = gpu.create_descriptor_heap(D3D12_DESCRIPTOR_HEAP_TYPE_RTV, 1, false)?;
+        let srv_heap =
+            gpu.create_descriptor_heap(D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV, 1, true)?;
+        unsafe {
+            gpu.device.CreateRenderTargetView(
+                &texture,
+                None,
+                rtv_heap.GetCPUDescriptorHandleForHeapStart(),
+            );
+            gpu.device.CreateShaderResourceView(
+                &texture,
+                None,
+                srv_heap.GetCPUDescriptorHandleForHeapStart(),
+            );
+        }
+
+        Ok(Self {
+            texture,
+            rtv_heap,
+            srv_heap,
+            size,
+            format,
+        })
+    }
+
+    pub fn texture(&self) -> &ID3D12Resource {
+        &self.texture
+    }
+
+    pub fn rtv(&self) -> D3D12_CPU_DESCRIPTOR_HANDLE {
+        unsafe { self.rtv_heap.GetCPUDescriptorHandleForHeapStart() }
+    }
+
+    /// Shader-visible descriptor heap containing only [`OffscreenTarget::srv`].
+    pub fn srv_heap(&self) -> &ID3D12DescriptorHeap {
+        &self.srv_heap
+    }
+
+    pub fn srv(&self) -> D3D12_GPU_DESCRIPTOR_HANDLE {
+        unsafe { self.srv_heap.GetGPUDescriptorHandleForHeapStart() }
+    }
+
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    pub fn format(&self) -> DXGI_FORMAT {
+        self.format
+    }
+
+    /// Viewport covering the whole texture.
+    pub fn viewport(&self) -> D3D12_VIEWPORT {
+        full_viewport(self.size)
+    }
+
+    /// Scissor rect covering the whole texture.
+    pub fn scissor_rect(&self) -> RECT {
+        full_scissor_rect(self.size)
+    }
+
+    /// Transition the texture from PIXEL_SHADER_RESOURCE to RENDER_TARGET, and bind it as the only render target.
+    pub fn begin_render(&self, command_list: &ID3D12GraphicsCommandList7) {
+        let rtv = self.rtv();
+        unsafe {
+            command_list.ResourceBarrier(&[transition_barrier(
+                &self.texture,
+                D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE,
+                D3D12_RESOURCE_STATE_RENDER_TARGET,
+            )]);
+            command_list.OMSetRenderTargets(1, Some(&rtv), false, None);
+        }
+    }
+
+    /// Transition the texture from RENDER_TARGET back to PIXEL_SHADER_RESOURCE, so that it can be sampled.
+    pub fn end_render(&self, command_list: &ID3D12GraphicsCommandList7) {
+        unsafe {
+            command_list.ResourceBarrier(&[transition_barrier(
+                &self.texture,
+                D3D12_RESOURCE_STATE_RENDER_TARGET,
+                D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE,
+            )]);
+        }
+    }
+}
+
+/// Several off-screen color textures of the same size, rendered to together as multiple render targets (MRT), e.g. a
+/// G-buffer, and then sampled from in a later pass.
+///
+/// Like [`OffscreenTarget`], but with contiguous RTVs, and contiguous SRVs in one shader-visible descriptor heap, so
+/// that a single descriptor table starting at `srv(0)` covers every texture. The textures rest in the
+/// PIXEL_SHADER_RESOURCE state, and are transitioned to RENDER_TARGET between
+/// [`OffscreenTargetGroup::begin_render`] and [`OffscreenTargetGroup::end_render`].
+pub struct OffscreenTargetGroup {
+    textures: Vec<ID3D12Resource>,
+    rtvs: Vec<D3D12_CPU_DESCRIPTOR_HANDLE>,
+    _rtv_heap: ID3D12DescriptorHeap,
+    srv_heap: ID3D12DescriptorHeap,
+    srv_increment: u32,
+    size: UVec2,
+    formats: Vec<DXGI_FORMAT>,
+    /// State the textures are in outside of [`OffscreenTargetGroup::begin_render`] and
+    /// [`OffscreenTargetGroup::end_render`].
+    resting_state: D3D12_RESOURCE_STATES,
+    /// From [`Gpu::debug_layer_enabled`].
+    validate: bool,
+}
+
+impl OffscreenTargetGroup {
+    /// Create one texture for each of `formats`, in order. At most `D3D12_SIMULTANEOUS_RENDER_TARGET_COUNT` (8).
+    pub fn new(gpu: &Gpu, size: UVec2, formats: &[DXGI_FORMAT]) -> Result<Self, DxError> {
+        Self::with_state(
+            gpu,
+            size,
+            formats,
+            D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE,
+            None,
+        )
+    }
+
+    /// [`OffscreenTargetGroup::new`], with the textures resting in `resting_state` instead, and optionally an
+    /// optimized clear color for every texture.
+    pub(crate) fn with_state(
+        gpu: &Gpu,
+        size: UVec2,
+        formats: &[DXGI_FORMAT],
+        resting_state: D3D12_RESOURCE_STATES,
+        clear_color: Option<[f32; 4]>,
+    ) -> Result<Self, DxError> {
+        assert!(
+            !formats.is_empty() && formats.len() <= D3D12_SIMULTANEOUS_RENDER_TARGET_COUNT as usize,
+            "BevyDirectX: OffscreenTargetGroup needs between 1 and 8 formats"
+        );
+
+        let count = formats.len() as u32;
+        let rtv_heap = gpu.create_descriptor_heap(D3D12_DESCRIPTOR_HEAP_TYPE_RTV, count, false)?;
+        let srv_heap =
+            gpu.create_descriptor_heap(D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV, count, true)?;
+        let (rtv_increment, srv_increment) = unsafe {
+            (
+                gpu.device
+                    .GetDescriptorHandleIncrementSize(D3D12_DESCRIPTOR_HEAP_TYPE_RTV),
+                gpu.device
+                    .GetDescriptorHandleIncrementSize(D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV),
+            )
+        };
+
+        let mut textures = Vec::with_capacity(formats.len());
+        let mut rtvs = Vec::with_capacity(formats.len());
+        for (i, &format) in formats.iter().enumerate() {
+            let texture = gpu.create_texture_2d(
+                size.x,
+                size.y,
+                format,
+                D3D12_RESOURCE_FLAG_ALLOW_RENDER_TARGET,
+                resting_state,
+                clear_color
+                    .map(|color| D3D12_CLEAR_VALUE {
+                        Format: format,
+                        Anonymous: D3D12_CLEAR_VALUE_0 { Color: color },
+                    })
+                    .as_ref(),
+            )?;
+            let mut rtv = unsafe { rtv_heap.GetCPUDescriptorHandleForHeapStart() };
+            rtv.ptr += i * rtv_increment as usize;
+            unsafe { gpu.device.CreateRenderTargetView(&texture, None, rtv) };
+
+            textures.push(texture);
+            rtvs.push(rtv);
+        }
+
+        let group = Self {
+            textures,
+            rtvs,
+            _rtv_heap: rtv_heap,
+            srv_heap,
+            srv_increment,
+            size,
+            formats: formats.to_vec(),
+            resting_state,
+            validate: gpu.debug_layer_enabled(),
+        };
+        group.create_srvs(gpu, unsafe {
+            group.srv_heap.GetCPUDescriptorHandleForHeapStart()
+        });
+        Ok(group)
+    }
+
+    /// Write SRVs of every texture, in order, to contiguous descriptors starting at `start`, e.g. to put them in the
+    /// same descriptor table as other resources.
+    pub fn create_srvs(&self, gpu: &Gpu, start: D3D12_CPU_DESCRIPTOR_HANDLE) {
+        for (i, texture) in self.textures.iter().enumerate() {
+            let mut srv = start;
+            srv.ptr += i * self.srv_increment as usize;
+            unsafe { gpu.device.CreateShaderResourceView(texture, None, srv) };
+        }
+    }
+
+    pub fn textures(&self) -> &[ID3D12Resource] {
+        &self.textures
+    }
+
+    /// RTVs of every texture, in the order of the formats the group was created with.
+    pub fn rtvs(&self) -> &[D3D12_CPU_DESCRIPTOR_HANDLE] {
+        &self.rtvs
+    }
+
+    /// Shader-visible descriptor heap containing only the SRVs of every texture, in order.
+    pub fn srv_heap(&self) -> &ID3D12DescriptorHeap {
+        &self.srv_heap
+    }
+
+    /// SRV of texture `index`. The SRVs of later textures directly follow it in [`OffscreenTargetGroup::srv_heap`].
+    pub fn srv(&self, index: usize) -> D3D12_GPU_DESCRIPTOR_HANDLE {
+        let mut srv = unsafe { self.srv_heap.GetGPUDescriptorHandleForHeapStart() };
+        srv.ptr += (index * self.srv_increment as usize) as u64;
+        srv
+    }
+
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    pub fn formats(&self) -> &[DXGI_FORMAT] {
+        &self.formats
+    }
+
+    /// Viewport covering the whole of every texture.
+    pub fn viewport(&self) -> D3D12_VIEWPORT {
+        full_viewport(self.size)
+    }
+
+    /// Scissor rect covering the whole of every texture.
+    pub fn scissor_rect(&self) -> RECT {
+        full_scissor_rect(self.size)
+    }
+
+    /// Set `NumRenderTargets` and `RTVFormats` of a pipeline that renders to the group, see
+    /// [`set_pipeline_rtv_formats`].
+    pub fn set_pipeline_formats(&self, desc: &mut D3D12_GRAPHICS_PIPELINE_STATE_DESC) {
+        set_pipeline_rtv_formats(desc, &self.formats);
+    }
+
+    /// Log an error if a pipeline rendering to the group doesn't have a matching number of render targets, or
+    /// matching formats, or a `DSVFormat` other than `dsv_format`, which otherwise only shows up as a debug layer
+    /// error at draw time. `dsv_format` is the format of the depth stencil view bound along with the group, or
+    /// `DXGI_FORMAT_UNKNOWN` for none.
+    ///
+    /// Only checked with [`crate::GpuConfig::debug_layer`], does nothing otherwise.
+    pub fn check_pipeline_formats(
+        &self,
+        desc: &D3D12_GRAPHICS_PIPELINE_STATE_DESC,
+        dsv_format: DXGI_FORMAT,
+    ) {
+        if self.validate {
+            check_pipeline_formats(desc, "offscreen target group", &self.formats, dsv_format);
+        }
+    }
+
+    /// Transition every texture from PIXEL_SHADER_RESOURCE to RENDER_TARGET, and bind them all as render targets, in
+    /// order, with an optional depth stencil view.
+    pub fn begin_render(
+        &self,
+        command_list: &ID3D12GraphicsCommandList7,
+        dsv: Option<D3D12_CPU_DESCRIPTOR_HANDLE>,
+    ) {
+        let barriers = self
+            .textures
+            .iter()
+            .map(|texture| {
+                transition_barrier(
+                    texture,
+                    self.resting_state,
+                    D3D12_RESOURCE_STATE_RENDER_TARGET,
+                )
+            })
+            .collect::<Vec<_>>();
+        unsafe { command_list.ResourceBarrier(&barriers) };
+        set_render_targets(command_list, &self.rtvs, dsv);
+    }
+
+    /// Transition every texture from RENDER_TARGET back to PIXEL_SHADER_RESOURCE, so that they can be sampled.
+    pub fn end_render(&self, command_list: &ID3D12GraphicsCommandList7) {
+        let barriers = self
+            .textures
+            .iter()
+            .map(|texture| {
+                transition_barrier(
+                    texture,
+                    D3D12_RESOURCE_STATE_RENDER_TARGET,
+                    self.resting_state,
+                )
+            })
+            .collect::<Vec<_>>();
+        unsafe { command_list.ResourceBarrier(&barriers) };
+    }
+}
+
+/// Bind `rtvs` as render targets 0, 1, 2..., with an optional depth stencil view. Pipelines drawn with them bound
+/// must have matching `RTVFormats`, see [`set_pipeline_rtv_formats`].
+///
+/// The RTVs don't need to be contiguous in a descriptor heap.
+pub fn set_render_targets(
+    command_list: &ID3D12GraphicsCommandList7,
+    rtvs: &[D3D12_CPU_DESCRIPTOR_HANDLE],
+    dsv: Option<D3D12_CPU_DESCRIPTOR_HANDLE>,
+) {
+    assert!(
+        rtvs.len() <= D3D12_SIMULTANEOUS_RENDER_TARGET_COUNT as usize,
+        "BevyDirectX: At most 8 render targets can be bound at once"
+    );
+    unsafe {
+        command_list.OMSetRenderTargets(
+            rtvs.len() as u32,
+            (!rtvs.is_empty()).then_some(rtvs.as_ptr()),
+            false,
+            dsv.as_ref().map(|dsv| dsv as *const _),
+        );
+    }
+}
+
+/// Log an error if `desc` doesn't render to exactly `rtv_formats` of `target`, and `dsv_format`.
+pub(crate) fn check_pipeline_formats(
+    desc: &D3D12_GRAPHICS_PIPELINE_STATE_DESC,
+    target: &str,
+    rtv_formats: &[DXGI_FORMAT],
+    dsv_format: DXGI_FORMAT,
+) {
+    let pipeline_formats = &desc.RTVFormats[..(desc.NumRenderTargets as usize).min(8)];
+    if pipeline_formats != rtv_formats {
+        error!(
+            "BevyDirectX: Pipeline RTVFormats are {pipeline_formats:?}, but the {target} formats are {rtv_formats:?}"
+        );
+    }
+    if desc.DSVFormat != dsv_format {
+        error!(
+            "BevyDirectX: Pipeline DSVFormat is {:?}, but the depth stencil view bound with the {target} is {dsv_format:?}",
+            desc.DSVFormat
+        );
+    }
+}
+
+/// Set `NumRenderTargets` and `RTVFormats` of a pipeline that renders to multiple render targets, and enable color
+/// writes to each of them, which only matters if `BlendState.IndependentBlendEnable` is set. Formats after
+/// `formats.len()` are reset to `DXGI_FORMAT_UNKNOWN`.
+pub fn set_pipeline_rtv_formats(
+    desc: &mut D3D12_GRAPHICS_PIPELINE_STATE_DESC,
+    formats: &[DXGI_FORMAT],
+) {
+    assert!(
+        formats.len() <= D3D12_SIMULTANEOUS_RENDER_TARGET_COUNT as usize,
+        "BevyDirectX: At most 8 render target formats can be set"
+    );
+
+    desc.NumRenderTargets = formats.len() as u32;
+    desc.RTVFormats = Default::default();
+    desc.RTVFormats[..formats.len()].copy_from_slice(formats);
+    for render_target in &mut desc.BlendState.RenderTarget[..formats.len()] {
+        if render_target.RenderTargetWriteMask == 0 {
+            render_target.RenderTargetWriteMask = D3D12_COLOR_WRITE_ENABLE_ALL.0 as u8;
+        }
+    }
+}
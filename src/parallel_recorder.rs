@@ -0,0 +1,86 @@
+use crate::{error::DxError, gpu::Gpu};
+use bevy::tasks::ComputeTaskPool;
+use windows::{core::Interface, Win32::Graphics::Direct3D12::*};
+
+/// Records command lists in parallel on Bevy's [`ComputeTaskPool`], and submits them in a deterministic order with a
+/// single `ExecuteCommandLists`, to spread the CPU cost of recording draw-heavy frames across threads.
+///
+/// Each task records into its own command allocator and list, which are kept and reused across frames. Lists are
+/// submitted in task index order, so barriers recorded in one list are seen by all later lists. Relative to
+/// [`Gpu::command_list`], lists execute in the order they're submitted to [`Gpu::queue`], e.g. call
+/// [`Gpu::execute_command_list`] before [`ParallelRecorder::submit`] for its commands to run first.
+#[derive(Default)]
+pub struct ParallelRecorder {
+    command_lists: Vec<(ID3D12CommandAllocator, ID3D12GraphicsCommandList7)>,
+    /// Number of lists recorded by the last [`ParallelRecorder::record`].
+    recorded: usize,
+}
+
+impl ParallelRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `task_count` command lists in parallel, calling `record(task_index, command_list)` once per task with
+    /// a freshly reset list. Lists are closed afterwards, ready for [`ParallelRecorder::submit`].
+    ///
+    /// The GPU must have finished executing the lists submitted last time, e.g. via [`Gpu::wait_for_fence`]. Each
+    /// list starts with no state set, so tasks must set their own descriptor heaps, root signature, pipeline, render
+    /// targets, viewports, and scissor rects. Requires [`ComputeTaskPool`] to be initialized, as it is by Bevy's
+    /// `TaskPoolPlugin`.
+    pub fn record<F>(&mut self, gpu: &Gpu, task_count: usize, record: F) -> Result<(), DxError>
+    where
+        F: Fn(usize, &ID3D12GraphicsCommandList7) + Sync,
+    {
+        self.recorded = 0;
+        while self.command_lists.len() < task_count {
+            unsafe {
+                let command_allocator = gpu
+                    .device
+                    .CreateCommandAllocator(D3D12_COMMAND_LIST_TYPE_DIRECT)?;
+                let command_list: ID3D12GraphicsCommandList7 = gpu.device.CreateCommandList(
+                    0,
+                    D3D12_COMMAND_LIST_TYPE_DIRECT,
+                    &command_allocator,
+                    None,
+                )?;
+                command_list.Close()?;
+                self.command_lists.push((command_allocator, command_list));
+            }
+        }
+
+        let record = &record;
+        let results = ComputeTaskPool::get().scope(|scope| {
+            for (task_index, (command_allocator, command_list)) in
+                self.command_lists[..task_count].iter().enumerate()
+            {
+                scope.spawn(async move {
+                    unsafe {
+                        command_allocator.Reset()?;
+                        command_list.Reset(command_allocator, None)?;
+                    }
+                    record(task_index, command_list);
+                    unsafe { command_list.Close() }
+                });
+            }
+        });
+        for result in results {
+            result?;
+        }
+
+        self.recorded = task_count;
+        Ok(())
+    }
+
+    /// Submit the lists from the last [`ParallelRecorder::record`] to [`Gpu::queue`] in task index order, with a
+    /// single `ExecuteCommandLists`. Call [`Gpu::signal_fence`] afterwards to be able to wait on them.
+    pub fn submit(&self, gpu: &Gpu) {
+        let command_lists: Vec<_> = self.command_lists[..self.recorded]
+            .iter()
+            .map(|(_, command_list)| Some(command_list.cast::<ID3D12CommandList>().unwrap()))
+            .collect();
+        if !command_lists.is_empty() {
+            unsafe { gpu.queue.ExecuteCommandLists(&command_lists) };
+        }
+    }
+}
@@ -0,0 +1,396 @@
+use crate::{error::DxError, format_support::pipeline_creation_error};
+use bevy::prelude::{info, warn};
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    slice,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+use windows::{
+    core::{Error, HSTRING, PCSTR},
+    Win32::{
+        Foundation::E_INVALIDARG,
+        Graphics::{Direct3D12::*, Dxgi::Common::DXGI_FORMAT},
+    },
+};
+
+/// Identifies pipeline cache files, followed by [`CACHE_FORMAT_VERSION`] as a little endian `u32`.
+const CACHE_MAGIC: &[u8; 8] = b"BDXPSOLB";
+/// Version of the pipeline cache file format, including how pipeline keys are hashed. Bump this when either changes.
+const CACHE_FORMAT_VERSION: u32 = 1;
+const CACHE_HEADER_LEN: usize = CACHE_MAGIC.len() + 4;
+
+/// Caches compiled pipeline state objects using an [`ID3D12PipelineLibrary`], optionally persisted to disk
+/// so that pipelines don't need to be recompiled on each launch.
+///
+/// Pipelines are keyed by a hash of their description, including shader bytecode. The root signature is not
+/// part of the key, so pipelines using different root signatures must also differ in some other way.
+///
+/// The file on disk starts with a header holding the cache format version, and files from other versions are discarded.
+///
+/// Can be used from multiple threads at once. Requests for the same pipeline are serialized, as the library doesn't
+/// allow loading the same pipeline concurrently, so the second waits for the first to compile it, then loads it.
+pub struct PipelineCache {
+    library: ID3D12PipelineLibrary,
+    path: Option<PathBuf>,
+    dirty: AtomicBool,
+    /// Held while loading or storing the pipeline with each name.
+    key_locks: Mutex<HashMap<String, Arc<Mutex<()>>>>,
+    // Must outlive the library, which uses the data after the header
+    _data: Vec<u8>,
+}
+
+impl PipelineCache {
+    /// Create a pipeline cache, loading existing data from `path` if it exists.
+    ///
+    /// If the cached data was created by a different driver or adapter, it is discarded and the cache starts empty.
+    pub fn new(device: &ID3D12Device9, path: Option<PathBuf>) -> Result<Self, DxError> {
+        let mut data = path
+            .as_ref()
+            .and_then(|path| fs::read(path).ok())
+            .unwrap_or_default();
+        if !data.is_empty() && !has_current_header(&data) {
+            warn!("BevyDirectX: Discarding pipeline cache from a different version of the cache format");
+            data.clear();
+        }
+
+        let library_data = data.get(CACHE_HEADER_LEN..).unwrap_or_default();
+        let (library, data) = match unsafe { device.CreatePipelineLibrary(library_data) } {
+            Ok(library) => (library, data),
+            Err(e) if !data.is_empty() => {
+                warn!("BevyDirectX: Discarding invalid or outdated pipeline cache: {e}");
+                (unsafe { device.CreatePipelineLibrary(&[]) }?, Vec::new())
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            library,
+            path,
+            dirty: AtomicBool::new(false),
+            key_locks: Mutex::new(HashMap::new()),
+            _data: data,
+        })
+    }
+
+    /// Load a graphics pipeline from the cache, or create and store it if not present.
+    pub fn create_graphics_pipeline(
+        &self,
+        device: &ID3D12Device9,
+        desc: &D3D12_GRAPHICS_PIPELINE_STATE_DESC,
+    ) -> Result<ID3D12PipelineState, DxError> {
+        let name = graphics_pipeline_key(desc);
+        let key_lock = self.key_lock(&name);
+        let _guard = key_lock.lock().unwrap();
+        match unsafe { self.library.LoadGraphicsPipeline(&name, desc) } {
+            Ok(pipeline) => Ok(pipeline),
+            Err(e) if e.code() == E_INVALIDARG => {
+                let pipeline = unsafe { device.CreateGraphicsPipelineState(desc) }
+                    .map_err(|e| pipeline_creation_error(device, desc, e))?;
+                self.store(&name, &pipeline);
+                Ok(pipeline)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Load a compute pipeline from the cache, or create and store it if not present.
+    pub fn create_compute_pipeline(
+        &self,
+        device: &ID3D12Device9,
+        desc: &D3D12_COMPUTE_PIPELINE_STATE_DESC,
+    ) -> Result<ID3D12PipelineState, DxError> {
+        let name = compute_pipeline_key(desc);
+        let key_lock = self.key_lock(&name);
+        let _guard = key_lock.lock().unwrap();
+
+        match unsafe { self.library.LoadComputePipeline(&name, desc) } {
+            Ok(pipeline) => Ok(pipeline),
+            Err(e) if e.code() == E_INVALIDARG => {
+                let pipeline = unsafe { device.CreateComputePipelineState(desc) }?;
+                self.store(&name, &pipeline);
+                Ok(pipeline)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn key_lock(&self, name: &HSTRING) -> Arc<Mutex<()>> {
+        let mut key_locks = self.key_locks.lock().unwrap();
+        key_locks.entry(name.to_string()).or_default().clone()
+    }
+
+    fn store(&self, name: &HSTRING, pipeline: &ID3D12PipelineState) {
+        match unsafe { self.library.StorePipeline(name, pipeline) } {
+            Ok(()) => self.dirty.store(true, Ordering::Relaxed),
+            Err(e) => warn!("BevyDirectX: Failed to store pipeline {name} in pipeline cache: {e}"),
+        }
+    }
+
+    /// Write the cache to disk, if a path was configured and new pipelines were added since the last save.
+    pub fn save(&self) -> Result<(), DxError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if !self.dirty.swap(false, Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        let mut data = vec![0; CACHE_HEADER_LEN + unsafe { self.library.GetSerializedSize() }];
+        data[..CACHE_MAGIC.len()].copy_from_slice(CACHE_MAGIC);
+        data[CACHE_MAGIC.len()..CACHE_HEADER_LEN]
+            .copy_from_slice(&CACHE_FORMAT_VERSION.to_le_bytes());
+        unsafe { self.library.Serialize(&mut data[CACHE_HEADER_LEN..]) }?;
+        fs::write(path, &data).map_err(Error::from)?;
+
+        info!(
+            "BevyDirectX: Saved pipeline cache to {} ({} KB)",
+            path.display(),
+            data.len() / 1000
+        );
+        Ok(())
+    }
+}
+
+fn has_current_header(data: &[u8]) -> bool {
+    data.len() >= CACHE_HEADER_LEN
+        && data[..CACHE_MAGIC.len()] == CACHE_MAGIC[..]
+        && data[CACHE_MAGIC.len()..CACHE_HEADER_LEN] == CACHE_FORMAT_VERSION.to_le_bytes()
+}
+
+fn compute_pipeline_key(desc: &D3D12_COMPUTE_PIPELINE_STATE_DESC) -> HSTRING {
+    let mut hasher = KeyHasher::new();
+    hasher.write_slice(b"compute");
+    hasher.write_shader(&desc.CS);
+    hasher.write_u32(desc.NodeMask);
+    hasher.write_i32(desc.Flags.0);
+    hasher.finish()
+}
+
+fn graphics_pipeline_key(desc: &D3D12_GRAPHICS_PIPELINE_STATE_DESC) -> HSTRING {
+    let mut hasher = KeyHasher::new();
+    hasher.write_slice(b"graphics");
+    for shader in [&desc.VS, &desc.PS, &desc.DS, &desc.HS, &desc.GS] {
+        hasher.write_shader(shader);
+    }
+
+    let stream_output = &desc.StreamOutput;
+    let declarations = unsafe {
+        slice_or_empty(
+            stream_output.pSODeclaration,
+            stream_output.NumEntries as usize,
+        )
+    };
+    hasher.write_u32(declarations.len() as u32);
+    for declaration in declarations {
+        hasher.write_u32(declaration.Stream);
+        hasher.write_slice(unsafe { pcstr_bytes(&declaration.SemanticName) });
+        hasher.write_u32(declaration.SemanticIndex);
+        hasher.write_slice(&[declaration.StartComponent, declaration.ComponentCount]);
+        hasher.write_slice(&[declaration.OutputSlot]);
+    }
+    let strides = unsafe {
+        slice_or_empty(
+            stream_output.pBufferStrides,
+            stream_output.NumStrides as usize,
+        )
+    };
+    hasher.write_u32(strides.len() as u32);
+    strides.iter().for_each(|&stride| hasher.write_u32(stride));
+    hasher.write_u32(stream_output.RasterizedStream);
+
+    let blend = &desc.BlendState;
+    hasher.write_i32(blend.AlphaToCoverageEnable.0);
+    hasher.write_i32(blend.IndependentBlendEnable.0);
+    for target in &blend.RenderTarget {
+        hasher.write_i32(target.BlendEnable.0);
+        hasher.write_i32(target.LogicOpEnable.0);
+        hasher.write_i32(target.SrcBlend.0);
+        hasher.write_i32(target.DestBlend.0);
+        hasher.write_i32(target.BlendOp.0);
+        hasher.write_i32(target.SrcBlendAlpha.0);
+        hasher.write_i32(target.DestBlendAlpha.0);
+        hasher.write_i32(target.BlendOpAlpha.0);
+        hasher.write_i32(target.LogicOp.0);
+        hasher.write_slice(&[target.RenderTargetWriteMask]);
+    }
+    hasher.write_u32(desc.SampleMask);
+
+    let rasterizer = &desc.RasterizerState;
+    hasher.write_i32(rasterizer.FillMode.0);
+    hasher.write_i32(rasterizer.CullMode.0);
+    hasher.write_i32(rasterizer.FrontCounterClockwise.0);
+    hasher.write_i32(rasterizer.DepthBias);
+    hasher.write_u32(rasterizer.DepthBiasClamp.to_bits());
+    hasher.write_u32(rasterizer.SlopeScaledDepthBias.to_bits());
+    hasher.write_i32(rasterizer.DepthClipEnable.0);
+    hasher.write_i32(rasterizer.MultisampleEnable.0);
+    hasher.write_i32(rasterizer.AntialiasedLineEnable.0);
+    hasher.write_u32(rasterizer.ForcedSampleCount);
+    hasher.write_i32(rasterizer.ConservativeRaster.0);
+
+    let depth_stencil = &desc.DepthStencilState;
+    hasher.write_i32(depth_stencil.DepthEnable.0);
+    hasher.write_i32(depth_stencil.DepthWriteMask.0);
+    hasher.write_i32(depth_stencil.DepthFunc.0);
+    hasher.write_i32(depth_stencil.StencilEnable.0);
+    hasher.write_slice(&[
+        depth_stencil.StencilReadMask,
+        depth_stencil.StencilWriteMask,
+    ]);
+    for face in [&depth_stencil.FrontFace, &depth_stencil.BackFace] {
+        hasher.write_i32(face.StencilFailOp.0);
+        hasher.write_i32(face.StencilDepthFailOp.0);
+        hasher.write_i32(face.StencilPassOp.0);
+        hasher.write_i32(face.StencilFunc.0);
+    }
+
+    let input_layout = unsafe {
+        slice_or_empty(
+            desc.InputLayout.pInputElementDescs,
+            desc.InputLayout.NumElements as usize,
+        )
+    };
+    hasher.write_u32(input_layout.len() as u32);
+    for element in input_layout {
+        hasher.write_slice(unsafe { pcstr_bytes(&element.SemanticName) });
+        hasher.write_u32(element.SemanticIndex);
+        hasher.write_i32(element.Format.0);
+        hasher.write_u32(element.InputSlot);
+        hasher.write_u32(element.AlignedByteOffset);
+        hasher.write_i32(element.InputSlotClass.0);
+        hasher.write_u32(element.InstanceDataStepRate);
+    }
+
+    hasher.write_i32(desc.IBStripCutValue.0);
+    hasher.write_i32(desc.PrimitiveTopologyType.0);
+    hasher.write_u32(desc.NumRenderTargets);
+    desc.RTVFormats
+        .iter()
+        .for_each(|format: &DXGI_FORMAT| hasher.write_i32(format.0));
+    hasher.write_i32(desc.DSVFormat.0);
+    hasher.write_u32(desc.SampleDesc.Count);
+    hasher.write_u32(desc.SampleDesc.Quality);
+    hasher.write_u32(desc.NodeMask);
+    hasher.write_i32(desc.Flags.0);
+
+    hasher.finish()
+}
+
+/// 64-bit FNV-1a over explicitly serialized fields. Keys are persisted with the cache, so unlike `DefaultHasher` and
+/// `Hash` impls, the result must not change between builds, Rust versions, or platforms.
+struct KeyHasher(u64);
+
+impl KeyHasher {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    fn write_i32(&mut self, value: i32) {
+        self.write(&value.to_le_bytes());
+    }
+
+    /// Prefixed with its length, so that adjacent variable length fields can't be confused.
+    fn write_slice(&mut self, bytes: &[u8]) {
+        self.write(&(bytes.len() as u64).to_le_bytes());
+        self.write(bytes);
+    }
+
+    fn write_shader(&mut self, shader: &D3D12_SHADER_BYTECODE) {
+        self.write_slice(unsafe {
+            slice_or_empty(shader.pShaderBytecode as *const u8, shader.BytecodeLength)
+        });
+    }
+
+    fn finish(self) -> HSTRING {
+        HSTRING::from(format!("{:016x}", self.0))
+    }
+}
+
+unsafe fn pcstr_bytes(string: &PCSTR) -> &[u8] {
+    if string.is_null() {
+        &[]
+    } else {
+        string.as_bytes()
+    }
+}
+
+unsafe fn slice_or_empty<'a, T>(data: *const T, len: usize) -> &'a [T] {
+    if data.is_null() || len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(data, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_hasher_is_fnv1a() {
+        // Reference values from the FNV specification
+        let mut hasher = KeyHasher::new();
+        hasher.write(b"");
+        assert_eq!(hasher.0, 0xcbf2_9ce4_8422_2325);
+        let mut hasher = KeyHasher::new();
+        hasher.write(b"a");
+        assert_eq!(hasher.0, 0xaf63_dc4c_8601_ec8c);
+        let mut hasher = KeyHasher::new();
+        hasher.write(b"foobar");
+        assert_eq!(hasher.0, 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn compute_pipeline_key_is_stable() {
+        let bytecode = [1u8, 2, 3, 4];
+        let desc = D3D12_COMPUTE_PIPELINE_STATE_DESC {
+            CS: D3D12_SHADER_BYTECODE {
+                pShaderBytecode: bytecode.as_ptr() as _,
+                BytecodeLength: bytecode.len(),
+            },
+            ..Default::default()
+        };
+        assert_eq!(compute_pipeline_key(&desc), compute_pipeline_key(&desc));
+
+        let other_bytecode = [1u8, 2, 3, 5];
+        let other_desc = D3D12_COMPUTE_PIPELINE_STATE_DESC {
+            CS: D3D12_SHADER_BYTECODE {
+                pShaderBytecode: other_bytecode.as_ptr() as _,
+                BytecodeLength: other_bytecode.len(),
+            },
+            ..Default::default()
+        };
+        assert_ne!(
+            compute_pipeline_key(&desc),
+            compute_pipeline_key(&other_desc)
+        );
+    }
+
+    #[test]
+    fn header_must_match_current_version() {
+        let mut data = CACHE_MAGIC.to_vec();
+        data.extend_from_slice(&CACHE_FORMAT_VERSION.to_le_bytes());
+        assert!(has_current_header(&data));
+        data[CACHE_MAGIC.len()] ^= 1;
+        assert!(!has_current_header(&data));
+        assert!(!has_current_header(&CACHE_MAGIC[..]));
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{
+    error::DxError, gpu::Gpu, mapped_buffer::MappedBuffer, resource_tracker::transition_barrier,
+};
+use std::mem;
+use windows::Win32::Graphics::Direct3D12::*;
+
+/// A heap of binary occlusion queries, along with buffers for reading the results back on the CPU
+/// and for GPU predication.
+///
+/// Each query's result is a `u64` that is 0 if no samples passed the depth/stencil test, and 1 otherwise.
+pub struct OcclusionQueryHeap {
+    heap: ID3D12QueryHeap,
+    readback_buffer: ID3D12Resource,
+    predication_buffer: ID3D12Resource,
+    count: u32,
+}
+
+impl OcclusionQueryHeap {
+    pub fn new(gpu: &Gpu, count: u32) -> Result<Self, DxError> {
+        let mut heap = None;
+        unsafe {
+            gpu.device.CreateQueryHeap(
+                &D3D12_QUERY_HEAP_DESC {
+                    Type: D3D12_QUERY_HEAP_TYPE_OCCLUSION,
+                    Count: count,
+                    NodeMask: 0,
+                },
+                &mut heap,
+            )?;
+        }
+
+        let size = count as u64 * mem::size_of::<u64>() as u64;
+        let readback_buffer = gpu.create_buffer(
+            size,
+            D3D12_HEAP_TYPE_READBACK,
+            D3D12_RESOURCE_FLAG_NONE,
+            D3D12_RESOURCE_STATE_COPY_DEST,
+        )?;
+        let predication_buffer = gpu.create_buffer(
+            size,
+            D3D12_HEAP_TYPE_DEFAULT,
+            D3D12_RESOURCE_FLAG_NONE,
+            D3D12_RESOURCE_STATE_PREDICATION,
+        )?;
+
+        Ok(Self {
+            heap: heap.unwrap(),
+            readback_buffer,
+            predication_buffer,
+            count,
+        })
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn begin_query(&self, command_list: &ID3D12GraphicsCommandList7, index: u32) {
+        unsafe { command_list.BeginQuery(&self.heap, D3D12_QUERY_TYPE_BINARY_OCCLUSION, index) };
+    }
+
+    pub fn end_query(&self, command_list: &ID3D12GraphicsCommandList7, index: u32) {
+        unsafe { command_list.EndQuery(&self.heap, D3D12_QUERY_TYPE_BINARY_OCCLUSION, index) };
+    }
+
+    /// Resolve all queries into both the readback buffer and the predication buffer.
+    ///
+    /// Must be called after the final [`OcclusionQueryHeap::end_query`], and before using
+    /// [`OcclusionQueryHeap::read_results`] or [`OcclusionQueryHeap::set_predication`].
+    pub fn resolve(&self, command_list: &ID3D12GraphicsCommandList7) {
+        unsafe {
+            command_list.ResolveQueryData(
+                &self.heap,
+                D3D12_QUERY_TYPE_BINARY_OCCLUSION,
+                0,
+                self.count,
+                &self.readback_buffer,
+                0,
+            );
+
+            command_list.ResourceBarrier(&[transition_barrier(
+                &self.predication_buffer,
+                D3D12_RESOURCE_STATE_PREDICATION,
+                D3D12_RESOURCE_STATE_COPY_DEST,
+            )]);
+            command_list.ResolveQueryData(
+                &self.heap,
+                D3D12_QUERY_TYPE_BINARY_OCCLUSION,
+                0,
+                self.count,
+                &self.predication_buffer,
+                0,
+            );
+            command_list.ResourceBarrier(&[transition_barrier(
+                &self.predication_buffer,
+                D3D12_RESOURCE_STATE_COPY_DEST,
+                D3D12_RESOURCE_STATE_PREDICATION,
+            )]);
+        }
+    }
+
+    /// Read back the resolved query results on the CPU.
+    ///
+    /// The command list containing [`OcclusionQueryHeap::resolve`] must have finished executing on the GPU.
+    pub fn read_results(&self) -> Result<Vec<u64>, DxError> {
+        let results = MappedBuffer::<u64>::read_write(&self.readback_buffer)?;
+        Ok(results.as_slice().to_vec())
+    }
+
+    /// Skip subsequent rendering and dispatch commands if the given query found no visible samples.
+    pub fn set_predication(&self, command_list: &ID3D12GraphicsCommandList7, index: u32) {
+        unsafe {
+            command_list.SetPredication(
+                &self.predication_buffer,
+                index as u64 * mem::size_of::<u64>() as u64,
+                D3D12_PREDICATION_OP_EQUAL_ZERO,
+            )
+        };
+    }
+
+    /// Disable predication set by [`OcclusionQueryHeap::set_predication`].
+    pub fn clear_predication(&self, command_list: &ID3D12GraphicsCommandList7) {
+        unsafe { command_list.SetPredication(None, 0, D3D12_PREDICATION_OP_EQUAL_ZERO) };
+    }
+}
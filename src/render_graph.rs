@@ -0,0 +1,181 @@
+use crate::{
+    error::DxError,
+    gpu::Gpu,
+    resource_tracker::{uav_barrier, ResourceTracker},
+    swapchain::WindowRenderTarget,
+};
+use windows::Win32::Graphics::Direct3D12::*;
+
+/// Opt-in helper for ordering render passes and automatically inserting barriers between them.
+///
+/// Passes declare the resources they read and write, along with the state each resource must be in.
+/// Passes execute in the order they were added, and before each pass the graph transitions its resources
+/// using a [`ResourceTracker`], and inserts UAV barriers between consecutive unordered access writes.
+///
+/// All resources used by the graph must already be registered with the [`ResourceTracker`], except for the window's
+/// backbuffer. To have the graph manage the whole frame, import it with [`RenderGraph::import_backbuffer`], declare
+/// the final pass's write to it with [`RenderGraphPass::write_backbuffer`], and finish with
+/// [`RenderGraph::execute_and_present`] instead of [`RenderGraph::execute`].
+#[derive(Default)]
+pub struct RenderGraph<'a> {
+    passes: Vec<RenderGraphPass<'a>>,
+    backbuffer: Option<&'a mut WindowRenderTarget>,
+}
+
+/// A single pass within a [`RenderGraph`].
+pub struct RenderGraphPass<'a> {
+    name: String,
+    reads: Vec<(ID3D12Resource, D3D12_RESOURCE_STATES)>,
+    writes: Vec<(ID3D12Resource, D3D12_RESOURCE_STATES)>,
+    writes_backbuffer: bool,
+    record: Box<dyn FnOnce(&ID3D12GraphicsCommandList7) + 'a>,
+}
+
+impl<'a> RenderGraph<'a> {
+    /// Add a pass to the end of the graph. The `record` closure is called during [`RenderGraph::execute`]
+    /// after all of the pass's resources have been transitioned.
+    pub fn add_pass(
+        &mut self,
+        name: impl Into<String>,
+        record: impl FnOnce(&ID3D12GraphicsCommandList7) + 'a,
+    ) -> &mut RenderGraphPass<'a> {
+        self.passes.push(RenderGraphPass {
+            name: name.into(),
+            reads: Vec::new(),
+            writes: Vec::new(),
+            writes_backbuffer: false,
+            record: Box::new(record),
+        });
+        self.passes.last_mut().unwrap()
+    }
+
+    /// Use the texture returned by `render_target`'s [`WindowRenderTarget::rtv`] as a graph resource, for passes to
+    /// declare with [`RenderGraphPass::write_backbuffer`].
+    ///
+    /// Its state is tracked by the render target, see [`WindowRenderTarget::rtv_state`], rather than by the
+    /// [`ResourceTracker`], so it must not be registered with the tracker. Get the RTV to render to from
+    /// [`WindowRenderTarget::rtv`] before importing it.
+    pub fn import_backbuffer(&mut self, render_target: &'a mut WindowRenderTarget) {
+        self.backbuffer = Some(render_target);
+    }
+
+    /// Record all passes into the command list in order, inserting barriers as needed.
+    ///
+    /// The command list should come from [`crate::Gpu::reset_commands`], and is left open for further recording. An
+    /// imported backbuffer is left in the state of the last pass to use it, see [`RenderGraph::execute_and_present`]
+    /// to present it.
+    pub fn execute(self, tracker: &mut ResourceTracker, command_list: &ID3D12GraphicsCommandList7) {
+        self.record(tracker, command_list);
+    }
+
+    /// Record all passes into [`Gpu::command_list`] like [`RenderGraph::execute`], then transition the imported
+    /// backbuffer to PRESENT, upscale it if needed, and submit, present, and signal the fence with
+    /// [`Gpu::submit_and_present`], completing the frame.
+    ///
+    /// The command list must have been reset with [`Gpu::reset_commands`], and the frame waited for with
+    /// [`crate::wait_for_ready_frame`], as usual. Not for use with [`crate::BevyDirectXPlugin::manage_backbuffer`],
+    /// which presents in [`crate::end_frame`] instead. Panics if no backbuffer was imported.
+    pub fn execute_and_present(
+        self,
+        tracker: &mut ResourceTracker,
+        gpu: &mut Gpu,
+    ) -> Result<(), DxError> {
+        let command_list = gpu.command_list().clone();
+        let render_target = self
+            .record(tracker, &command_list)
+            .expect("BevyDirectX: RenderGraph::execute_and_present() needs an imported backbuffer");
+
+        render_target.transition_rtv(&command_list, D3D12_RESOURCE_STATE_PRESENT);
+        render_target.upscale_to_backbuffer(&command_list);
+        gpu.submit_and_present(&*render_target)
+    }
+
+    /// Record all passes, returning the imported backbuffer.
+    fn record(
+        self,
+        tracker: &mut ResourceTracker,
+        command_list: &ID3D12GraphicsCommandList7,
+    ) -> Option<&'a mut WindowRenderTarget> {
+        let RenderGraph {
+            passes,
+            mut backbuffer,
+        } = self;
+        let mut last_uav_writes: Vec<ID3D12Resource> = Vec::new();
+
+        for pass in passes {
+            // Consecutive unordered access to a resource written by the previous pass needs a UAV barrier
+            let uav_barriers = pass
+                .reads
+                .iter()
+                .chain(&pass.writes)
+                .filter(|(resource, state)| {
+                    *state == D3D12_RESOURCE_STATE_UNORDERED_ACCESS
+                        && tracker.state(resource) == Some(D3D12_RESOURCE_STATE_UNORDERED_ACCESS)
+                        && last_uav_writes.contains(resource)
+                })
+                .map(|(resource, _)| uav_barrier(Some(resource)))
+                .collect::<Vec<_>>();
+            if !uav_barriers.is_empty() {
+                unsafe { command_list.ResourceBarrier(&uav_barriers) };
+            }
+
+            // Transition resources into the states this pass needs
+            let transitions = pass
+                .reads
+                .iter()
+                .chain(&pass.writes)
+                .map(|(resource, state)| {
+                    if tracker.state(resource).is_none() {
+                        panic!(
+                            "BevyDirectX: RenderGraph pass \"{}\" uses a resource not registered with ResourceTracker",
+                            pass.name
+                        );
+                    }
+                    (resource, *state)
+                })
+                .collect::<Vec<_>>();
+            tracker.transition_many(&transitions, command_list);
+            if pass.writes_backbuffer {
+                let Some(render_target) = &mut backbuffer else {
+                    panic!(
+                        "BevyDirectX: RenderGraph pass \"{}\" writes the backbuffer, but none was imported",
+                        pass.name
+                    );
+                };
+                render_target.transition_rtv(command_list, D3D12_RESOURCE_STATE_RENDER_TARGET);
+            }
+
+            last_uav_writes = pass
+                .writes
+                .iter()
+                .filter(|(_, state)| *state == D3D12_RESOURCE_STATE_UNORDERED_ACCESS)
+                .map(|(resource, _)| resource.clone())
+                .collect();
+
+            (pass.record)(command_list);
+        }
+
+        backbuffer
+    }
+}
+
+impl<'a> RenderGraphPass<'a> {
+    /// Declare that this pass reads from a resource in the given state.
+    pub fn read(&mut self, resource: &ID3D12Resource, state: D3D12_RESOURCE_STATES) -> &mut Self {
+        self.reads.push((resource.clone(), state));
+        self
+    }
+
+    /// Declare that this pass writes to a resource in the given state.
+    pub fn write(&mut self, resource: &ID3D12Resource, state: D3D12_RESOURCE_STATES) -> &mut Self {
+        self.writes.push((resource.clone(), state));
+        self
+    }
+
+    /// Declare that this pass renders to the backbuffer imported with [`RenderGraph::import_backbuffer`], in the
+    /// RENDER_TARGET state.
+    pub fn write_backbuffer(&mut self) -> &mut Self {
+        self.writes_backbuffer = true;
+        self
+    }
+}
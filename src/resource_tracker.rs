@@ -0,0 +1,342 @@
+use bevy::{
+    prelude::{error, Resource},
+    utils::HashMap,
+};
+use std::mem::{transmute_copy, ManuallyDrop};
+use windows::{
+    core::Interface,
+    Win32::Graphics::{Direct3D::WKPDID_D3DDebugObjectNameW, Direct3D12::*},
+};
+
+/// Tracks the current state of a set of resources, and records transition barriers only when needed.
+///
+/// Resources must be registered via [`ResourceTracker::track`] with their initial state before transitioning them.
+#[derive(Resource, Default)]
+pub struct ResourceTracker {
+    states: HashMap<usize, (ID3D12Resource, D3D12_RESOURCE_STATES)>,
+}
+
+impl ResourceTracker {
+    /// Start tracking a resource that is currently in the given state.
+    pub fn track(&mut self, resource: &ID3D12Resource, state: D3D12_RESOURCE_STATES) {
+        self.states
+            .insert(resource.as_raw() as usize, (resource.clone(), state));
+    }
+
+    /// Stop tracking a resource, releasing the tracker's reference to it.
+    pub fn untrack(&mut self, resource: &ID3D12Resource) {
+        self.states.remove(&(resource.as_raw() as usize));
+    }
+
+    /// Current state of a tracked resource, or `None` if the resource is not tracked.
+    pub fn state(&self, resource: &ID3D12Resource) -> Option<D3D12_RESOURCE_STATES> {
+        self.states
+            .get(&(resource.as_raw() as usize))
+            .map(|(_, state)| *state)
+    }
+
+    /// Transition a tracked resource to a new state, recording a barrier into the command list if the
+    /// resource is not already in that state.
+    pub fn transition(
+        &mut self,
+        resource: &ID3D12Resource,
+        new_state: D3D12_RESOURCE_STATES,
+        command_list: &ID3D12GraphicsCommandList7,
+    ) {
+        let Some((_, state)) = self.states.get_mut(&(resource.as_raw() as usize)) else {
+            panic!("BevyDirectX: Attempted to transition a resource not registered with ResourceTracker");
+        };
+
+        if *state == new_state {
+            return;
+        }
+
+        unsafe { command_list.ResourceBarrier(&[transition_barrier(resource, *state, new_state)]) };
+        *state = new_state;
+    }
+
+    /// Transition several tracked resources at once, batching any needed barriers into a single call.
+    pub fn transition_many(
+        &mut self,
+        transitions: &[(&ID3D12Resource, D3D12_RESOURCE_STATES)],
+        command_list: &ID3D12GraphicsCommandList7,
+    ) {
+        let mut barriers = Vec::with_capacity(transitions.len());
+        for (resource, new_state) in transitions {
+            let Some((_, state)) = self.states.get_mut(&(resource.as_raw() as usize)) else {
+                panic!("BevyDirectX: Attempted to transition a resource not registered with ResourceTracker");
+            };
+
+            if *state != *new_state {
+                barriers.push(transition_barrier(resource, *state, *new_state));
+                *state = *new_state;
+            }
+        }
+
+        if !barriers.is_empty() {
+            unsafe { command_list.ResourceBarrier(&barriers) };
+        }
+    }
+
+    /// Log an error if a resource about to be used, e.g. by a draw, dispatch, or copy, isn't in the `expected` state
+    /// or isn't tracked, naming the resource by the name given to `ID3D12Object::SetName`.
+    ///
+    /// A resource in a combined state such as `D3D12_RESOURCE_STATE_GENERIC_READ` satisfies any state it includes.
+    /// Only checked in debug builds, does nothing in release builds.
+    pub fn assert_state(&self, resource: &ID3D12Resource, expected: D3D12_RESOURCE_STATES) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+
+        let name = || debug_name(resource).unwrap_or_else(|| format!("{:?}", resource.as_raw()));
+        match self.state(resource) {
+            None => error!(
+                "BevyDirectX: Expected resource {} to be in state {expected:?}, but it isn't registered with ResourceTracker",
+                name()
+            ),
+            Some(state) if !state_satisfies(state, expected) => error!(
+                "BevyDirectX: Expected resource {} to be in state {expected:?}, but it's in state {state:?}",
+                name()
+            ),
+            Some(_) => {}
+        }
+    }
+
+    /// [`ResourceTracker::assert_state`] for several resources at once.
+    pub fn assert_states(&self, expected: &[(&ID3D12Resource, D3D12_RESOURCE_STATES)]) {
+        for (resource, expected) in expected {
+            self.assert_state(resource, *expected);
+        }
+    }
+}
+
+fn state_satisfies(state: D3D12_RESOURCE_STATES, expected: D3D12_RESOURCE_STATES) -> bool {
+    // COMMON is no bits set, so would otherwise be satisfied by every state
+    if expected == D3D12_RESOURCE_STATE_COMMON {
+        state == D3D12_RESOURCE_STATE_COMMON
+    } else {
+        state & expected == expected
+    }
+}
+
+/// Name given to `ID3D12Object::SetName`, if any.
+fn debug_name(object: &ID3D12Resource) -> Option<String> {
+    let mut size = 0;
+    unsafe { object.GetPrivateData(&WKPDID_D3DDebugObjectNameW, &mut size, None) }.ok()?;
+    let mut name = vec![0u16; size as usize / 2];
+    unsafe {
+        object.GetPrivateData(
+            &WKPDID_D3DDebugObjectNameW,
+            &mut size,
+            Some(name.as_mut_ptr() as _),
+        )
+    }
+    .ok()?;
+
+    let len = name.iter().position(|c| *c == 0).unwrap_or(name.len());
+    Some(String::from_utf16_lossy(&name[..len]))
+}
+
+/// Build a transition barrier for all subresources of a resource.
+///
+/// The barrier does not hold a reference to the resource, so the resource must outlive the barrier.
+pub fn transition_barrier(
+    resource: &ID3D12Resource,
+    state_before: D3D12_RESOURCE_STATES,
+    state_after: D3D12_RESOURCE_STATES,
+) -> D3D12_RESOURCE_BARRIER {
+    subresource_transition_barrier(
+        resource,
+        D3D12_RESOURCE_BARRIER_ALL_SUBRESOURCES,
+        state_before,
+        state_after,
+    )
+}
+
+/// Build a transition barrier for a single subresource of a resource, e.g. one mip level of a texture.
+///
+/// The barrier does not hold a reference to the resource, so the resource must outlive the barrier.
+pub fn subresource_transition_barrier(
+    resource: &ID3D12Resource,
+    subresource: u32,
+    state_before: D3D12_RESOURCE_STATES,
+    state_after: D3D12_RESOURCE_STATES,
+) -> D3D12_RESOURCE_BARRIER {
+    D3D12_RESOURCE_BARRIER {
+        Type: D3D12_RESOURCE_BARRIER_TYPE_TRANSITION,
+        Flags: D3D12_RESOURCE_BARRIER_FLAG_NONE,
+        Anonymous: D3D12_RESOURCE_BARRIER_0 {
+            Transition: ManuallyDrop::new(D3D12_RESOURCE_TRANSITION_BARRIER {
+                pResource: unsafe { transmute_copy(resource) },
+                Subresource: subresource,
+                StateBefore: state_before,
+                StateAfter: state_after,
+            }),
+        },
+    }
+}
+
+/// Build an enhanced barrier transitioning all subresources of a texture between layouts, for
+/// `ID3D12GraphicsCommandList7::Barrier`. Synchronizes with the accesses each layout is used for by this crate, e.g.
+/// render target writes for `D3D12_BARRIER_LAYOUT_RENDER_TARGET`, and with no accesses for
+/// `D3D12_BARRIER_LAYOUT_PRESENT`, so is only correct for PRESENT when the texture isn't accessed in that layout
+/// within the command list, as for swapchain backbuffers.
+///
+/// The barrier does not hold a reference to the resource, so the resource must outlive the barrier.
+pub fn texture_layout_barrier(
+    resource: &ID3D12Resource,
+    layout_before: D3D12_BARRIER_LAYOUT,
+    layout_after: D3D12_BARRIER_LAYOUT,
+) -> D3D12_TEXTURE_BARRIER {
+    let (sync_before, access_before) = layout_sync_access(layout_before);
+    let (sync_after, access_after) = layout_sync_access(layout_after);
+    D3D12_TEXTURE_BARRIER {
+        SyncBefore: sync_before,
+        SyncAfter: sync_after,
+        AccessBefore: access_before,
+        AccessAfter: access_after,
+        LayoutBefore: layout_before,
+        LayoutAfter: layout_after,
+        pResource: unsafe { transmute_copy(resource) },
+        // All mips, array slices, and planes
+        Subresources: D3D12_BARRIER_SUBRESOURCE_RANGE {
+            IndexOrFirstMipLevel: u32::MAX,
+            ..Default::default()
+        },
+        Flags: D3D12_TEXTURE_BARRIER_FLAG_NONE,
+    }
+}
+
+/// [`texture_layout_barrier`] for textures that are also accessed in the PRESENT (COMMON) layout within the command
+/// list, unlike swapchain backbuffers, e.g. an intermediate texture that's sampled right after rendering to it.
+/// Transitions to or from PRESENT synchronize with all accesses, instead of none.
+pub(crate) fn common_texture_layout_barrier(
+    resource: &ID3D12Resource,
+    layout_before: D3D12_BARRIER_LAYOUT,
+    layout_after: D3D12_BARRIER_LAYOUT,
+) -> D3D12_TEXTURE_BARRIER {
+    let mut barrier = texture_layout_barrier(resource, layout_before, layout_after);
+    if layout_before == D3D12_BARRIER_LAYOUT_PRESENT {
+        barrier.SyncBefore = D3D12_BARRIER_SYNC_ALL;
+        barrier.AccessBefore = D3D12_BARRIER_ACCESS_COMMON;
+    }
+    if layout_after == D3D12_BARRIER_LAYOUT_PRESENT {
+        barrier.SyncAfter = D3D12_BARRIER_SYNC_ALL;
+        barrier.AccessAfter = D3D12_BARRIER_ACCESS_COMMON;
+    }
+    barrier
+}
+
+/// Record texture barriers built with [`texture_layout_barrier`] as a single barrier group.
+pub(crate) fn record_texture_barriers(
+    command_list: &ID3D12GraphicsCommandList7,
+    barriers: &[D3D12_TEXTURE_BARRIER],
+) {
+    unsafe {
+        command_list.Barrier(&[D3D12_BARRIER_GROUP {
+            Type: D3D12_BARRIER_TYPE_TEXTURE,
+            NumBarriers: barriers.len() as u32,
+            Anonymous: D3D12_BARRIER_GROUP_0 {
+                pTextureBarriers: barriers.as_ptr(),
+            },
+        }])
+    };
+}
+
+/// Layout equivalent to a legacy resource state, for interop between the two barrier models.
+pub(crate) fn state_layout(state: D3D12_RESOURCE_STATES) -> D3D12_BARRIER_LAYOUT {
+    match state {
+        // PRESENT and COMMON are the same state, and the same layout
+        D3D12_RESOURCE_STATE_COMMON => D3D12_BARRIER_LAYOUT_PRESENT,
+        D3D12_RESOURCE_STATE_RENDER_TARGET => D3D12_BARRIER_LAYOUT_RENDER_TARGET,
+        D3D12_RESOURCE_STATE_UNORDERED_ACCESS => D3D12_BARRIER_LAYOUT_UNORDERED_ACCESS,
+        D3D12_RESOURCE_STATE_DEPTH_WRITE => D3D12_BARRIER_LAYOUT_DEPTH_STENCIL_WRITE,
+        D3D12_RESOURCE_STATE_DEPTH_READ => D3D12_BARRIER_LAYOUT_DEPTH_STENCIL_READ,
+        D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE
+        | D3D12_RESOURCE_STATE_NON_PIXEL_SHADER_RESOURCE
+        | D3D12_RESOURCE_STATE_ALL_SHADER_RESOURCE => D3D12_BARRIER_LAYOUT_SHADER_RESOURCE,
+        D3D12_RESOURCE_STATE_COPY_SOURCE => D3D12_BARRIER_LAYOUT_COPY_SOURCE,
+        D3D12_RESOURCE_STATE_COPY_DEST => D3D12_BARRIER_LAYOUT_COPY_DEST,
+        D3D12_RESOURCE_STATE_RESOLVE_SOURCE => D3D12_BARRIER_LAYOUT_RESOLVE_SOURCE,
+        D3D12_RESOURCE_STATE_RESOLVE_DEST => D3D12_BARRIER_LAYOUT_RESOLVE_DEST,
+        _ => D3D12_BARRIER_LAYOUT_GENERIC_READ,
+    }
+}
+
+fn layout_sync_access(layout: D3D12_BARRIER_LAYOUT) -> (D3D12_BARRIER_SYNC, D3D12_BARRIER_ACCESS) {
+    match layout {
+        D3D12_BARRIER_LAYOUT_PRESENT => (D3D12_BARRIER_SYNC_NONE, D3D12_BARRIER_ACCESS_NO_ACCESS),
+        D3D12_BARRIER_LAYOUT_RENDER_TARGET => (
+            D3D12_BARRIER_SYNC_RENDER_TARGET,
+            D3D12_BARRIER_ACCESS_RENDER_TARGET,
+        ),
+        D3D12_BARRIER_LAYOUT_UNORDERED_ACCESS => (
+            D3D12_BARRIER_SYNC_ALL_SHADING,
+            D3D12_BARRIER_ACCESS_UNORDERED_ACCESS,
+        ),
+        D3D12_BARRIER_LAYOUT_DEPTH_STENCIL_WRITE => (
+            D3D12_BARRIER_SYNC_DEPTH_STENCIL,
+            D3D12_BARRIER_ACCESS_DEPTH_STENCIL_WRITE,
+        ),
+        D3D12_BARRIER_LAYOUT_DEPTH_STENCIL_READ => (
+            D3D12_BARRIER_SYNC_DEPTH_STENCIL,
+            D3D12_BARRIER_ACCESS_DEPTH_STENCIL_READ,
+        ),
+        D3D12_BARRIER_LAYOUT_SHADER_RESOURCE => (
+            D3D12_BARRIER_SYNC_ALL_SHADING,
+            D3D12_BARRIER_ACCESS_SHADER_RESOURCE,
+        ),
+        D3D12_BARRIER_LAYOUT_COPY_SOURCE => {
+            (D3D12_BARRIER_SYNC_COPY, D3D12_BARRIER_ACCESS_COPY_SOURCE)
+        }
+        D3D12_BARRIER_LAYOUT_COPY_DEST => (D3D12_BARRIER_SYNC_COPY, D3D12_BARRIER_ACCESS_COPY_DEST),
+        D3D12_BARRIER_LAYOUT_RESOLVE_SOURCE => (
+            D3D12_BARRIER_SYNC_RESOLVE,
+            D3D12_BARRIER_ACCESS_RESOLVE_SOURCE,
+        ),
+        D3D12_BARRIER_LAYOUT_RESOLVE_DEST => (
+            D3D12_BARRIER_SYNC_RESOLVE,
+            D3D12_BARRIER_ACCESS_RESOLVE_DEST,
+        ),
+        _ => (D3D12_BARRIER_SYNC_ALL, D3D12_BARRIER_ACCESS_COMMON),
+    }
+}
+
+/// Build a UAV barrier for a resource, or for all UAV accesses if `resource` is `None`.
+pub fn uav_barrier(resource: Option<&ID3D12Resource>) -> D3D12_RESOURCE_BARRIER {
+    D3D12_RESOURCE_BARRIER {
+        Type: D3D12_RESOURCE_BARRIER_TYPE_UAV,
+        Flags: D3D12_RESOURCE_BARRIER_FLAG_NONE,
+        Anonymous: D3D12_RESOURCE_BARRIER_0 {
+            UAV: ManuallyDrop::new(D3D12_RESOURCE_UAV_BARRIER {
+                pResource: resource.map_or(ManuallyDrop::new(None), |resource| unsafe {
+                    transmute_copy(resource)
+                }),
+            }),
+        },
+    }
+}
+
+/// Build an aliasing barrier, marking `after` as the placed resource now using memory shared with `before`.
+///
+/// `None` for `before` means any resource sharing memory with `after` may have been in use.
+pub fn aliasing_barrier(
+    before: Option<&ID3D12Resource>,
+    after: Option<&ID3D12Resource>,
+) -> D3D12_RESOURCE_BARRIER {
+    let resource = |resource: Option<&ID3D12Resource>| {
+        resource.map_or(ManuallyDrop::new(None), |resource| unsafe {
+            transmute_copy(resource)
+        })
+    };
+    D3D12_RESOURCE_BARRIER {
+        Type: D3D12_RESOURCE_BARRIER_TYPE_ALIASING,
+        Flags: D3D12_RESOURCE_BARRIER_FLAG_NONE,
+        Anonymous: D3D12_RESOURCE_BARRIER_0 {
+            Aliasing: ManuallyDrop::new(D3D12_RESOURCE_ALIASING_BARRIER {
+                pResourceBefore: resource(before),
+                pResourceAfter: resource(after),
+            }),
+        },
+    }
+}
@@ -0,0 +1,36 @@
+use windows::Win32::Graphics::Direct3D12::*;
+
+/// Static sampler bound to `register(s<shader_register>)`, using `address_mode` on every axis, and sampling every
+/// mip, visible to all shader stages.
+pub fn static_sampler(
+    shader_register: u32,
+    filter: D3D12_FILTER,
+    address_mode: D3D12_TEXTURE_ADDRESS_MODE,
+) -> D3D12_STATIC_SAMPLER_DESC {
+    D3D12_STATIC_SAMPLER_DESC {
+        Filter: filter,
+        AddressU: address_mode,
+        AddressV: address_mode,
+        AddressW: address_mode,
+        MaxLOD: D3D12_FLOAT32_MAX,
+        ShaderRegister: shader_register,
+        ShaderVisibility: D3D12_SHADER_VISIBILITY_ALL,
+        ..Default::default()
+    }
+}
+
+/// [`static_sampler`] with anisotropic filtering, taking up to `max_anisotropy` samples along the direction a
+/// texture is viewed at, e.g. from a texture quality setting of 1x to 16x. Higher values keep textures viewed at
+/// grazing angles sharper, at the cost of bandwidth.
+///
+/// `max_anisotropy` is clamped to the range every D3D12 GPU supports, 1 to `D3D12_MAX_MAXANISOTROPY` (16).
+pub fn anisotropic_static_sampler(
+    shader_register: u32,
+    address_mode: D3D12_TEXTURE_ADDRESS_MODE,
+    max_anisotropy: u32,
+) -> D3D12_STATIC_SAMPLER_DESC {
+    D3D12_STATIC_SAMPLER_DESC {
+        MaxAnisotropy: max_anisotropy.clamp(1, D3D12_MAX_MAXANISOTROPY),
+        ..static_sampler(shader_register, D3D12_FILTER_ANISOTROPIC, address_mode)
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{
+    error::DxError,
+    gpu::Gpu,
+    mapped_buffer::MappedBuffer,
+    resource_tracker::{transition_barrier, uav_barrier},
+};
+use bevy::math::UVec2;
+use std::mem::transmute_copy;
+use windows::{
+    core::Error,
+    Win32::{
+        Foundation::E_NOTIMPL,
+        Graphics::{Direct3D12::*, Dxgi::Common::*},
+    },
+};
+
+/// A MIN_MIP sampler feedback map paired with a texture, recording the most detailed mip sampled from each region
+/// of the texture, e.g. for deciding which mips of a streamed texture to load.
+///
+/// Requires [`crate::GpuCapabilities::sampler_feedback_tier`] to be at least 0.9, and shaders compiled for shader
+/// model 6.5 or above with DXC, which FXC and [`crate::compile_shader`] can't produce. See
+/// `assets/sampler_feedback.hlsl` for a minimal pixel shader.
+///
+/// Per frame:
+/// 1. [`SamplerFeedbackMap::clear`] the map.
+/// 2. Draw with a shader calling `WriteSamplerFeedback()` on a `FeedbackTexture2D<SAMPLER_FEEDBACK_MIN_MIP>`, bound
+///    to a UAV created with [`SamplerFeedbackMap::create_uav`].
+/// 3. [`SamplerFeedbackMap::resolve`] the map.
+/// 4. Once the GPU has finished executing the command list, call [`SamplerFeedbackMap::read_min_mips`].
+///
+/// The map rests in the UNORDERED_ACCESS state.
+pub struct SamplerFeedbackMap {
+    feedback: ID3D12Resource,
+    decoded: ID3D12Resource,
+    readback_buffer: ID3D12Resource,
+    clear_heap: ID3D12DescriptorHeap,
+    region_size: UVec2,
+    region_count: UVec2,
+    row_pitch: u32,
+}
+
+impl SamplerFeedbackMap {
+    /// Create a feedback map for every mip of the 2D `texture`, with one entry per `region_size` texels of mip 0.
+    ///
+    /// `region_size` must be a power of two of at least 4 in each dimension.
+    pub fn new(gpu: &Gpu, texture: &ID3D12Resource, region_size: UVec2) -> Result<Self, DxError> {
+        if gpu.capabilities().sampler_feedback_tier == D3D12_SAMPLER_FEEDBACK_TIER_NOT_SUPPORTED {
+            return Err(Error::from(E_NOTIMPL).into());
+        }
+        assert!(
+            region_size.x >= 4
+                && region_size.y >= 4
+                && region_size.x.is_power_of_two()
+                && region_size.y.is_power_of_two(),
+            "BevyDirectX: SamplerFeedbackMap region size must be a power of two of at least 4, was {region_size}"
+        );
+
+        let texture_desc = unsafe { texture.GetDesc() };
+        assert_eq!(
+            texture_desc.Dimension, D3D12_RESOURCE_DIMENSION_TEXTURE2D,
+            "BevyDirectX: SamplerFeedbackMap can only be paired with a 2D texture"
+        );
+
+        let mut feedback = None;
+        unsafe {
+            gpu.device.CreateCommittedResource2(
+                &D3D12_HEAP_PROPERTIES {
+                    Type: D3D12_HEAP_TYPE_DEFAULT,
+                    ..Default::default()
+                },
+                D3D12_HEAP_FLAG_NONE,
+                &D3D12_RESOURCE_DESC1 {
+                    Dimension: D3D12_RESOURCE_DIMENSION_TEXTURE2D,
+                    Width: texture_desc.Width,
+                    Height: texture_desc.Height,
+                    DepthOrArraySize: texture_desc.DepthOrArraySize,
+                    MipLevels: texture_desc.MipLevels,
+                    Format: DXGI_FORMAT_SAMPLER_FEEDBACK_MIN_MIP_OPAQUE,
+                    SampleDesc: DXGI_SAMPLE_DESC {
+                        Count: 1,
+                        Quality: 0,
+                    },
+                    Layout: D3D12_TEXTURE_LAYOUT_UNKNOWN,
+                    Flags: D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS,
+                    SamplerFeedbackMipRegion: D3D12_MIP_REGION {
+                        Width: region_size.x,
+                        Height: region_size.y,
+                        Depth: 1,
+                    },
+                    ..Default::default()
+                },
+                D3D12_RESOURCE_STATE_UNORDERED_ACCESS,
+                None,
+                None,
+                &mut feedback,
+            )?;
+        }
+        let feedback: ID3D12Resource = feedback.unwrap();
+
+        // Decoding a MIN_MIP map produces one R8_UINT texel per region
+        let region_count = UVec2::new(
+            (texture_desc.Width as u32).div_ceil(region_size.x),
+            texture_desc.Height.div_ceil(region_size.y),
+        );
+        let decoded = gpu.create_texture_2d(
+            region_count.x,
+            region_count.y,
+            DXGI_FORMAT_R8_UINT,
+            D3D12_RESOURCE_FLAG_NONE,
+            D3D12_RESOURCE_STATE_RESOLVE_DEST,
+            None,
+        )?;
+        let row_pitch = region_count
+            .x
+            .next_multiple_of(D3D12_TEXTURE_DATA_PITCH_ALIGNMENT);
+        let readback_buffer = gpu.create_buffer(
+            row_pitch as u64 * region_count.y as u64,
+            D3D12_HEAP_TYPE_READBACK,
+            D3D12_RESOURCE_FLAG_NONE,
+            D3D12_RESOURCE_STATE_COPY_DEST,
+        )?;
+
+        // ClearUnorderedAccessViewUint() needs a CPU descriptor in a non-shader-visible heap
+        let clear_heap =
+            gpu.create_descriptor_heap(D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV, 1, false)?;
+        unsafe {
+            gpu.device.CreateSamplerFeedbackUnorderedAccessView(
+                texture,
+                &feedback,
+                clear_heap.GetCPUDescriptorHandleForHeapStart(),
+            );
+        }
+
+        Ok(Self {
+            feedback,
+            decoded,
+            readback_buffer,
+            clear_heap,
+            region_size,
+            region_count,
+            row_pitch,
+        })
+    }
+
+    /// Write a UAV descriptor for binding the map to a shader's `FeedbackTexture2D` at `destination`.
+    ///
+    /// `texture` must be the texture passed to [`SamplerFeedbackMap::new`].
+    pub fn create_uav(
+        &self,
+        gpu: &Gpu,
+        texture: &ID3D12Resource,
+        destination: D3D12_CPU_DESCRIPTOR_HANDLE,
+    ) {
+        unsafe {
+            gpu.device.CreateSamplerFeedbackUnorderedAccessView(
+                texture,
+                &self.feedback,
+                destination,
+            );
+        }
+    }
+
+    /// Reset the map to "not sampled" before recording new feedback.
+    ///
+    /// `uav` must be a UAV created with [`SamplerFeedbackMap::create_uav`], in the descriptor heap currently bound to
+    /// `command_list`.
+    pub fn clear(
+        &self,
+        command_list: &ID3D12GraphicsCommandList7,
+        uav: D3D12_GPU_DESCRIPTOR_HANDLE,
+    ) {
+        unsafe {
+            command_list.ClearUnorderedAccessViewUint(
+                uav,
+                self.clear_heap.GetCPUDescriptorHandleForHeapStart(),
+                &self.feedback,
+                &[u32::MAX; 4],
+                &[],
+            );
+            command_list.ResourceBarrier(&[uav_barrier(Some(&self.feedback))]);
+        }
+    }
+
+    /// Decode the map and copy it to a CPU-readable buffer for [`SamplerFeedbackMap::read_min_mips`].
+    ///
+    /// Must be recorded after all draws writing feedback.
+    pub fn resolve(&self, command_list: &ID3D12GraphicsCommandList7) {
+        unsafe {
+            command_list.ResourceBarrier(&[transition_barrier(
+                &self.feedback,
+                D3D12_RESOURCE_STATE_UNORDERED_ACCESS,
+                D3D12_RESOURCE_STATE_RESOLVE_SOURCE,
+            )]);
+            command_list.ResolveSubresourceRegion(
+                &self.decoded,
+                0,
+                0,
+                0,
+                &self.feedback,
+                0,
+                None,
+                DXGI_FORMAT_R8_UINT,
+                D3D12_RESOLVE_MODE_DECODE_SAMPLER_FEEDBACK,
+            );
+            command_list.ResourceBarrier(&[
+                transition_barrier(
+                    &self.feedback,
+                    D3D12_RESOURCE_STATE_RESOLVE_SOURCE,
+                    D3D12_RESOURCE_STATE_UNORDERED_ACCESS,
+                ),
+                transition_barrier(
+                    &self.decoded,
+                    D3D12_RESOURCE_STATE_RESOLVE_DEST,
+                    D3D12_RESOURCE_STATE_COPY_SOURCE,
+                ),
+            ]);
+
+            command_list.CopyTextureRegion(
+                &D3D12_TEXTURE_COPY_LOCATION {
+                    pResource: transmute_copy(&self.readback_buffer),
+                    Type: D3D12_TEXTURE_COPY_TYPE_PLACED_FOOTPRINT,
+                    Anonymous: D3D12_TEXTURE_COPY_LOCATION_0 {
+                        PlacedFootprint: D3D12_PLACED_SUBRESOURCE_FOOTPRINT {
+                            Offset: 0,
+                            Footprint: D3D12_SUBRESOURCE_FOOTPRINT {
+                                Format: DXGI_FORMAT_R8_UINT,
+                                Width: self.region_count.x,
+                                Height: self.region_count.y,
+                                Depth: 1,
+                                RowPitch: self.row_pitch,
+                            },
+                        },
+                    },
+                },
+                0,
+                0,
+                0,
+                &D3D12_TEXTURE_COPY_LOCATION {
+                    pResource: transmute_copy(&self.decoded),
+                    Type: D3D12_TEXTURE_COPY_TYPE_SUBRESOURCE_INDEX,
+                    Anonymous: D3D12_TEXTURE_COPY_LOCATION_0 {
+                        SubresourceIndex: 0,
+                    },
+                },
+                None,
+            );
+            command_list.ResourceBarrier(&[transition_barrier(
+                &self.decoded,
+                D3D12_RESOURCE_STATE_COPY_SOURCE,
+                D3D12_RESOURCE_STATE_RESOLVE_DEST,
+            )]);
+        }
+    }
+
+    /// Read back the most detailed mip sampled from each region, row by row, as resolved by
+    /// [`SamplerFeedbackMap::resolve`]. Regions that were not sampled are `u8::MAX`.
+    ///
+    /// The command list containing [`SamplerFeedbackMap::resolve`] must have finished executing on the GPU.
+    pub fn read_min_mips(&self) -> Result<Vec<u8>, DxError> {
+        let readback = MappedBuffer::<u8>::read_write(&self.readback_buffer)?;
+        Ok(readback
+            .as_slice()
+            .chunks(self.row_pitch as usize)
+            .flat_map(|row| &row[..self.region_count.x as usize])
+            .copied()
+            .collect())
+    }
+
+    /// Size in texels of mip 0 covered by each entry of the map.
+    pub fn region_size(&self) -> UVec2 {
+        self.region_size
+    }
+
+    /// Number of regions in each dimension, i.e. the width and height of [`SamplerFeedbackMap::read_min_mips`].
+    pub fn region_count(&self) -> UVec2 {
+        self.region_count
+    }
+
+    pub fn feedback_resource(&self) -> &ID3D12Resource {
+        &self.feedback
+    }
+}
@@ -0,0 +1,83 @@
+use crate::{
+    error::DxError,
+    gpu::{wait_for_fence_value, Gpu},
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+use windows::Win32::Graphics::Direct3D12::*;
+
+/// A timeline of increasing values, signaled by GPU queues, that the CPU or other queues can wait on.
+///
+/// Wraps an `ID3D12Fence`, plus a counter for handing out new values to signal. Can be shared between threads and
+/// queues, e.g. in an `Arc`, to synchronize work across queues, or across frames.
+///
+/// There are two kinds of waits:
+/// * [`GpuSemaphore::wait_cpu`] blocks the calling thread until the value is reached. Use it before reading GPU
+///   results on the CPU, or reusing memory the GPU may still be accessing.
+/// * [`GpuSemaphore::wait_gpu`] returns immediately, and instead makes a queue stall before executing any work
+///   submitted after the wait, until the value is reached. Use it to order work between queues, without the CPU
+///   waiting.
+pub struct GpuSemaphore {
+    fence: ID3D12Fence,
+    counter: AtomicU64,
+    timeout: u32,
+}
+
+impl Gpu {
+    /// Create a new [`GpuSemaphore`] starting at value 0. CPU waits use the same timeout as [`Gpu::wait_for_fence`].
+    pub fn create_semaphore(&self) -> Result<GpuSemaphore, DxError> {
+        let fence = unsafe { self.device.CreateFence(0, D3D12_FENCE_FLAG_NONE)? };
+        Ok(GpuSemaphore {
+            fence,
+            counter: AtomicU64::new(0),
+            timeout: self.fence_timeout(),
+        })
+    }
+}
+
+impl GpuSemaphore {
+    /// Signal `value` once `queue` finishes all work submitted to it before this call.
+    ///
+    /// Values should only ever increase. Use [`GpuSemaphore::signal_next`] to get a new value from the counter instead.
+    pub fn signal(&self, queue: &ID3D12CommandQueue, value: u64) -> Result<(), DxError> {
+        self.counter.fetch_max(value, Ordering::Relaxed);
+        unsafe { queue.Signal(&self.fence, value)? };
+        Ok(())
+    }
+
+    /// Increment the counter, and signal the new value on `queue`, returning it for waiting on.
+    pub fn signal_next(&self, queue: &ID3D12CommandQueue) -> Result<u64, DxError> {
+        let value = self.counter.fetch_add(1, Ordering::Relaxed) + 1;
+        unsafe { queue.Signal(&self.fence, value)? };
+        Ok(value)
+    }
+
+    /// Block the calling thread until the semaphore reaches `value`.
+    ///
+    /// If the timeout elapses first, the GPU is assumed to have hung and an error is returned, which is the device
+    /// removed reason if the device was lost.
+    pub fn wait_cpu(&self, value: u64) -> Result<(), DxError> {
+        wait_for_fence_value(&self.fence, value, self.timeout)
+    }
+
+    /// Make `queue` wait until the semaphore reaches `value` before executing work submitted after this call.
+    ///
+    /// Doesn't block the calling thread.
+    pub fn wait_gpu(&self, queue: &ID3D12CommandQueue, value: u64) -> Result<(), DxError> {
+        unsafe { queue.Wait(&self.fence, value)? };
+        Ok(())
+    }
+
+    /// Latest value the semaphore has reached.
+    pub fn completed_value(&self) -> u64 {
+        unsafe { self.fence.GetCompletedValue() }
+    }
+
+    /// Highest value signaled, or to be signaled, so far. Waiting on it waits for all signaled work.
+    pub fn current_value(&self) -> u64 {
+        self.counter.load(Ordering::Relaxed)
+    }
+
+    pub fn fence(&self) -> &ID3D12Fence {
+        &self.fence
+    }
+}
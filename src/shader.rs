@@ -0,0 +1,65 @@
+use crate::error::DxError;
+use bevy::prelude::warn;
+use std::slice;
+use windows::{
+    core::{Error, PCSTR},
+    Win32::Graphics::Direct3D::{
+        Fxc::{D3DCompile, D3DCOMPILE_OPTIMIZATION_LEVEL3},
+        ID3DBlob,
+    },
+};
+
+/// Compile HLSL source code at runtime using the FXC compiler shipped with Windows.
+///
+/// Only supports shader models up to 5.1 (e.g. `"vs_5_1"`, `"ps_5_1"`, `"cs_5_1"`), and is intended for small
+/// built-in shaders. Prefer precompiling shaders to DXIL with DXC for anything else.
+///
+/// Compile errors are returned with the compiler's messages. Warnings are logged, and don't fail compilation.
+pub fn compile_shader(source: &str, entry_point: &str, target: &str) -> Result<Vec<u8>, DxError> {
+    let entry_point = format!("{entry_point}\0");
+    let target = format!("{target}\0");
+
+    let mut code = None;
+    let mut error = None;
+    let result = unsafe {
+        D3DCompile(
+            source.as_ptr() as _,
+            source.len(),
+            None,
+            None,
+            None,
+            PCSTR::from_raw(entry_point.as_ptr()),
+            PCSTR::from_raw(target.as_ptr()),
+            D3DCOMPILE_OPTIMIZATION_LEVEL3,
+            0,
+            &mut code,
+            Some(&mut error),
+        )
+    };
+
+    // Filled with warnings on success, and errors on failure
+    let messages = error
+        .map(|error| {
+            String::from_utf8_lossy(blob_bytes(&error))
+                .trim_end_matches('\0')
+                .trim()
+                .to_owned()
+        })
+        .unwrap_or_default();
+    if let Err(e) = result {
+        return Err(Error::new(
+            e.code(),
+            format!("BevyDirectX: Failed to compile shader: {messages}"),
+        )
+        .into());
+    }
+    if !messages.is_empty() {
+        warn!("BevyDirectX: Shader compiled with warnings: {messages}");
+    }
+
+    Ok(blob_bytes(&code.unwrap()).to_vec())
+}
+
+fn blob_bytes(blob: &ID3DBlob) -> &[u8] {
+    unsafe { slice::from_raw_parts(blob.GetBufferPointer() as *const u8, blob.GetBufferSize()) }
+}
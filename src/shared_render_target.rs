@@ -0,0 +1,157 @@
+use crate::{
+    error::DxError,
+    gpu::Gpu,
+    offscreen::{full_scissor_rect, full_viewport},
+    resource_tracker::transition_barrier,
+    shared_resource::SharedResource,
+};
+use bevy::math::UVec2;
+use windows::Win32::{
+    Foundation::{HANDLE, RECT},
+    Graphics::{
+        Direct3D12::*,
+        Dxgi::Common::{DXGI_FORMAT, DXGI_SAMPLE_DESC},
+    },
+};
+
+/// A color texture rendered to by this crate, and sampled by another device, e.g. to show the renderer's output as a
+/// Bevy `Image` in egui or Bevy UI through wgpu's D3D12 backend.
+///
+/// Built on a [`SharedResource`], so the other device opens the texture and fence via NT handles, and frames are
+/// handed back and forth with increasing fence values: [`SharedRenderTarget::acquire`] waits for the even value the
+/// other device last released with, and [`SharedRenderTarget::release`] signals the next odd value. The other device
+/// must wait for that odd value before sampling, and signal the following even value once it's done.
+///
+/// On the wgpu side, the texture and fence must be opened on the same adapter as [`Gpu`]:
+/// 1. Get wgpu's `ID3D12Device` through `wgpu::Device::as_hal::<wgpu::hal::api::Dx12, _, _>`, and open both
+///    handles with `ID3D12Device::OpenSharedHandle`.
+/// 2. Wrap the texture with `wgpu::hal::dx12::Device::texture_from_raw`, then
+///    `wgpu::Device::create_texture_from_hal`, with a matching format and `TEXTURE_BINDING` usage, and insert it
+///    into a Bevy `Image`'s GPU texture.
+/// 3. Each frame, before wgpu's commands sampling it are submitted, `ID3D12CommandQueue::Wait` on wgpu's raw queue
+///    for the released value, and `Signal` the next value after them.
+///
+/// The texture rests in the COMMON state, which it decays to at the end of each `ExecuteCommandLists` regardless, as
+/// it's created with `D3D12_RESOURCE_FLAG_ALLOW_SIMULTANEOUS_ACCESS`.
+pub struct SharedRenderTarget {
+    shared_resource: SharedResource,
+    rtv_heap: ID3D12DescriptorHeap,
+    size: UVec2,
+    format: DXGI_FORMAT,
+    /// Fence value the next [`SharedRenderTarget::acquire`] waits for.
+    acquire_key: u64,
+}
+
+impl SharedRenderTarget {
+    /// Create a shared texture of `size` and `format`, which must be a format the other API can import, such as
+    /// `DXGI_FORMAT_R8G8B8A8_UNORM` or `DXGI_FORMAT_B8G8R8A8_UNORM` for wgpu.
+    pub fn new(gpu: &Gpu, size: UVec2, format: DXGI_FORMAT) -> Result<Self, DxError> {
+        let shared_resource = gpu.create_shared_resource(
+            &D3D12_RESOURCE_DESC {
+                Dimension: D3D12_RESOURCE_DIMENSION_TEXTURE2D,
+                Width: size.x as u64,
+                Height: size.y,
+                DepthOrArraySize: 1,
+                MipLevels: 1,
+                Format: format,
+                SampleDesc: DXGI_SAMPLE_DESC {
+                    Count: 1,
+                    Quality: 0,
+                },
+                Layout: D3D12_TEXTURE_LAYOUT_UNKNOWN,
+                Flags: D3D12_RESOURCE_FLAG_ALLOW_RENDER_TARGET,
+                ..Default::default()
+            },
+            D3D12_RESOURCE_STATE_COMMON,
+        )?;
+        let rtv_heap = gpu.create_descriptor_heap(D3D12_DESCRIPTOR_HEAP_TYPE_RTV, 1, false)?;
+        unsafe {
+            gpu.device.CreateRenderTargetView(
+                shared_resource.resource(),
+                None,
+                rtv_heap.GetCPUDescriptorHandleForHeapStart(),
+            );
+        }
+
+        Ok(Self {
+            shared_resource,
+            rtv_heap,
+            size,
+            format,
+            acquire_key: 0,
+        })
+    }
+
+    pub fn texture(&self) -> &ID3D12Resource {
+        self.shared_resource.resource()
+    }
+
+    pub fn rtv(&self) -> D3D12_CPU_DESCRIPTOR_HANDLE {
+        unsafe { self.rtv_heap.GetCPUDescriptorHandleForHeapStart() }
+    }
+
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    pub fn format(&self) -> DXGI_FORMAT {
+        self.format
+    }
+
+    /// Viewport covering the whole texture.
+    pub fn viewport(&self) -> D3D12_VIEWPORT {
+        full_viewport(self.size)
+    }
+
+    /// Scissor rect covering the whole texture.
+    pub fn scissor_rect(&self) -> RECT {
+        full_scissor_rect(self.size)
+    }
+
+    /// Create NT handles for opening the texture and its fence on the other device, see [`SharedRenderTarget`]. The
+    /// caller owns both handles, and must close them with `CloseHandle` once opened.
+    pub fn create_shared_handles(&self, gpu: &Gpu) -> Result<(HANDLE, HANDLE), DxError> {
+        let texture = self.shared_resource.create_shared_handle(gpu)?;
+        let fence = self.shared_resource.create_fence_shared_handle(gpu)?;
+        Ok((texture, fence))
+    }
+
+    /// Make [`Gpu::queue`] wait until the other device has finished sampling the previous frame, before executing
+    /// work submitted after this call. Call before submitting the commands that render to the texture.
+    pub fn acquire(&self, gpu: &Gpu) -> Result<(), DxError> {
+        self.shared_resource.acquire(&gpu.queue, self.acquire_key)
+    }
+
+    /// Hand the texture to the other device once [`Gpu::queue`] finishes all work submitted before this call,
+    /// returning the fence value the other device must wait for. It must signal that value plus 1 once done with it.
+    pub fn release(&mut self, gpu: &Gpu) -> Result<u64, DxError> {
+        let key = self.acquire_key + 1;
+        self.shared_resource.release(&gpu.queue, key)?;
+        self.acquire_key = key + 1;
+        Ok(key)
+    }
+
+    /// Transition the texture from COMMON to RENDER_TARGET, and bind it as the only render target.
+    pub fn begin_render(&self, command_list: &ID3D12GraphicsCommandList7) {
+        let rtv = self.rtv();
+        unsafe {
+            command_list.ResourceBarrier(&[transition_barrier(
+                self.texture(),
+                D3D12_RESOURCE_STATE_COMMON,
+                D3D12_RESOURCE_STATE_RENDER_TARGET,
+            )]);
+            command_list.OMSetRenderTargets(1, Some(&rtv), false, None);
+        }
+    }
+
+    /// Transition the texture from RENDER_TARGET back to COMMON, for the other device to sample.
+    pub fn end_render(&self, command_list: &ID3D12GraphicsCommandList7) {
+        unsafe {
+            command_list.ResourceBarrier(&[transition_barrier(
+                self.texture(),
+                D3D12_RESOURCE_STATE_RENDER_TARGET,
+                D3D12_RESOURCE_STATE_COMMON,
+            )]);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use crate::{error::DxError, format_support::resource_creation_error, gpu::Gpu};
+use windows::{
+    core::{Error, Interface},
+    Win32::{
+        Foundation::{GENERIC_ALL, HANDLE, WAIT_ABANDONED, WAIT_TIMEOUT},
+        Graphics::{
+            Direct3D12::*,
+            Dxgi::{IDXGIKeyedMutex, DXGI_ERROR_WAIT_TIMEOUT},
+        },
+    },
+};
+
+/// A resource that other D3D12 devices, or other APIs such as D3D11 or Media Foundation, can open via a shared NT
+/// handle, plus a shared fence for synchronizing access to it.
+///
+/// Textures are created with `D3D12_RESOURCE_FLAG_ALLOW_SIMULTANEOUS_ACCESS`, and buffers always behave as if they
+/// were, so the resource can be read by one device while another writes to it, and it starts and ends each
+/// `ExecuteCommandLists` in the COMMON state. Depth stencil and multisampled textures can't be shared this way.
+///
+/// D3D12 has no keyed mutexes, so [`SharedResource::acquire`] and [`SharedResource::release`] use the shared fence
+/// with the same semantics instead: a device acquires the resource by waiting for a key, and hands it over by
+/// releasing it with a key. Unlike `IDXGIKeyedMutex`, whose keys are usually 0 and 1 alternated between two devices,
+/// fence keys must only ever increase. Use an increasing handoff counter, e.g. this device acquires even keys and
+/// releases odd keys, and the other device acquires odd keys and releases even keys. Acquiring a key nobody will
+/// release deadlocks both devices. For the other side's keyed mutex, see [`KeyedMutex`].
+pub struct SharedResource {
+    resource: ID3D12Resource,
+    fence: ID3D12Fence,
+}
+
+impl Gpu {
+    /// Create a committed resource in its own shared heap, plus a shared fence, see [`SharedResource`].
+    pub fn create_shared_resource(
+        &self,
+        desc: &D3D12_RESOURCE_DESC,
+        initial_state: D3D12_RESOURCE_STATES,
+    ) -> Result<SharedResource, DxError> {
+        // D3D12 rejects the flag on buffers, which always allow simultaneous access
+        let desc = D3D12_RESOURCE_DESC {
+            Flags: if desc.Dimension == D3D12_RESOURCE_DIMENSION_BUFFER {
+                desc.Flags
+            } else {
+                desc.Flags | D3D12_RESOURCE_FLAG_ALLOW_SIMULTANEOUS_ACCESS
+            },
+            ..*desc
+        };
+
+        let mut resource = None;
+        let fence;
+        unsafe {
+            self.device
+                .CreateCommittedResource(
+                    &D3D12_HEAP_PROPERTIES {
+                        Type: D3D12_HEAP_TYPE_DEFAULT,
+                        ..Default::default()
+                    },
+                    D3D12_HEAP_FLAG_SHARED,
+                    &desc,
+                    initial_state,
+                    None,
+                    &mut resource,
+                )
+                .map_err(|e| resource_creation_error(&self.device, &desc, e))?;
+            fence = self.device.CreateFence(0, D3D12_FENCE_FLAG_SHARED)?;
+        }
+
+        Ok(SharedResource {
+            resource: resource.unwrap(),
+            fence,
+        })
+    }
+}
+
+impl SharedResource {
+    pub fn resource(&self) -> &ID3D12Resource {
+        &self.resource
+    }
+
+    /// Fence backing [`SharedResource::acquire`] and [`SharedResource::release`].
+    pub fn fence(&self) -> &ID3D12Fence {
+        &self.fence
+    }
+
+    /// Create an NT handle for opening the resource on another device, e.g. with `ID3D12Device::OpenSharedHandle` or
+    /// `ID3D11Device1::OpenSharedResource1`. The caller owns the handle, and must close it with `CloseHandle`.
+    pub fn create_shared_handle(&self, gpu: &Gpu) -> Result<HANDLE, DxError> {
+        Ok(unsafe {
+            gpu.device
+                .CreateSharedHandle(&self.resource, None, GENERIC_ALL.0, None)?
+        })
+    }
+
+    /// Create an NT handle for opening [`SharedResource::fence`] on another device, e.g. with
+    /// `ID3D12Device::OpenSharedHandle` or `ID3D11Device5::OpenSharedFence`. The caller owns the handle, and must close
+    /// it with `CloseHandle`.
+    pub fn create_fence_shared_handle(&self, gpu: &Gpu) -> Result<HANDLE, DxError> {
+        Ok(unsafe {
+            gpu.device
+                .CreateSharedHandle(&self.fence, None, GENERIC_ALL.0, None)?
+        })
+    }
+
+    /// Make `queue` wait until the other device releases the resource with `key`, before executing work submitted
+    /// after this call. Doesn't block the calling thread.
+    pub fn acquire(&self, queue: &ID3D12CommandQueue, key: u64) -> Result<(), DxError> {
+        unsafe { queue.Wait(&self.fence, key)? };
+        Ok(())
+    }
+
+    /// Release the resource to the other device with `key`, once `queue` finishes all work submitted before this call.
+    pub fn release(&self, queue: &ID3D12CommandQueue, key: u64) -> Result<(), DxError> {
+        unsafe { queue.Signal(&self.fence, key)? };
+        Ok(())
+    }
+}
+
+/// Wrapper around the `IDXGIKeyedMutex` of a resource shared with `D3D11_RESOURCE_MISC_SHARED_KEYEDMUTEX`, for the
+/// D3D11 side of a cross-API share. D3D12 resources have no keyed mutex, see [`SharedResource`] instead.
+///
+/// Each side acquires with the key the other side last released with. With two devices, the convention is that the
+/// producer acquires key 0 and releases key 1, and the consumer acquires key 1 and releases key 0. Every acquire must
+/// be paired with a release on the same thread, and acquiring a key nobody will release blocks until the timeout.
+pub struct KeyedMutex(IDXGIKeyedMutex);
+
+impl KeyedMutex {
+    /// Get the keyed mutex of a shared resource. Returns [`DxError::Unsupported`] if it wasn't created with one.
+    pub fn new<T: Interface>(resource: &T) -> Result<Self, DxError> {
+        Ok(Self(resource.cast()?))
+    }
+
+    /// Block until the resource is released with `key`, or `timeout_ms` elapses. Returns an error on timeout.
+    pub fn acquire(&self, key: u64, timeout_ms: u32) -> Result<(), DxError> {
+        // Timeouts are success codes, which the generated AcquireSync() binding discards, so call it directly
+        let result = unsafe {
+            (Interface::vtable(&self.0).AcquireSync)(Interface::as_raw(&self.0), key, timeout_ms)
+        };
+        if result.0 == WAIT_TIMEOUT.0 as i32 || result.0 == WAIT_ABANDONED.0 as i32 {
+            return Err(Error::from(DXGI_ERROR_WAIT_TIMEOUT).into());
+        }
+        result.ok()?;
+        Ok(())
+    }
+
+    /// Release the resource to whoever acquires `key` next.
+    pub fn release(&self, key: u64) -> Result<(), DxError> {
+        unsafe { self.0.ReleaseSync(key)? };
+        Ok(())
+    }
+}
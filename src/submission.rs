@@ -0,0 +1,66 @@
+use crate::{
+    error::DxError,
+    gpu::{wait_for_fence_value, Gpu},
+};
+use windows::{core::Interface, Win32::Graphics::Direct3D12::*};
+
+/// Handle to command lists submitted with [`Gpu::submit`], which can be checked or waited on independently of other
+/// submissions.
+///
+/// Identified by the value [`Gpu`]'s fence is signaled with after the lists, so a submission is also complete once
+/// any later submission, or later [`Gpu::signal_fence`], is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Submission {
+    fence_value: u64,
+}
+
+impl Submission {
+    /// Value of [`Gpu`]'s fence that marks this submission as complete, e.g. for [`Gpu::on_fence_complete`] or
+    /// [`Gpu::queue_wait`].
+    pub fn fence_value(&self) -> u64 {
+        self.fence_value
+    }
+}
+
+impl Gpu {
+    /// Submit closed `command_lists` to [`Gpu::queue`] with a single `ExecuteCommandLists`, then signal the fence,
+    /// returning a [`Submission`] to check or wait on.
+    ///
+    /// Lists are submitted in order, within one call and across calls, including [`Gpu::execute_command_list`], but
+    /// only their submission order is guaranteed: the GPU can overlap work from consecutive lists. For B to read A's
+    /// results, record barriers for the dependency, e.g. a transition at the end of A or the start of B. Implicit
+    /// state promotion and decay happen at `ExecuteCommandLists` boundaries, so a state a resource was promoted to in
+    /// one list may have decayed back to common by the next. Each submission's fence value only guarantees its own
+    /// lists and everything submitted before them have completed.
+    ///
+    /// Each list's allocator can't be reset until its submission completes. An empty `command_lists` just signals.
+    pub fn submit(
+        &mut self,
+        command_lists: &[&ID3D12GraphicsCommandList7],
+    ) -> Result<Submission, DxError> {
+        let command_lists: Vec<_> = command_lists
+            .iter()
+            .map(|command_list| command_list.cast::<ID3D12CommandList>().map(Some))
+            .collect::<Result<_, _>>()?;
+        if !command_lists.is_empty() {
+            unsafe { self.queue.ExecuteCommandLists(&command_lists) };
+        }
+
+        let fence_value = self.next_fence_value();
+        self.signal_fence()?;
+        Ok(Submission { fence_value })
+    }
+
+    /// Whether the GPU has finished executing `submission`, without blocking.
+    pub fn is_submission_complete(&self, submission: Submission) -> bool {
+        self.completed_fence_value() >= submission.fence_value
+    }
+
+    /// Block until the GPU has finished executing `submission`, but not necessarily anything submitted after it.
+    ///
+    /// If [`crate::GpuConfig::fence_timeout`] elapses first, the GPU is assumed to have hung and an error is returned,
+    /// which is the device removed reason if the device was lost.
+    pub fn wait_for_submission(&self, submission: Submission) -> Result<(), DxError> {
+        wait_for_fence_value(&self.fence, submission.fence_value, self.fence_timeout())
+    }
+}
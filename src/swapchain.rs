@@ -1,72 +1,1214 @@
-use crate::gpu::Gpu;
+use crate::{
+    blit::BlitPipeline,
+    error::DxError,
+    frame_pacing::{FramePacing, SmoothFramePacer},
+    gpu::Gpu,
+    offscreen::check_pipeline_formats,
+    resource_tracker::{
+        common_texture_layout_barrier, record_texture_barriers, state_layout,
+        texture_layout_barrier, transition_barrier,
+    },
+};
 use bevy::{
-    math::UVec2,
-    prelude::{Commands, Component, Entity, Query, Res, ResMut, With},
+    math::{IRect, IVec2, Mat4, URect, UVec2},
+    prelude::{
+        error, warn, Commands, Component, DetectChanges, Entity, Local, Query, Ref, Res, ResMut,
+        Resource, With,
+    },
     window::{PrimaryWindow, RawHandleWrapperHolder, Window, WindowMode},
 };
 use raw_window_handle::RawWindowHandle;
 use smallvec::SmallVec;
+use std::{
+    f32::consts::{FRAC_PI_2, PI},
+    mem, ptr, thread,
+    time::Duration,
+};
 use windows::{
-    core::Interface,
+    core::{Error, IUnknown, Interface, PCWSTR},
     Win32::{
-        Foundation::{HANDLE, HWND, RECT},
+        Devices::Display::{
+            DisplayConfigGetDeviceInfo, GetDisplayConfigBufferSizes, QueryDisplayConfig,
+            DISPLAYCONFIG_DEVICE_INFO_GET_SDR_WHITE_LEVEL,
+            DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME, DISPLAYCONFIG_DEVICE_INFO_HEADER,
+            DISPLAYCONFIG_MODE_INFO, DISPLAYCONFIG_PATH_INFO, DISPLAYCONFIG_SDR_WHITE_LEVEL,
+            DISPLAYCONFIG_SOURCE_DEVICE_NAME, QDC_ONLY_ACTIVE_PATHS,
+        },
+        Foundation::{
+            CloseHandle, BOOL, DXGI_STATUS_OCCLUDED, E_FAIL, E_INVALIDARG, HANDLE, HWND, POINT,
+            RECT, WAIT_TIMEOUT, WIN32_ERROR,
+        },
         Graphics::{
             Direct3D12::*,
+            DirectComposition::{
+                DCompositionCreateDevice, IDCompositionDevice, IDCompositionTarget,
+                IDCompositionVisual,
+            },
             Dxgi::{
-                Common::{DXGI_ALPHA_MODE_IGNORE, DXGI_FORMAT_R8G8B8A8_UNORM, DXGI_SAMPLE_DESC},
+                Common::{
+                    DXGI_ALPHA_MODE, DXGI_ALPHA_MODE_IGNORE, DXGI_ALPHA_MODE_PREMULTIPLIED,
+                    DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020,
+                    DXGI_COLOR_SPACE_RGB_FULL_G22_NONE_P709, DXGI_COLOR_SPACE_TYPE, DXGI_FORMAT,
+                    DXGI_FORMAT_R8G8B8A8_UNORM, DXGI_FORMAT_UNKNOWN, DXGI_MODE_ROTATION,
+                    DXGI_MODE_ROTATION_IDENTITY, DXGI_MODE_ROTATION_ROTATE180,
+                    DXGI_MODE_ROTATION_ROTATE270, DXGI_MODE_ROTATION_ROTATE90,
+                    DXGI_MODE_ROTATION_UNSPECIFIED, DXGI_SAMPLE_DESC,
+                },
                 *,
             },
+            Gdi::{
+                EnumDisplaySettingsW, MonitorFromWindow, DEVMODEW, ENUM_CURRENT_SETTINGS, HMONITOR,
+                MONITOR_DEFAULTTONEAREST,
+            },
         },
-        System::Threading::{WaitForSingleObjectEx, INFINITE},
+        System::Threading::WaitForSingleObjectEx,
     },
 };
 
-// TODO: Reflex-like frame pacing, HDR/WCG support, VRR support
+// TODO: Reflex-like latency reduction, HDR support, VRR support
+
+const SWAPCHAIN_FORMAT: DXGI_FORMAT = DXGI_FORMAT_R8G8B8A8_UNORM; // TODO
+/// How long [`wait_for_ready_frame`] sleeps each frame while the window is occluded, before checking again.
+const OCCLUDED_POLL_INTERVAL: Duration = Duration::from_millis(50);
+const NO_SWAPCHAIN: &str =
+    "BevyDirectX: Window has no swapchain, see WindowRenderTarget::has_swapchain()";
+
+/// Scale factor applied to the window size to get the resolution rendering happens at, for dynamic resolution scaling.
+///
+/// When not 1.0, [`WindowRenderTarget::rtv`] returns an intermediate texture of the scaled size, which
+/// [`WindowRenderTarget::upscale_to_backbuffer`] then stretches onto the swapchain's backbuffer. Defaults to 1.0.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct RenderScale(pub f32);
+
+impl Default for RenderScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+/// Render at a constant resolution regardless of window size, e.g. 320x180 for pixel art. Overrides [`RenderScale`]
+/// when inserted as a resource.
+///
+/// [`WindowRenderTarget::rtv`] returns an intermediate texture of this size, which
+/// [`WindowRenderTarget::upscale_to_backbuffer`] then scales onto the swapchain's backbuffer with point sampling.
+/// Resizing the window only changes how the texture is scaled, and never recreates it.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct FixedResolution {
+    pub size: UVec2,
+    pub scaling: FixedResolutionScaling,
+}
+
+impl FixedResolution {
+    /// Fixed resolution with [`FixedResolutionScaling::Integer`] scaling.
+    pub fn new(size: UVec2) -> Self {
+        Self {
+            size,
+            scaling: FixedResolutionScaling::Integer,
+        }
+    }
+}
+
+/// How a [`FixedResolution`] texture is scaled to the window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum FixedResolutionScaling {
+    /// Scale by the largest whole number that fits in the window, centered, with bars of
+    /// [`SwapchainConfig::letterbox_color`] around it. Keeps every texel the same size on screen. If the window is
+    /// smaller than the fixed resolution, scales down to fit while keeping the aspect ratio instead.
+    #[default]
+    Integer,
+    /// Stretch to cover the whole window, ignoring aspect ratio.
+    Stretch,
+}
+
+/// How frames are presented to the screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum PresentMode {
+    /// Wait for vertical blank before presenting. Never tears.
+    #[default]
+    Vsync,
+    /// Present immediately without waiting for vertical blank. May tear, and requires [`Gpu::supports_tearing`] and
+    /// [`SwapchainConfig::allow_tearing`]. Set per window with [`SwapchainConfig::present_mode`].
+    Immediate,
+}
+
+/// Optional per-window swapchain settings. Add this component to a window entity to override the defaults.
+#[derive(Component, Clone, Debug)]
+pub struct SwapchainConfig {
+    /// Color space the swapchain contents are interpreted in when composited to the display.
+    ///
+    /// The color space describes both the color gamut (which colors can be represented, via the primaries)
+    /// and the dynamic range (how bright they can be, via the transfer function). Wide color gamut (WCG)
+    /// only changes the primaries, e.g. `DXGI_COLOR_SPACE_RGB_FULL_G22_NONE_P2020` keeps the SDR gamma 2.2
+    /// transfer function but uses Rec.2020 primaries, and works on SDR displays. HDR additionally changes
+    /// the transfer function (e.g. `DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020`) and needs luminance metadata.
+    ///
+    /// If the color space is unsupported, the swapchain falls back to sRGB (`DXGI_COLOR_SPACE_RGB_FULL_G22_NONE_P709`).
+    pub color_space: DXGI_COLOR_SPACE_TYPE,
+    /// How the swapchain's buffers are handed to the compositor. Only read when the swapchain is created.
+    ///
+    /// Must be a flip model effect: `DXGI_SWAP_EFFECT_FLIP_DISCARD` (the default) or `DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL`.
+    /// Flip sequential preserves the contents of previously presented buffers, which partial-update UIs need.
+    pub swap_effect: DXGI_SWAP_EFFECT,
+    /// How the compositor treats the alpha channel of presented buffers. Only read when the swapchain is created.
+    ///
+    /// Swapchains created for a window (HWND) only support `DXGI_ALPHA_MODE_IGNORE` (the default), under either flip
+    /// model swap effect. `DXGI_ALPHA_MODE_PREMULTIPLIED` requires a composition swapchain, which is used when
+    /// [`SwapchainConfig::transparent_window`] is set, and `DXGI_ALPHA_MODE_STRAIGHT` is not supported by the flip
+    /// model at all.
+    pub alpha_mode: DXGI_ALPHA_MODE,
+    /// Make the window per-pixel transparent, showing the desktop through it wherever the backbuffer's alpha is
+    /// less than 1. Colors written to the backbuffer must be premultiplied by alpha. Only read when the swapchain is
+    /// created, and only supported for Win32 windows ([`SwapchainSurface::Hwnd`]). Defaults to false.
+    ///
+    /// Instead of presenting directly to the window, this creates a composition swapchain with
+    /// `DXGI_ALPHA_MODE_PREMULTIPLIED` (overriding [`SwapchainConfig::alpha_mode`]), and binds it to the window via a
+    /// DirectComposition target and visual.
+    ///
+    /// The Bevy [`Window`] must be created with `transparent: true`, so that the window is styled to not draw an
+    /// opaque background of its own under the composition visual. Typically `decorations: false` is also wanted,
+    /// as the title bar and borders remain opaque.
+    pub transparent_window: bool,
+    /// Fixed width / height aspect ratio to render at. When set, [`WindowRenderTarget::viewport`] returns the largest
+    /// centered region with this aspect ratio, leaving letterbox (horizontal) or pillarbox (vertical) bars around it.
+    /// Defaults to `None`, using the whole window.
+    pub aspect_ratio: Option<f32>,
+    /// Color [`WindowRenderTarget::clear_letterbox`] clears the bars to, when [`SwapchainConfig::aspect_ratio`] is set.
+    pub letterbox_color: [f32; 4],
+    /// Number of backbuffers, from 2 to 16. Only read when the swapchain is created. Defaults to 2.
+    pub buffer_count: u32,
+    /// Maximum number of frames queued for display before [`wait_for_ready_frame`] blocks, from 1 to 16. Must be less
+    /// than [`SwapchainConfig::buffer_count`], so that a backbuffer is free to render to once the wait returns. Only
+    /// read when the swapchain is created, see [`WindowRenderTarget::set_max_frame_latency`] for changing it
+    /// afterwards. Defaults to 1, for the lowest latency.
+    pub max_frame_latency: u32,
+    /// Create the swapchain with `DXGI_SWAP_CHAIN_FLAG_ALLOW_TEARING`, needed for [`PresentMode::Immediate`]. Ignored
+    /// with a warning if [`Gpu::supports_tearing`] is false. Only read when the swapchain is created. Defaults to false.
+    pub allow_tearing: bool,
+    /// How this window's frames are presented, independently of other windows. Can be changed at any time, and
+    /// applies from the next present. [`PresentMode::Immediate`] requires [`SwapchainConfig::allow_tearing`], and
+    /// falls back to [`PresentMode::Vsync`] if tearing is unsupported. Defaults to [`PresentMode::Vsync`].
+    pub present_mode: PresentMode,
+    /// Render in the display's native orientation on rotated displays, e.g. a tablet turned on its side, instead of
+    /// letting the compositor rotate each frame, saving a full-screen copy.
+    ///
+    /// Content drawn to the backbuffer must then be rotated with [`WindowRenderTarget::rotation_transform`], which
+    /// nothing built into the crate does, including [`WindowRenderTarget::upscale_to_backbuffer`], so only enable
+    /// this if every pass drawing to the window applies it. Defaults to false.
+    pub pre_rotate: bool,
+}
 
-const SWAPCHAIN_BUFFER_COUNT: usize = 2;
+impl Default for SwapchainConfig {
+    fn default() -> Self {
+        Self {
+            color_space: DXGI_COLOR_SPACE_RGB_FULL_G22_NONE_P709,
+            swap_effect: DXGI_SWAP_EFFECT_FLIP_DISCARD,
+            alpha_mode: DXGI_ALPHA_MODE_IGNORE,
+            transparent_window: false,
+            aspect_ratio: None,
+            letterbox_color: [0.0, 0.0, 0.0, 1.0],
+            buffer_count: 2,
+            max_frame_latency: 1,
+            allow_tearing: false,
+            present_mode: PresentMode::Vsync,
+            pre_rotate: false,
+        }
+    }
+}
+
+impl SwapchainConfig {
+    /// Check for illegal combinations of settings up front, returning a description of the first problem found.
+    ///
+    /// DXGI otherwise only reports these as `E_INVALIDARG` when creating the swapchain, or as a swapchain that
+    /// blocks unexpectedly.
+    pub fn validate(&self) -> Result<(), String> {
+        if !matches!(
+            self.swap_effect,
+            DXGI_SWAP_EFFECT_FLIP_DISCARD | DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL
+        ) {
+            return Err(format!(
+                "swap_effect must be FLIP_DISCARD or FLIP_SEQUENTIAL, as only flip model swapchains are supported, was {:?}",
+                self.swap_effect
+            ));
+        }
+
+        if self.alpha_mode == DXGI_ALPHA_MODE_PREMULTIPLIED && !self.transparent_window {
+            return Err(
+                "alpha_mode PREMULTIPLIED requires a composition swapchain, enabled by transparent_window"
+                    .to_owned(),
+            );
+        }
+        if !matches!(
+            self.alpha_mode,
+            DXGI_ALPHA_MODE_IGNORE | DXGI_ALPHA_MODE_PREMULTIPLIED
+        ) {
+            return Err(format!(
+                "alpha_mode must be IGNORE, or PREMULTIPLIED with transparent_window, was {:?}",
+                self.alpha_mode
+            ));
+        }
+
+        if !(2..=DXGI_MAX_SWAP_CHAIN_BUFFERS).contains(&self.buffer_count) {
+            return Err(format!(
+                "buffer_count must be between 2 and {DXGI_MAX_SWAP_CHAIN_BUFFERS} for flip model swapchains, was {}",
+                self.buffer_count
+            ));
+        }
+        if !(1..=DXGI_MAX_SWAP_CHAIN_BUFFERS).contains(&self.max_frame_latency) {
+            return Err(format!(
+                "max_frame_latency must be between 1 and {DXGI_MAX_SWAP_CHAIN_BUFFERS}, was {}",
+                self.max_frame_latency
+            ));
+        }
+        if self.max_frame_latency >= self.buffer_count {
+            return Err(format!(
+                "max_frame_latency ({}) must be less than buffer_count ({}), or rendering will block waiting for a free backbuffer",
+                self.max_frame_latency, self.buffer_count
+            ));
+        }
+
+        if self.present_mode == PresentMode::Immediate && !self.allow_tearing {
+            return Err("present_mode Immediate requires allow_tearing".to_owned());
+        }
+
+        if let Some(aspect_ratio) = self.aspect_ratio {
+            if !(aspect_ratio.is_finite() && aspect_ratio > 0.0) {
+                return Err(format!(
+                    "aspect_ratio must be positive and finite, was {aspect_ratio}"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
 
 /// Stores a swapchain and other objects necessary for rendering to a [`Window`].
 #[derive(Component)]
 pub struct WindowRenderTarget {
     size: UVec2,
-    swapchain: IDXGISwapChain4,
+    render_size: UVec2,
+    /// Only `None` while being recreated, see [`WindowRenderTarget::recreate`].
+    swapchain: Option<IDXGISwapChain4>,
+    /// Adapter the swapchain's device was created on, for enumerating outputs.
+    adapter: IDXGIAdapter4,
+    /// `None` for [`SwapchainSurface::CoreWindow`].
+    hwnd: Option<HWND>,
     wait_object: HANDLE,
+    /// Timeout in milliseconds for [`WindowRenderTarget::wait_for_ready`], from [`Gpu::fence_timeout`].
+    wait_timeout: u32,
+    rtv_heap: ID3D12DescriptorHeap,
+    textures: Option<SmallVec<[ID3D12Resource; 3]>>,
+    rtvs: Option<SmallVec<[D3D12_CPU_DESCRIPTOR_HANDLE; 3]>>,
+    /// State of each backbuffer as of the end of the commands recorded so far, see
+    /// [`WindowRenderTarget::transition_rtv`].
+    backbuffer_states: SmallVec<[D3D12_RESOURCE_STATES; 3]>,
+    /// From [`Gpu::uses_enhanced_barriers`].
+    enhanced_barriers: bool,
+    /// From [`Gpu::debug_layer_enabled`].
+    validate: bool,
+    supports_tearing: bool,
+    present_mode: PresentMode,
+    color_space: DXGI_COLOR_SPACE_TYPE,
+    aspect_ratio: Option<f32>,
+    letterbox_color: [f32; 4],
+    swap_effect: DXGI_SWAP_EFFECT,
+    scaled_texture: Option<ScaledTexture>,
+    blit_pipeline: Option<BlitPipeline>,
+    blit_filter: D3D12_FILTER,
+    fixed_resolution_scaling: Option<FixedResolutionScaling>,
+    composition: Option<WindowComposition>,
+    rotation: DXGI_MODE_ROTATION,
+    /// Set once `SetRotation` fails, so that it isn't retried every frame.
+    rotation_unsupported: bool,
+    /// Window size and monitor the output's rotation was last queried for. Rotating a display resizes the windows on
+    /// it, so the rotation is only queried again when either changes.
+    rotation_queried_for: Option<(UVec2, HMONITOR)>,
+}
+
+/// Intermediate texture rendered to at a scaled resolution, before being upscaled to the backbuffer.
+struct ScaledTexture {
+    texture: ID3D12Resource,
+    state: D3D12_RESOURCE_STATES,
     rtv_heap: ID3D12DescriptorHeap,
-    textures: Option<[ID3D12Resource; SWAPCHAIN_BUFFER_COUNT]>,
-    rtvs: Option<[D3D12_CPU_DESCRIPTOR_HANDLE; SWAPCHAIN_BUFFER_COUNT]>,
+    srv_heap: ID3D12DescriptorHeap,
+}
+
+/// DirectComposition objects presenting a composition swapchain to a window, see [`SwapchainConfig::transparent_window`].
+///
+/// Never read, but must be kept alive for as long as the swapchain is shown.
+struct WindowComposition {
+    _device: IDCompositionDevice,
+    _target: IDCompositionTarget,
+    _visual: IDCompositionVisual,
 }
 
+// Safety: DirectComposition objects are thread-safe, and these are never accessed after creation
+unsafe impl Send for WindowComposition {}
+unsafe impl Sync for WindowComposition {}
+
 impl WindowRenderTarget {
+    /// Format of the texture returned by [`WindowRenderTarget::rtv`], for creating pipelines before a window exists.
+    pub const FORMAT: DXGI_FORMAT = SWAPCHAIN_FORMAT;
+
+    /// The texture to render to this frame, and its RTV.
+    ///
+    /// This is the swapchain's current backbuffer, unless [`RenderScale`] is not 1.0 or [`FixedResolution`] is set, in
+    /// which case it's an intermediate texture of size [`WindowRenderTarget::render_size`]. Either way, the texture is in the PRESENT (COMMON) state
+    /// at the start and end of the frame, see [`WindowRenderTarget::transition_rtv`].
+    ///
+    /// Panics if [`WindowRenderTarget::has_swapchain`] is false.
     pub fn rtv(&self) -> (&ID3D12Resource, D3D12_CPU_DESCRIPTOR_HANDLE) {
-        let i = unsafe { self.swapchain.GetCurrentBackBufferIndex() } as usize;
-        (&self.textures.as_ref().unwrap()[i], self.rtvs.unwrap()[i])
+        match &self.scaled_texture {
+            Some(scaled_texture) => (&scaled_texture.texture, unsafe {
+                scaled_texture.rtv_heap.GetCPUDescriptorHandleForHeapStart()
+            }),
+            None => self.backbuffer_rtv().expect(NO_SWAPCHAIN),
+        }
+    }
+
+    /// Tracked state of the texture returned by [`WindowRenderTarget::rtv`].
+    ///
+    /// Backbuffers start in the PRESENT (COMMON) state when the swapchain is created or resized, including ones that
+    /// haven't been rendered to or presented yet, as does the intermediate texture when it's created. States are
+    /// only tracked through [`WindowRenderTarget::transition_rtv`], so transitions recorded manually must end in
+    /// the tracked state.
+    pub fn rtv_state(&self) -> D3D12_RESOURCE_STATES {
+        match &self.scaled_texture {
+            Some(scaled_texture) => scaled_texture.state,
+            None => self.backbuffer_states[self.backbuffer_index().expect(NO_SWAPCHAIN) as usize],
+        }
+    }
+
+    /// Transition the texture returned by [`WindowRenderTarget::rtv`] from its tracked state to `state`, recording
+    /// nothing if it's already in it. See [`WindowRenderTarget::rtv_state`].
+    ///
+    /// The texture must be back in the PRESENT state before [`WindowRenderTarget::upscale_to_backbuffer`] and
+    /// presenting.
+    ///
+    /// With [`crate::GpuConfig::use_enhanced_barriers`], records an enhanced barrier between the equivalent layouts
+    /// instead, e.g. `D3D12_BARRIER_LAYOUT_PRESENT` to `D3D12_BARRIER_LAYOUT_RENDER_TARGET`. Backbuffers have stricter
+    /// layout rules than other textures:
+    /// - They're created by DXGI in the legacy COMMON state, and must be in the PRESENT layout when presented. PRESENT
+    ///   is the same layout as COMMON, but not the queue-specific layouts such as
+    ///   `D3D12_BARRIER_LAYOUT_DIRECT_QUEUE_COMMON`, which can't be presented from.
+    /// - Legacy and enhanced barriers can both be used on a backbuffer, but only in separate command lists, and only if
+    ///   each list leaves it in the PRESENT layout, as COMMON is the only layout with a legacy equivalent at
+    ///   `ExecuteCommandLists` boundaries.
+    /// - Transitions out of PRESENT are recorded with `D3D12_BARRIER_SYNC_NONE`, as the backbuffer isn't accessed in
+    ///   it, so it must not be copied from or to without first transitioning to a COPY layout. The intermediate
+    ///   texture is read in PRESENT by [`WindowRenderTarget::upscale_to_backbuffer`], so its transitions to and from
+    ///   PRESENT synchronize with all accesses instead.
+    pub fn transition_rtv(
+        &mut self,
+        command_list: &ID3D12GraphicsCommandList7,
+        state: D3D12_RESOURCE_STATES,
+    ) {
+        let i = self.backbuffer_index().expect(NO_SWAPCHAIN) as usize;
+        let (texture, tracked_state, is_backbuffer) = match &mut self.scaled_texture {
+            Some(scaled_texture) => (&scaled_texture.texture, &mut scaled_texture.state, false),
+            None => (
+                &self.textures.as_ref().unwrap()[i],
+                &mut self.backbuffer_states[i],
+                true,
+            ),
+        };
+
+        if *tracked_state != state {
+            record_transitions(
+                self.enhanced_barriers,
+                command_list,
+                &[(texture, *tracked_state, state, is_backbuffer)],
+            );
+            *tracked_state = state;
+        }
+    }
+
+    /// The swapchain's current backbuffer, and its RTV. `None` while the window has no swapchain.
+    pub fn backbuffer_rtv(&self) -> Option<(&ID3D12Resource, D3D12_CPU_DESCRIPTOR_HANDLE)> {
+        let i = self.backbuffer_index()? as usize;
+        Some((&self.textures.as_ref()?[i], self.rtvs.as_ref()?[i]))
+    }
+
+    /// The swapchain, or `DXGI_ERROR_INVALID_CALL` if [`WindowRenderTarget::recreate`] failed.
+    fn swapchain(&self) -> Result<&IDXGISwapChain4, DxError> {
+        self.swapchain
+            .as_ref()
+            .ok_or_else(|| Error::new(DXGI_ERROR_INVALID_CALL, NO_SWAPCHAIN).into())
+    }
+
+    /// Whether the window has a swapchain, which is only false after [`WindowRenderTarget::recreate`] failed. Render
+    /// systems should skip the window until it's true again.
+    pub fn has_swapchain(&self) -> bool {
+        self.swapchain.is_some()
+    }
+
+    /// Index of the swapchain's current backbuffer, which changes after each present. `None` while the window has no
+    /// swapchain.
+    pub fn backbuffer_index(&self) -> Option<u32> {
+        let swapchain = self.swapchain().ok()?;
+        Some(unsafe { swapchain.GetCurrentBackBufferIndex() })
+    }
+
+    /// Size of the swapchain's backbuffers. This is the window size, with the width and height swapped if
+    /// [`WindowRenderTarget::rotation`] is 90 or 270 degrees.
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// Size of the texture returned by [`WindowRenderTarget::rtv`], after applying [`RenderScale`] or
+    /// [`FixedResolution`].
+    pub fn render_size(&self) -> UVec2 {
+        self.render_size
     }
 
+    /// Format of the RTV returned by [`WindowRenderTarget::rtv`]. Use this for `RTVFormats[0]` when creating
+    /// pipelines that render to it, or see [`WindowRenderTarget::pipeline_rtv_formats`].
+    pub fn format(&self) -> DXGI_FORMAT {
+        Self::FORMAT
+    }
+
+    /// `RTVFormats` for a pipeline rendering only to [`WindowRenderTarget::rtv`], with `NumRenderTargets` set to 1.
+    ///
+    /// Pipelines must match the format of the RTV rather than of the texture, which differ when the RTV is a view in
+    /// another format of the same texture, such as an sRGB RTV of a UNORM backbuffer, so use this rather than
+    /// `GetDesc().Format` on the texture.
+    pub fn pipeline_rtv_formats(&self) -> [DXGI_FORMAT; 8] {
+        let mut formats = [DXGI_FORMAT_UNKNOWN; 8];
+        formats[0] = self.format();
+        formats
+    }
+
+    /// Log an error if a pipeline rendering to [`WindowRenderTarget::rtv`] doesn't render to exactly its format, or
+    /// has a `DSVFormat` other than `dsv_format`, which otherwise only shows up as a debug layer error (or a black
+    /// screen) at draw time. `dsv_format` is the format of the depth stencil view bound along with the window, e.g.
+    /// [`crate::DepthTarget::DSV_FORMAT`], or `DXGI_FORMAT_UNKNOWN` for none.
+    ///
+    /// Only checked with [`crate::GpuConfig::debug_layer`], does nothing otherwise.
+    pub fn check_pipeline_formats(
+        &self,
+        desc: &D3D12_GRAPHICS_PIPELINE_STATE_DESC,
+        dsv_format: DXGI_FORMAT,
+    ) {
+        if self.validate {
+            check_pipeline_formats(desc, "window render target", &[self.format()], dsv_format);
+        }
+    }
+
+    /// Viewport covering the texture returned by [`WindowRenderTarget::rtv`], or the centered aspect-correct region
+    /// of it if [`SwapchainConfig::aspect_ratio`] is set.
     pub fn viewport(&self) -> D3D12_VIEWPORT {
+        let region = self.aspect_region();
         D3D12_VIEWPORT {
-            TopLeftX: 0.0,
-            TopLeftY: 0.0,
-            Width: self.size.x as f32,
-            Height: self.size.y as f32,
+            TopLeftX: region.min.x as f32,
+            TopLeftY: region.min.y as f32,
+            Width: region.width() as f32,
+            Height: region.height() as f32,
             MinDepth: D3D12_MIN_DEPTH,
             MaxDepth: D3D12_MAX_DEPTH,
         }
     }
 
+    /// [`WindowRenderTarget::viewport`], mapping depth to `min_depth..=max_depth` instead of 0 to 1, e.g. to draw a
+    /// layer such as a first-person weapon into its own slice of the depth range, or to split the scene's depth range.
+    ///
+    /// Both must be within 0 to 1, but `min_depth` may be greater than `max_depth`. For reversed-Z, where the near
+    /// plane maps to 1 and the far plane to 0 for better floating point precision in the distance, either use a
+    /// reversed projection matrix with the default 0 to 1 range, or a standard one with `viewport_with_depth(1.0,
+    /// 0.0)`. Either way, clear depth to 0 rather than 1, and use a `GREATER` or `GREATER_EQUAL` depth test.
+    pub fn viewport_with_depth(&self, min_depth: f32, max_depth: f32) -> D3D12_VIEWPORT {
+        D3D12_VIEWPORT {
+            MinDepth: min_depth,
+            MaxDepth: max_depth,
+            ..self.viewport()
+        }
+    }
+
+    /// Scissor rect matching [`WindowRenderTarget::viewport`].
     pub fn scissor_rect(&self) -> RECT {
+        let region = self.aspect_region();
+        RECT {
+            left: region.min.x as i32,
+            top: region.min.y as i32,
+            right: region.max.x as i32,
+            bottom: region.max.y as i32,
+        }
+    }
+
+    /// Clear the letterbox or pillarbox bars outside of [`WindowRenderTarget::viewport`] to
+    /// [`SwapchainConfig::letterbox_color`]. Does nothing if [`SwapchainConfig::aspect_ratio`] is not set.
+    ///
+    /// The texture returned by [`WindowRenderTarget::rtv`] must be in the render target state.
+    pub fn clear_letterbox(&self, command_list: &ID3D12GraphicsCommandList7) {
+        let region = self.aspect_region();
+        let size = self.render_size.as_ivec2();
+        let (min, max) = (region.min.as_ivec2(), region.max.as_ivec2());
+        let bars = [
+            // Top and bottom (letterbox)
+            (0, 0, size.x, min.y),
+            (0, max.y, size.x, size.y),
+            // Left and right (pillarbox)
+            (0, min.y, min.x, max.y),
+            (max.x, min.y, size.x, max.y),
+        ]
+        .into_iter()
+        .filter(|(left, top, right, bottom)| left < right && top < bottom)
+        .map(|(left, top, right, bottom)| RECT {
+            left,
+            top,
+            right,
+            bottom,
+        })
+        .collect::<SmallVec<[RECT; 4]>>();
+
+        if !bars.is_empty() {
+            let (_, rtv) = self.rtv();
+            unsafe { command_list.ClearRenderTargetView(rtv, &self.letterbox_color, Some(&bars)) };
+        }
+    }
+
+    fn aspect_region(&self) -> URect {
+        let size = self.render_size;
+        let Some(aspect_ratio) = self.aspect_ratio.filter(|a| *a > 0.0) else {
+            return URect::from_corners(UVec2::ZERO, size);
+        };
+
+        let region_size = if size.x as f32 > size.y as f32 * aspect_ratio {
+            // Window is wider than the target aspect ratio, add bars on the sides
+            UVec2::new((size.y as f32 * aspect_ratio).round() as u32, size.y)
+        } else {
+            // Window is taller than the target aspect ratio, add bars on the top and bottom
+            UVec2::new(size.x, (size.x as f32 / aspect_ratio).round() as u32)
+        }
+        .clamp(UVec2::ONE, size.max(UVec2::ONE));
+        let min = (size - region_size.min(size)) / 2;
+
+        URect::from_corners(min, min + region_size)
+    }
+
+    /// Viewport covering a sub-region of the window, e.g. for split-screen rendering.
+    ///
+    /// `region` is in physical pixels of [`WindowRenderTarget::render_size`], with the origin at the top-left and
+    /// Y pointing down. The region is clamped to the render size.
+    pub fn sub_viewport(&self, region: URect) -> D3D12_VIEWPORT {
+        let region = self.clamp_region(region);
+        D3D12_VIEWPORT {
+            TopLeftX: region.min.x as f32,
+            TopLeftY: region.min.y as f32,
+            Width: region.width() as f32,
+            Height: region.height() as f32,
+            MinDepth: D3D12_MIN_DEPTH,
+            MaxDepth: D3D12_MAX_DEPTH,
+        }
+    }
+
+    /// Scissor rect matching [`WindowRenderTarget::sub_viewport`] for the same region.
+    pub fn sub_scissor_rect(&self, region: URect) -> RECT {
+        let region = self.clamp_region(region);
         RECT {
-            left: 0,
-            top: 0,
-            right: self.size.x as i32,
-            bottom: self.size.y as i32,
+            left: region.min.x as i32,
+            top: region.min.y as i32,
+            right: region.max.x as i32,
+            bottom: region.max.y as i32,
+        }
+    }
+
+    /// Scissor rect clipping drawing to `region`, e.g. a UI panel, clamped to [`WindowRenderTarget::render_size`].
+    ///
+    /// Unlike [`WindowRenderTarget::sub_scissor_rect`], `region` may extend past any edge of the window, e.g. for a
+    /// panel partially scrolled or dragged off-screen, and regions entirely outside it clip everything.
+    pub fn scissor_from(&self, region: IRect) -> RECT {
+        let max = region.max.clamp(IVec2::ZERO, self.render_size.as_ivec2());
+        let min = region.min.clamp(IVec2::ZERO, max);
+        RECT {
+            left: min.x,
+            top: min.y,
+            right: max.x,
+            bottom: max.y,
+        }
+    }
+
+    fn clamp_region(&self, region: URect) -> URect {
+        let max = region.max.min(self.render_size);
+        URect::from_corners(region.min.min(max), max)
+    }
+
+    /// Whether the given [`PresentMode`] is supported by the hardware, driver, and swapchain.
+    pub fn supports_present_mode(&self, mode: PresentMode) -> bool {
+        match mode {
+            PresentMode::Vsync => true,
+            PresentMode::Immediate => self.supports_tearing,
+        }
+    }
+
+    /// How [`WindowRenderTarget::present`] presents this window, from [`SwapchainConfig::present_mode`].
+    pub fn present_mode(&self) -> PresentMode {
+        self.present_mode
+    }
+
+    /// Override [`SwapchainConfig::present_mode`] for this window until the config next changes, e.g. for a custom
+    /// frame loop without a config. Returns false and leaves the mode unchanged if it isn't supported, see
+    /// [`WindowRenderTarget::supports_present_mode`].
+    pub fn set_present_mode(&mut self, mode: PresentMode) -> bool {
+        if !self.supports_present_mode(mode) {
+            return false;
+        }
+        self.present_mode = mode;
+        true
+    }
+
+    /// Sync interval and flags to present with for [`WindowRenderTarget::present_mode`].
+    fn present_parameters(&self) -> (u32, u32) {
+        match self.present_mode {
+            PresentMode::Immediate if self.supports_tearing => (0, DXGI_PRESENT_ALLOW_TEARING),
+            _ => (1, 0),
+        }
+    }
+
+    /// The color space currently applied to the swapchain.
+    pub fn color_space(&self) -> DXGI_COLOR_SPACE_TYPE {
+        self.color_space
+    }
+
+    /// Set the color DWM fills the window with outside of the swapchain's contents, e.g. when the window is resized
+    /// faster than the swapchain, or the swapchain is scaled to fit with letterboxing. Lost when the swapchain is
+    /// recreated.
+    pub fn set_background_color(&self, color: [f32; 4]) -> Result<(), DxError> {
+        let [r, g, b, a] = color;
+        unsafe {
+            self.swapchain()?
+                .SetBackgroundColor(&DXGI_RGBA { r, g, b, a })
+        }?;
+        Ok(())
+    }
+
+    /// Hint to DWM that each frame is shown for `duration`, e.g. the frame interval of a video being played, so that
+    /// it can switch the display to a matching refresh rate. `Duration::ZERO` clears the hint. Lost when the swapchain
+    /// is recreated.
+    ///
+    /// The closest duration the display supports is used, and returned. Returns `None` without doing anything if
+    /// the swapchain, driver, or display don't support custom present durations.
+    pub fn set_present_duration(&self, duration: Duration) -> Result<Option<Duration>, DxError> {
+        let Ok(swapchain_media) = self.swapchain()?.cast::<IDXGISwapChainMedia>() else {
+            return Ok(None);
+        };
+
+        // Durations are in units of 100 nanoseconds
+        let desired = (duration.as_nanos() / 100) as u32;
+        let applied = if desired == 0 {
+            0
+        } else {
+            let (mut smaller, mut larger) = (0, 0);
+            if unsafe {
+                swapchain_media.CheckPresentDurationSupport(desired, &mut smaller, &mut larger)
+            }
+            .is_err()
+            {
+                return Ok(None);
+            }
+            match (smaller, larger) {
+                (0, 0) => return Ok(None),
+                (0, closest) | (closest, 0) => closest,
+                _ if desired.abs_diff(smaller) <= larger.abs_diff(desired) => smaller,
+                _ => larger,
+            }
+        };
+
+        unsafe { swapchain_media.SetPresentDuration(applied) }?;
+        Ok(Some(Duration::from_nanos(applied as u64 * 100)))
+    }
+
+    /// The display output (monitor) the window is on. If the window spans multiple outputs, this is the one
+    /// containing the largest part of it.
+    ///
+    /// Used as the target of [`WindowRenderTarget::set_exclusive_fullscreen`], and for display queries like
+    /// [`WindowRenderTarget::output_supports_hdr`]. Found by matching the window's monitor against the GPU's outputs,
+    /// as `GetContainingOutput` can return a stale output after the window moves, and fails for composition
+    /// swapchains. Falls back to `GetContainingOutput` if the monitor is connected to a different GPU.
+    pub fn output(&self) -> Result<IDXGIOutput6, DxError> {
+        if let Some(hwnd) = self.hwnd {
+            // Picks the monitor with the largest intersection with the window, or the nearest if there's none
+            let monitor = unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) };
+            let mut i = 0;
+            while let Ok(output) = unsafe { self.adapter.EnumOutputs(i) } {
+                let mut output_desc = DXGI_OUTPUT_DESC::default();
+                unsafe { output.GetDesc(&mut output_desc) }?;
+                if output_desc.Monitor == monitor {
+                    return Ok(output.cast::<IDXGIOutput6>()?);
+                }
+                i += 1;
+            }
+        }
+
+        Ok(unsafe { self.swapchain()?.GetContainingOutput() }?.cast::<IDXGIOutput6>()?)
+    }
+
+    /// Whether [`WindowRenderTarget::output`] has HDR enabled in Windows display settings, i.e. its color space is
+    /// `DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020`. Re-check when the window moves between monitors.
+    pub fn output_supports_hdr(&self) -> Result<bool, DxError> {
+        let mut output_desc = DXGI_OUTPUT_DESC1::default();
+        unsafe { self.output()?.GetDesc1(&mut output_desc) }?;
+        Ok(output_desc.ColorSpace == DXGI_COLOR_SPACE_RGB_FULL_G2084_NONE_P2020)
+    }
+
+    /// Brightness in nits that Windows displays SDR white at on [`WindowRenderTarget::output`], as set by the "SDR
+    /// content brightness" slider in display settings.
+    ///
+    /// When presenting in an HDR color space, scale SDR content such as UI by this over 80 nits (the scRGB reference
+    /// white) to match the brightness of other SDR apps. Defaults to 80 nits on displays without HDR enabled.
+    pub fn sdr_white_level(&self) -> Result<f32, DxError> {
+        let mut output_desc = DXGI_OUTPUT_DESC1::default();
+        unsafe { self.output()?.GetDesc1(&mut output_desc) }?;
+
+        // Find the display path driving the output, by its GDI device name
+        let (mut path_count, mut mode_count) = (0, 0);
+        unsafe {
+            GetDisplayConfigBufferSizes(QDC_ONLY_ACTIVE_PATHS, &mut path_count, &mut mode_count)
+                .to_hresult()
+                .ok()?;
+        }
+        let mut paths = vec![DISPLAYCONFIG_PATH_INFO::default(); path_count as usize];
+        let mut modes = vec![DISPLAYCONFIG_MODE_INFO::default(); mode_count as usize];
+        unsafe {
+            QueryDisplayConfig(
+                QDC_ONLY_ACTIVE_PATHS,
+                &mut path_count,
+                paths.as_mut_ptr(),
+                &mut mode_count,
+                modes.as_mut_ptr(),
+                None,
+            )
+            .to_hresult()
+            .ok()?;
+        }
+        paths.truncate(path_count as usize);
+
+        for path in paths {
+            let mut source_name = DISPLAYCONFIG_SOURCE_DEVICE_NAME {
+                header: DISPLAYCONFIG_DEVICE_INFO_HEADER {
+                    r#type: DISPLAYCONFIG_DEVICE_INFO_GET_SOURCE_NAME,
+                    size: mem::size_of::<DISPLAYCONFIG_SOURCE_DEVICE_NAME>() as u32,
+                    adapterId: path.sourceInfo.adapterId,
+                    id: path.sourceInfo.id,
+                },
+                ..Default::default()
+            };
+            if unsafe { DisplayConfigGetDeviceInfo(&mut source_name.header) } != 0
+                || source_name.viewGdiDeviceName != output_desc.DeviceName
+            {
+                continue;
+            }
+
+            let mut white_level = DISPLAYCONFIG_SDR_WHITE_LEVEL {
+                header: DISPLAYCONFIG_DEVICE_INFO_HEADER {
+                    r#type: DISPLAYCONFIG_DEVICE_INFO_GET_SDR_WHITE_LEVEL,
+                    size: mem::size_of::<DISPLAYCONFIG_SDR_WHITE_LEVEL>() as u32,
+                    adapterId: path.targetInfo.adapterId,
+                    id: path.targetInfo.id,
+                },
+                ..Default::default()
+            };
+            let result = unsafe { DisplayConfigGetDeviceInfo(&mut white_level.header) };
+            WIN32_ERROR(result as u32).to_hresult().ok()?;
+
+            // Reported in thousandths of 80 nits
+            return Ok(white_level.SDRWhiteLevel as f32 / 1000.0 * 80.0);
+        }
+
+        Err(Error::new(
+            E_FAIL,
+            "BevyDirectX: Failed to find the display path for the window's output",
+        )
+        .into())
+    }
+
+    /// Enter or leave exclusive fullscreen on [`WindowRenderTarget::output`], the monitor the window is currently on.
+    ///
+    /// DXGI resizes the window to cover the output, and the swapchain is resized to match by the next
+    /// [`update_render_target`]. Not supported for [`SwapchainConfig::transparent_window`] or
+    /// [`SwapchainSurface::CoreWindow`] swapchains. Prefer `WindowMode::BorderlessFullscreen`, which flip model
+    /// swapchains present from just as efficiently, unless exclusive control of the display mode is needed.
+    pub fn set_exclusive_fullscreen(&self, fullscreen: bool) -> Result<(), DxError> {
+        if self.composition.is_some() || self.hwnd.is_none() {
+            return Err(Error::new(
+                E_INVALIDARG,
+                "BevyDirectX: Exclusive fullscreen is only supported for non-transparent Win32 windows",
+            )
+            .into());
+        }
+
+        let output = if fullscreen {
+            Some(self.output()?.cast::<IDXGIOutput>()?)
+        } else {
+            None
+        };
+        unsafe {
+            self.swapchain()?
+                .SetFullscreenState(fullscreen, output.as_ref())
+        }?;
+        Ok(())
+    }
+
+    /// Current rotation of [`WindowRenderTarget::output`], e.g. from turning a tablet on its side.
+    pub fn output_rotation(&self) -> Result<DXGI_MODE_ROTATION, DxError> {
+        let mut output_desc = DXGI_OUTPUT_DESC1::default();
+        unsafe { self.output()?.GetDesc1(&mut output_desc) }?;
+        Ok(output_desc.Rotation)
+    }
+
+    /// Rotation the swapchain's contents are presented with. Always `DXGI_MODE_ROTATION_IDENTITY` unless
+    /// [`SwapchainConfig::pre_rotate`] is set, in which case it's matched to [`WindowRenderTarget::output_rotation`] on
+    /// swapchain creation, on resize, and when the window moves to another monitor.
+    ///
+    /// When not `DXGI_MODE_ROTATION_IDENTITY`, the backbuffers are in the display's native orientation rather than the
+    /// window's, and content must be rendered pre-rotated, see [`WindowRenderTarget::rotation_transform`]. Swapchains
+    /// that can't be pre-rotated stay at identity, and are rotated by the compositor instead.
+    pub fn rotation(&self) -> DXGI_MODE_ROTATION {
+        self.rotation
+    }
+
+    /// Clip-space rotation to apply after the projection matrix, so that content rendered to a pre-rotated swapchain
+    /// appears upright, see [`WindowRenderTarget::rotation`]. The projection's aspect ratio should use the window's
+    /// size, not [`WindowRenderTarget::size`].
+    pub fn rotation_transform(&self) -> Mat4 {
+        match self.rotation {
+            DXGI_MODE_ROTATION_ROTATE90 => Mat4::from_rotation_z(-FRAC_PI_2),
+            DXGI_MODE_ROTATION_ROTATE180 => Mat4::from_rotation_z(PI),
+            DXGI_MODE_ROTATION_ROTATE270 => Mat4::from_rotation_z(FRAC_PI_2),
+            _ => Mat4::IDENTITY,
+        }
+    }
+
+    /// Display modes (resolution and refresh rate combinations) supported by [`WindowRenderTarget::output`] in the
+    /// swapchain's format, e.g. for listing in a settings menu.
+    pub fn display_modes(&self) -> Result<Vec<DXGI_MODE_DESC1>, DxError> {
+        let output = self.output()?;
+        let mut count = 0;
+        unsafe { output.GetDisplayModeList1(SWAPCHAIN_FORMAT, 0, &mut count, None) }?;
+        let mut modes = vec![DXGI_MODE_DESC1::default(); count as usize];
+        unsafe {
+            output.GetDisplayModeList1(SWAPCHAIN_FORMAT, 0, &mut count, Some(modes.as_mut_ptr()))
+        }?;
+        modes.truncate(count as usize);
+        Ok(modes)
+    }
+
+    /// Current refresh rate in Hz of [`WindowRenderTarget::output`].
+    ///
+    /// Windows reports this as a whole number, rounded down, e.g. 59 for a 59.94 Hz display.
+    pub fn refresh_rate(&self) -> Result<u32, DxError> {
+        let mut output_desc = DXGI_OUTPUT_DESC1::default();
+        unsafe { self.output()?.GetDesc1(&mut output_desc) }?;
+        let mut mode = DEVMODEW {
+            dmSize: mem::size_of::<DEVMODEW>() as u16,
+            ..Default::default()
+        };
+        let found = unsafe {
+            EnumDisplaySettingsW(
+                PCWSTR(output_desc.DeviceName.as_ptr()),
+                ENUM_CURRENT_SETTINGS,
+                &mut mode,
+            )
+        };
+        if !found.as_bool() {
+            return Err(Error::from_win32().into());
+        }
+        Ok(mode.dmDisplayFrequency)
+    }
+
+    /// Presentation statistics for the swapchain, e.g. for detecting missed vsyncs by comparing how `PresentCount`
+    /// and `SyncRefreshCount` change between frames.
+    ///
+    /// Fails with `DXGI_ERROR_FRAME_STATISTICS_DISJOINT` while statistics are unavailable, such as shortly after the
+    /// window is created or changes display mode.
+    pub fn frame_statistics(&self) -> Result<DXGI_FRAME_STATISTICS, DxError> {
+        let mut statistics = DXGI_FRAME_STATISTICS::default();
+        unsafe { self.swapchain()?.GetFrameStatistics(&mut statistics) }?;
+        Ok(statistics)
+    }
+
+    /// Scale the texture returned by [`WindowRenderTarget::rtv`] onto the swapchain's backbuffer, if [`RenderScale`]
+    /// is not 1.0 or [`FixedResolution`] is set. Otherwise does nothing.
+    ///
+    /// Call after all rendering to [`WindowRenderTarget::rtv`] has been recorded, and the texture has been transitioned
+    /// back to the PRESENT state. Changes the command list's pipeline, root signature, and descriptor heaps.
+    pub fn upscale_to_backbuffer(&self, command_list: &ID3D12GraphicsCommandList7) {
+        let (Some(scaled_texture), Some(blit_pipeline)) =
+            (&self.scaled_texture, &self.blit_pipeline)
+        else {
+            return;
+        };
+        let Some((backbuffer, backbuffer_rtv)) = self.backbuffer_rtv() else {
+            return;
+        };
+
+        record_transitions(
+            self.enhanced_barriers,
+            command_list,
+            &[
+                (
+                    &scaled_texture.texture,
+                    D3D12_RESOURCE_STATE_PRESENT,
+                    D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE,
+                    false,
+                ),
+                (
+                    backbuffer,
+                    D3D12_RESOURCE_STATE_PRESENT,
+                    D3D12_RESOURCE_STATE_RENDER_TARGET,
+                    true,
+                ),
+            ],
+        );
+
+        // Clear the bars around an integer-scaled fixed resolution image
+        let region = self.upscale_region();
+        if region.size() != self.size {
+            unsafe {
+                command_list.ClearRenderTargetView(backbuffer_rtv, &self.letterbox_color, None)
+            };
+        }
+
+        blit_pipeline.blit(
+            command_list,
+            &scaled_texture.srv_heap,
+            unsafe { scaled_texture.srv_heap.GetGPUDescriptorHandleForHeapStart() },
+            backbuffer_rtv,
+            D3D12_VIEWPORT {
+                TopLeftX: region.min.x as f32,
+                TopLeftY: region.min.y as f32,
+                Width: region.width() as f32,
+                Height: region.height() as f32,
+                MinDepth: D3D12_MIN_DEPTH,
+                MaxDepth: D3D12_MAX_DEPTH,
+            },
+            RECT {
+                left: region.min.x as i32,
+                top: region.min.y as i32,
+                right: region.max.x as i32,
+                bottom: region.max.y as i32,
+            },
+        );
+
+        record_transitions(
+            self.enhanced_barriers,
+            command_list,
+            &[
+                (
+                    &scaled_texture.texture,
+                    D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE,
+                    D3D12_RESOURCE_STATE_PRESENT,
+                    false,
+                ),
+                (
+                    backbuffer,
+                    D3D12_RESOURCE_STATE_RENDER_TARGET,
+                    D3D12_RESOURCE_STATE_PRESENT,
+                    true,
+                ),
+            ],
+        );
+    }
+
+    /// Tear down the swapchain and rebuild it against `gpu`, keeping the window size, e.g. after the device was removed
+    /// and [`Gpu`] was recreated, as the old swapchain is tied to the old device's queue.
+    ///
+    /// The old device's queue must be idle. The intermediate texture for [`RenderScale`] or [`FixedResolution`] is
+    /// recreated the next time [`update_render_target`] runs.
+    ///
+    /// If this returns an error, the render target has no swapchain until a later call succeeds. In the meantime
+    /// [`WindowRenderTarget::has_swapchain`] is false, [`WindowRenderTarget::is_occluded`] is true, and the
+    /// plugin's frame loop systems skip the window.
+    pub fn recreate(
+        &mut self,
+        gpu: &Gpu,
+        window_handle: &RawHandleWrapperHolder,
+        config: &SwapchainConfig,
+    ) -> Result<(), DxError> {
+        let surface = SwapchainSurface::from_window_handle(window_handle)
+            .map_err(|e| Error::new(E_INVALIDARG, format!("BevyDirectX: {e}")))?;
+        config.validate().map_err(|e| {
+            Error::new(
+                E_INVALIDARG,
+                format!("BevyDirectX: Invalid SwapchainConfig: {e}"),
+            )
+        })?;
+
+        // A window can only have one swapchain at a time, so release the old one first, after leaving exclusive
+        // fullscreen, which a swapchain can't be released in
+        if let Some(swapchain) = self.swapchain.take() {
+            unsafe {
+                let mut fullscreen = BOOL::default();
+                if swapchain
+                    .GetFullscreenState(Some(&mut fullscreen), None)
+                    .is_ok()
+                    && fullscreen.as_bool()
+                {
+                    let _ = swapchain.SetFullscreenState(false, None);
+                }
+            }
+        }
+        self.textures = None;
+        self.rtvs = None;
+        self.scaled_texture = None;
+        self.blit_pipeline = None;
+        self.composition = None;
+        unsafe {
+            let _ = CloseHandle(self.wait_object);
+        }
+        self.wait_object = HANDLE::default();
+
+        let mut render_target = create_new_swapchain(
+            gpu,
+            &surface,
+            swapchain_desc(config, gpu, self.size),
+            config.max_frame_latency,
+        )?;
+        render_target.aspect_ratio = config.aspect_ratio;
+        render_target.letterbox_color = config.letterbox_color;
+        render_target.set_present_mode(config.present_mode);
+        set_color_space(&mut render_target, config.color_space);
+        *self = render_target;
+        Ok(())
+    }
+
+    /// Region of the backbuffer [`WindowRenderTarget::upscale_to_backbuffer`] scales the render texture onto.
+    fn upscale_region(&self) -> URect {
+        upscale_region(self.size, self.render_size, self.fixed_resolution_scaling)
+    }
+
+    /// Maximum number of frames queued for display before [`WindowRenderTarget::wait_for_ready`] blocks, see
+    /// [`SwapchainConfig::max_frame_latency`].
+    pub fn max_frame_latency(&self) -> Result<u32, DxError> {
+        Ok(unsafe { self.swapchain()?.GetMaximumFrameLatency() }?)
+    }
+
+    /// Change [`WindowRenderTarget::max_frame_latency`] at runtime, e.g. from a settings menu, and re-acquire the
+    /// waitable object [`WindowRenderTarget::wait_for_ready`] blocks on.
+    ///
+    /// `max_frame_latency` must be between 1 and 16, and less than the swapchain's buffer count. Changing it may
+    /// cause a one-frame hitch while the queue of frames awaiting display grows or drains to the new length.
+    pub fn set_max_frame_latency(&mut self, max_frame_latency: u32) -> Result<(), DxError> {
+        let mut swapchain_desc = DXGI_SWAP_CHAIN_DESC1::default();
+        let swapchain = self.swapchain()?.clone();
+        unsafe { swapchain.GetDesc1(&mut swapchain_desc) }?;
+        if swapchain_desc.Flags & DXGI_SWAP_CHAIN_FLAG_FRAME_LATENCY_WAITABLE_OBJECT.0 as u32 == 0 {
+            return Err(Error::new(
+                E_INVALIDARG,
+                "BevyDirectX: Swapchain was not created with DXGI_SWAP_CHAIN_FLAG_FRAME_LATENCY_WAITABLE_OBJECT",
+            )
+            .into());
+        }
+        if !(1..=DXGI_MAX_SWAP_CHAIN_BUFFERS).contains(&max_frame_latency)
+            || max_frame_latency >= swapchain_desc.BufferCount
+        {
+            return Err(Error::new(
+                E_INVALIDARG,
+                format!(
+                    "BevyDirectX: max_frame_latency must be between 1 and {DXGI_MAX_SWAP_CHAIN_BUFFERS}, and less than the buffer count ({}), was {max_frame_latency}",
+                    swapchain_desc.BufferCount
+                ),
+            )
+            .into());
+        }
+
+        unsafe {
+            swapchain.SetMaximumFrameLatency(max_frame_latency)?;
+            let _ = CloseHandle(self.wait_object);
+            self.wait_object = swapchain.GetFrameLatencyWaitableObject();
+        }
+        Ok(())
+    }
+
+    /// Block until the swapchain estimates there is 1 frame's worth of time left before it can accept a new frame.
+    ///
+    /// Returns an error if [`crate::GpuConfig::fence_timeout`] elapses first. See [`wait_for_ready_frame`] for
+    /// where this fits into the frame.
+    pub fn wait_for_ready(&self) -> Result<(), DxError> {
+        if !self.has_swapchain() {
+            return Ok(());
         }
+        if unsafe { WaitForSingleObjectEx(self.wait_object, self.wait_timeout, true) }
+            == WAIT_TIMEOUT
+        {
+            error!("BevyDirectX: Swapchain wait timed out — possible hang");
+            return Err(Error::from(DXGI_ERROR_WAIT_TIMEOUT).into());
+        }
+        Ok(())
+    }
+
+    /// Whether the window is currently hidden, e.g. minimized or covered by another app in exclusive fullscreen, in
+    /// which case render systems can skip the frame to save power, as nothing presented would be shown.
+    ///
+    /// Checked with a `DXGI_PRESENT_TEST` present, which doesn't queue a frame or wait on anything, so it's cheap
+    /// enough to call every frame before rendering. Also true while the window has no swapchain.
+    pub fn is_occluded(&self) -> bool {
+        let Ok(swapchain) = self.swapchain() else {
+            return true;
+        };
+        let result = unsafe { swapchain.Present(0, DXGI_PRESENT_TEST) };
+        result == DXGI_STATUS_OCCLUDED
     }
 
-    pub fn present(&self) {
-        unsafe { self.swapchain.Present(1, 0) }.unwrap();
+    /// Queue the current backbuffer for display, with this window's [`WindowRenderTarget::present_mode`]. See also
+    /// [`Gpu::submit_and_present`].
+    ///
+    /// Returns `DXGI_ERROR_INVALID_CALL` while the window has no swapchain.
+    pub fn present(&self) -> Result<(), DxError> {
+        let (sync_interval, flags) = self.present_parameters();
+        unsafe { self.swapchain()?.Present(sync_interval, flags) }.ok()?;
+        Ok(())
+    }
+
+    /// Present, telling the compositor that only `dirty_rects` changed since the last present, and optionally that
+    /// `scroll_rect` was moved by `scroll_offset`. An empty `dirty_rects` marks the whole window as dirty.
+    ///
+    /// Requires [`SwapchainConfig::swap_effect`] to be `DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL`, as flip discard does not
+    /// preserve the contents of previous frames outside of the dirty regions, and returns [`DxError::InvalidCall`]
+    /// otherwise.
+    pub fn present_dirty(
+        &self,
+        dirty_rects: &[RECT],
+        scroll_rect: Option<RECT>,
+        scroll_offset: POINT,
+    ) -> Result<(), DxError> {
+        if self.swap_effect != DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL {
+            return Err(Error::new(
+                DXGI_ERROR_INVALID_CALL,
+                "BevyDirectX: present_dirty() requires SwapchainConfig::swap_effect to be FLIP_SEQUENTIAL",
+            )
+            .into());
+        }
+
+        let mut scroll_rect = scroll_rect;
+        let mut scroll_offset = scroll_offset;
+        let parameters = DXGI_PRESENT_PARAMETERS {
+            DirtyRectsCount: dirty_rects.len() as u32,
+            pDirtyRects: dirty_rects.as_ptr() as *mut _,
+            pScrollRect: scroll_rect
+                .as_mut()
+                .map_or(ptr::null_mut(), |rect| rect as *mut _),
+            pScrollOffset: if scroll_rect.is_some() {
+                &mut scroll_offset
+            } else {
+                ptr::null_mut()
+            },
+        };
+
+        let (sync_interval, flags) = self.present_parameters();
+        unsafe {
+            self.swapchain()?
+                .Present1(sync_interval, flags, &parameters)
+        }
+        .ok()?;
+        Ok(())
     }
 }
 
+/// Set the command list's scissor rects, one per viewport, e.g. from [`WindowRenderTarget::scissor_from`].
+pub fn set_scissor_rects(command_list: &ID3D12GraphicsCommandList7, rects: &[RECT]) {
+    unsafe { command_list.RSSetScissorRects(rects) };
+}
+
 /// Delay starting the main schedule until the swapchain estimates there is 1 frame's worth of time left
 /// before it is able to accept a new frame, reducing overall frame latency. Also waits for the command list
 /// to finish executing from last frame.
@@ -74,34 +1216,67 @@ impl WindowRenderTarget {
 /// It's better to block here, before we read user inputs, update game state, and record rendering commands, rather
 /// than blocking at the end of the frame waiting for the swapchain to become available. This minimizes the latency
 /// between reading user inputs, and submitting the rendered frame to the swapchain.
+///
+/// Added to [`bevy::app::First`] unless [`crate::BevyDirectXPlugin::manage_frame_loop`] is disabled, in which case
+/// custom frame loops should do the same each frame, in this order:
+/// 1. [`WindowRenderTarget::wait_for_ready`]
+/// 2. [`Gpu::wait_for_frame`]
+/// 3. [`SmoothFramePacer::pace`], if using [`FramePacing::Smooth`].
+/// 4. Read input, and update game state.
+/// 5. Record and submit rendering commands, then present.
+/// 6. [`Gpu::signal_fence`]
+///
+/// While the window is occluded or has no swapchain, nothing is presented, so the swapchain is never ready. Sleeps
+/// for 50 ms instead, so that skipped frames don't spin the CPU.
 pub fn wait_for_ready_frame(
     window: Query<&WindowRenderTarget, With<PrimaryWindow>>,
     gpu: Res<Gpu>,
+    frame_pacing: Res<FramePacing>,
+    mut smooth_pacer: Local<SmoothFramePacer>,
 ) {
     if let Ok(render_target) = window.get_single() {
-        unsafe { WaitForSingleObjectEx(render_target.wait_object, INFINITE, true) };
+        if render_target.is_occluded() {
+            thread::sleep(OCCLUDED_POLL_INTERVAL);
+        } else {
+            // Timeouts are logged, then the GPU wait below reports whether the device was lost
+            let _ = render_target.wait_for_ready();
+        }
+
+        if let Err(e) = gpu.wait_for_frame() {
+            panic!("BevyDirectX: Failed waiting for GPU: {e}");
+        }
 
-        gpu.wait_for_fence();
+        if *frame_pacing == FramePacing::Smooth {
+            smooth_pacer.pace();
+        }
     }
 }
 
 /// Create or update the swapchain for a newly created or changed window.
+#[allow(clippy::type_complexity)]
 pub fn update_render_target(
     mut window: Query<
         (
             Entity,
             &Window,
             &RawHandleWrapperHolder,
+            Option<Ref<SwapchainConfig>>,
             Option<&mut WindowRenderTarget>,
         ),
         With<PrimaryWindow>,
     >,
     mut commands: Commands,
     mut gpu: ResMut<Gpu>,
+    render_scale: Res<RenderScale>,
+    fixed_resolution: Option<Res<FixedResolution>>,
+    mut reported_unsupported_surface: Local<bool>,
 ) {
-    let Ok((entity, window, window_handle, render_target)) = window.get_single_mut() else {
+    let Ok((entity, window, window_handle, config, render_target)) = window.get_single_mut() else {
         return;
     };
+    // Only reapply the present mode when the config changes, so that WindowRenderTarget::set_present_mode() sticks
+    let config_changed = config.as_ref().is_some_and(|config| config.is_changed());
+    let config = config.map(|config| config.clone()).unwrap_or_default();
 
     // Check for unsupported window modes
     if !matches!(
@@ -114,95 +1289,412 @@ pub fn update_render_target(
         );
     }
 
-    // Setup swapchain descriptor
-    let swapchain_desc = DXGI_SWAP_CHAIN_DESC1 {
-        Width: window.physical_width(),
-        Height: window.physical_height(),
-        Format: DXGI_FORMAT_R8G8B8A8_UNORM, // TODO
-        SampleDesc: DXGI_SAMPLE_DESC {
-            Count: 1,
-            ..Default::default()
-        },
-        BufferUsage: DXGI_USAGE_RENDER_TARGET_OUTPUT, // TODO
-        BufferCount: SWAPCHAIN_BUFFER_COUNT as u32,
-        SwapEffect: DXGI_SWAP_EFFECT_FLIP_DISCARD,
-        AlphaMode: DXGI_ALPHA_MODE_IGNORE,
-        Flags: DXGI_SWAP_CHAIN_FLAG_FRAME_LATENCY_WAITABLE_OBJECT.0 as u32, // TODO: VRR support
-        ..Default::default()
-    };
+    // Check for illegal swapchain settings
+    if let Err(e) = config.validate() {
+        panic!("BevyDirectX: Invalid SwapchainConfig: {e}");
+    }
+    let swapchain_desc = swapchain_desc(
+        &config,
+        &gpu,
+        UVec2::new(window.physical_width(), window.physical_height()),
+    );
 
     // If there's an existing swapchain, resize if needed, else create a new swapchain
     if let Some(mut render_target) = render_target {
+        // Recreating the swapchain failed, so leave it for the app to retry
+        if !render_target.has_swapchain() {
+            return;
+        }
+        let swapchain_desc =
+            apply_output_rotation(&mut render_target, swapchain_desc, config.pre_rotate);
         resize_swapchain_if_needed(&mut render_target, swapchain_desc, &mut gpu);
         render_target.size = UVec2::new(swapchain_desc.Width, swapchain_desc.Height);
+        render_target.aspect_ratio = config.aspect_ratio;
+        render_target.letterbox_color = config.letterbox_color;
+        if config_changed {
+            render_target.set_present_mode(config.present_mode);
+        }
+        if render_target.color_space != config.color_space {
+            set_color_space(&mut render_target, config.color_space);
+        }
+        update_render_scale(
+            &mut render_target,
+            &gpu,
+            *render_scale,
+            fixed_resolution.as_deref().copied(),
+        );
     } else {
-        let render_target = create_new_swapchain(&gpu, window_handle, swapchain_desc);
+        // Wait for the window to be created
+        if window_handle.0.lock().unwrap().is_none() {
+            return;
+        }
+
+        let surface = match SwapchainSurface::from_window_handle(window_handle) {
+            Ok(surface) => surface,
+            Err(e) => {
+                if !*reported_unsupported_surface {
+                    error!("BevyDirectX: Unable to create swapchain: {e}");
+                    *reported_unsupported_surface = true;
+                }
+                return;
+            }
+        };
+
+        if config.transparent_window && !window.transparent {
+            warn!("BevyDirectX: SwapchainConfig::transparent_window is set, but Window::transparent is not");
+        }
+
+        if config.allow_tearing && !gpu.supports_tearing() {
+            warn!(
+                "BevyDirectX: SwapchainConfig::allow_tearing is set, but tearing is not supported"
+            );
+        }
+
+        let mut render_target =
+            create_new_swapchain(&gpu, &surface, swapchain_desc, config.max_frame_latency)
+                .expect("BevyDirectX: Failed to create swapchain");
+        let swapchain_desc =
+            apply_output_rotation(&mut render_target, swapchain_desc, config.pre_rotate);
+        resize_swapchain_if_needed(&mut render_target, swapchain_desc, &mut gpu);
+        render_target.size = UVec2::new(swapchain_desc.Width, swapchain_desc.Height);
+        render_target.aspect_ratio = config.aspect_ratio;
+        render_target.letterbox_color = config.letterbox_color;
+        render_target.set_present_mode(config.present_mode);
+        set_color_space(&mut render_target, config.color_space);
+        update_render_scale(
+            &mut render_target,
+            &gpu,
+            *render_scale,
+            fixed_resolution.as_deref().copied(),
+        );
         commands.entity(entity).insert(render_target);
     }
 }
 
+/// Swapchain settings for a window of the given size, from its [`SwapchainConfig`].
+fn swapchain_desc(config: &SwapchainConfig, gpu: &Gpu, size: UVec2) -> DXGI_SWAP_CHAIN_DESC1 {
+    let alpha_mode = if config.transparent_window {
+        DXGI_ALPHA_MODE_PREMULTIPLIED
+    } else {
+        config.alpha_mode
+    };
+    let mut flags = DXGI_SWAP_CHAIN_FLAG_FRAME_LATENCY_WAITABLE_OBJECT.0 as u32; // TODO: VRR support
+    if config.allow_tearing && gpu.supports_tearing() {
+        flags |= DXGI_SWAP_CHAIN_FLAG_ALLOW_TEARING.0 as u32;
+    }
+
+    DXGI_SWAP_CHAIN_DESC1 {
+        Width: size.x,
+        Height: size.y,
+        Format: SWAPCHAIN_FORMAT,
+        SampleDesc: DXGI_SAMPLE_DESC {
+            Count: 1,
+            ..Default::default()
+        },
+        BufferUsage: DXGI_USAGE_RENDER_TARGET_OUTPUT, // TODO
+        BufferCount: config.buffer_count,
+        SwapEffect: config.swap_effect,
+        AlphaMode: alpha_mode,
+        Flags: flags,
+        ..Default::default()
+    }
+}
+
 fn create_new_swapchain(
     gpu: &Gpu,
-    window_handle: &RawHandleWrapperHolder,
+    surface: &SwapchainSurface,
     swapchain_desc: DXGI_SWAP_CHAIN_DESC1,
-) -> WindowRenderTarget {
+    max_frame_latency: u32,
+) -> Result<WindowRenderTarget, DxError> {
     // Create new swapchain
-    let factory = gpu.factory.cast::<IDXGIFactory2>().unwrap();
-    let swapchain = unsafe {
-        factory.CreateSwapChainForHwnd(
-            &gpu.queue,
-            get_hwnd(window_handle),
-            &swapchain_desc,
-            None,
-            None,
-        )
-    }
-    .unwrap()
-    .cast::<IDXGISwapChain4>()
-    .unwrap();
+    let (swapchain, composition) = if swapchain_desc.AlphaMode == DXGI_ALPHA_MODE_PREMULTIPLIED {
+        let (swapchain, composition) =
+            surface.create_composition_swapchain(gpu, &swapchain_desc)?;
+        (swapchain, Some(composition))
+    } else {
+        (surface.create_swapchain(gpu, &swapchain_desc)?, None)
+    };
+    let swapchain = swapchain.cast::<IDXGISwapChain4>()?;
+    let adapter = unsafe { gpu.factory.EnumAdapterByLuid(gpu.device.GetAdapterLuid()) }?;
+    let hwnd = match surface {
+        SwapchainSurface::Hwnd(hwnd) => Some(*hwnd),
+        SwapchainSurface::CoreWindow(_) => None,
+    };
 
     // Setup frame latency
-    unsafe { swapchain.SetMaximumFrameLatency(1).unwrap() };
+    unsafe { swapchain.SetMaximumFrameLatency(max_frame_latency)? };
     let wait_object = unsafe { swapchain.GetFrameLatencyWaitableObject() };
-    unsafe { WaitForSingleObjectEx(wait_object, INFINITE, true) };
+    unsafe { WaitForSingleObjectEx(wait_object, gpu.fence_timeout(), true) };
 
     // Setup RTVs
     let rtv_heap = unsafe {
         gpu.device
             .CreateDescriptorHeap(&D3D12_DESCRIPTOR_HEAP_DESC {
                 Type: D3D12_DESCRIPTOR_HEAP_TYPE_RTV,
-                NumDescriptors: SWAPCHAIN_BUFFER_COUNT as u32,
+                NumDescriptors: swapchain_desc.BufferCount,
                 ..Default::default()
             })
-    }
-    .unwrap();
+    }?;
     let (textures, rtvs) = create_rtvs(&gpu.device, &swapchain, &rtv_heap);
 
     // Wrap into a component
-    WindowRenderTarget {
+    Ok(WindowRenderTarget {
         size: UVec2::new(swapchain_desc.Width, swapchain_desc.Height),
-        swapchain,
+        render_size: UVec2::new(swapchain_desc.Width, swapchain_desc.Height),
+        swapchain: Some(swapchain),
+        adapter,
+        hwnd,
         wait_object,
+        wait_timeout: gpu.fence_timeout(),
         rtv_heap,
+        backbuffer_states: SmallVec::from_elem(D3D12_RESOURCE_STATE_PRESENT, textures.len()),
+        enhanced_barriers: gpu.uses_enhanced_barriers(),
+        validate: gpu.debug_layer_enabled(),
         textures: Some(textures),
         rtvs: Some(rtvs),
+        supports_tearing: swapchain_desc.Flags & DXGI_SWAP_CHAIN_FLAG_ALLOW_TEARING.0 as u32 != 0,
+        present_mode: PresentMode::Vsync,
+        color_space: DXGI_COLOR_SPACE_RGB_FULL_G22_NONE_P709,
+        aspect_ratio: None,
+        letterbox_color: [0.0, 0.0, 0.0, 1.0],
+        swap_effect: swapchain_desc.SwapEffect,
+        scaled_texture: None,
+        blit_pipeline: None,
+        blit_filter: D3D12_FILTER_MIN_MAG_MIP_LINEAR,
+        fixed_resolution_scaling: None,
+        composition,
+        rotation: DXGI_MODE_ROTATION_IDENTITY,
+        rotation_unsupported: false,
+        rotation_queried_for: None,
+    })
+}
+
+/// Record legacy transition barriers for backbuffers and the intermediate scaled texture, or with `enhanced_barriers`,
+/// enhanced barriers between the equivalent layouts. See [`WindowRenderTarget::transition_rtv`].
+///
+/// Each transition is `(texture, before, after, is_backbuffer)`. Only backbuffers skip synchronizing with PRESENT.
+fn record_transitions(
+    enhanced_barriers: bool,
+    command_list: &ID3D12GraphicsCommandList7,
+    transitions: &[(
+        &ID3D12Resource,
+        D3D12_RESOURCE_STATES,
+        D3D12_RESOURCE_STATES,
+        bool,
+    )],
+) {
+    if enhanced_barriers {
+        let barriers: SmallVec<[_; 2]> = transitions
+            .iter()
+            .map(|(texture, before, after, is_backbuffer)| {
+                let (before, after) = (state_layout(*before), state_layout(*after));
+                if *is_backbuffer {
+                    texture_layout_barrier(texture, before, after)
+                } else {
+                    common_texture_layout_barrier(texture, before, after)
+                }
+            })
+            .collect();
+        record_texture_barriers(command_list, &barriers);
+    } else {
+        let barriers: SmallVec<[_; 2]> = transitions
+            .iter()
+            .map(|(texture, before, after, _)| transition_barrier(texture, *before, *after))
+            .collect();
+        unsafe { command_list.ResourceBarrier(&barriers) };
+    }
+}
+
+/// Create, resize, or remove the intermediate scaled texture to match the window size and [`RenderScale`], or
+/// [`FixedResolution`] if set.
+///
+/// GPU should be idle since we waited on the fence in wait_for_ready_frame(), so it's safe to drop the old texture.
+fn update_render_scale(
+    render_target: &mut WindowRenderTarget,
+    gpu: &Gpu,
+    render_scale: RenderScale,
+    fixed_resolution: Option<FixedResolution>,
+) {
+    render_target.fixed_resolution_scaling = fixed_resolution.map(|fixed| fixed.scaling);
+    let (render_size, blit_filter) = match fixed_resolution {
+        Some(fixed) => (fixed.size.max(UVec2::ONE), D3D12_FILTER_MIN_MAG_MIP_POINT),
+        None => (
+            (render_target.size.as_vec2() * render_scale.0)
+                .round()
+                .as_uvec2()
+                .max(UVec2::ONE),
+            D3D12_FILTER_MIN_MAG_MIP_LINEAR,
+        ),
+    };
+
+    if render_size == render_target.size {
+        render_target.render_size = render_target.size;
+        render_target.scaled_texture = None;
+        return;
+    }
+
+    if render_target.blit_pipeline.is_none() || render_target.blit_filter != blit_filter {
+        render_target.blit_pipeline =
+            Some(BlitPipeline::with_filter(gpu, SWAPCHAIN_FORMAT, blit_filter).unwrap());
+        render_target.blit_filter = blit_filter;
+    }
+
+    // Only the blit parameters change on window resize with a fixed resolution, the texture is kept
+    if render_target.scaled_texture.is_some() && render_target.render_size == render_size {
+        return;
+    }
+    render_target.render_size = render_size;
+
+    let texture = gpu
+        .create_texture_2d(
+            render_size.x,
+            render_size.y,
+            SWAPCHAIN_FORMAT,
+            D3D12_RESOURCE_FLAG_ALLOW_RENDER_TARGET,
+            D3D12_RESOURCE_STATE_PRESENT,
+            None,
+        )
+        .unwrap();
+    let rtv_heap = gpu
+        .create_descriptor_heap(D3D12_DESCRIPTOR_HEAP_TYPE_RTV, 1, false)
+        .unwrap();
+    let srv_heap = gpu
+        .create_descriptor_heap(D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV, 1, true)
+        .unwrap();
+    unsafe {
+        gpu.device.CreateRenderTargetView(
+            &texture,
+            None,
+            rtv_heap.GetCPUDescriptorHandleForHeapStart(),
+        );
+        gpu.device.CreateShaderResourceView(
+            &texture,
+            None,
+            srv_heap.GetCPUDescriptorHandleForHeapStart(),
+        );
+    }
+
+    render_target.scaled_texture = Some(ScaledTexture {
+        texture,
+        state: D3D12_RESOURCE_STATE_PRESENT,
+        rtv_heap,
+        srv_heap,
+    });
+}
+
+/// With [`SwapchainConfig::pre_rotate`], match the swapchain's rotation to the output it's on, returning
+/// `swapchain_desc` with its width and height swapped if the backbuffers need to be in the display's native
+/// orientation. Otherwise keep the rotation at identity, leaving rotation to the compositor.
+///
+/// The output is only queried when the window's size or monitor changed since the last call.
+fn apply_output_rotation(
+    render_target: &mut WindowRenderTarget,
+    mut swapchain_desc: DXGI_SWAP_CHAIN_DESC1,
+    pre_rotate: bool,
+) -> DXGI_SWAP_CHAIN_DESC1 {
+    let window_size = UVec2::new(swapchain_desc.Width, swapchain_desc.Height);
+    let monitor = render_target
+        .hwnd
+        .map(|hwnd| unsafe { MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST) })
+        .unwrap_or_default();
+    let queried_for = Some((window_size, monitor));
+
+    let rotation = if !pre_rotate {
+        render_target.rotation_queried_for = None;
+        Some(DXGI_MODE_ROTATION_IDENTITY)
+    } else if render_target.rotation_queried_for != queried_for
+        && !render_target.rotation_unsupported
+    {
+        render_target.rotation_queried_for = queried_for;
+        Some(
+            render_target
+                .output_rotation()
+                .ok()
+                .filter(|rotation| *rotation != DXGI_MODE_ROTATION_UNSPECIFIED)
+                .unwrap_or(DXGI_MODE_ROTATION_IDENTITY),
+        )
+    } else {
+        None
+    };
+
+    if let Some(rotation) = rotation {
+        if rotation != render_target.rotation {
+            match unsafe { render_target.swapchain().unwrap().SetRotation(rotation) } {
+                Ok(()) => render_target.rotation = rotation,
+                // Typically HWND swapchains, which the compositor rotates instead
+                Err(e) => {
+                    warn!("BevyDirectX: Unable to set swapchain rotation to {rotation:?}, leaving it to the compositor: {e}");
+                    render_target.rotation_unsupported = true;
+                }
+            }
+        }
+    }
+
+    if matches!(
+        render_target.rotation,
+        DXGI_MODE_ROTATION_ROTATE90 | DXGI_MODE_ROTATION_ROTATE270
+    ) {
+        mem::swap(&mut swapchain_desc.Width, &mut swapchain_desc.Height);
     }
+    swapchain_desc
+}
+
+fn set_color_space(render_target: &mut WindowRenderTarget, color_space: DXGI_COLOR_SPACE_TYPE) {
+    let supported = unsafe {
+        render_target
+            .swapchain()
+            .unwrap()
+            .CheckColorSpaceSupport(color_space)
+    }
+    .is_ok_and(|support| support & DXGI_SWAP_CHAIN_COLOR_SPACE_SUPPORT_FLAG_PRESENT.0 as u32 != 0);
+
+    let color_space = if supported {
+        color_space
+    } else {
+        warn!("BevyDirectX: Color space {color_space:?} is not supported, falling back to sRGB");
+        DXGI_COLOR_SPACE_RGB_FULL_G22_NONE_P709
+    };
+
+    unsafe {
+        render_target
+            .swapchain()
+            .unwrap()
+            .SetColorSpace1(color_space)
+    }
+    .unwrap();
+    render_target.color_space = color_space;
 }
 
 fn resize_swapchain_if_needed(
     render_target: &mut WindowRenderTarget,
-    swapchain_desc: DXGI_SWAP_CHAIN_DESC1,
+    mut swapchain_desc: DXGI_SWAP_CHAIN_DESC1,
     gpu: &mut Gpu,
 ) {
-    // Skip resizing swapchain if unchanged
     let mut old_swapchain_desc = Default::default();
-    unsafe { render_target.swapchain.GetDesc1(&mut old_swapchain_desc) }.unwrap();
+    unsafe {
+        render_target
+            .swapchain()
+            .unwrap()
+            .GetDesc1(&mut old_swapchain_desc)
+    }
+    .unwrap();
+
+    // Swap effect, alpha mode, buffer count (the RTV heap is sized for it), and flags can't be changed by resizing
+    swapchain_desc.SwapEffect = old_swapchain_desc.SwapEffect;
+    swapchain_desc.AlphaMode = old_swapchain_desc.AlphaMode;
+    swapchain_desc.BufferCount = old_swapchain_desc.BufferCount;
+    swapchain_desc.Flags = old_swapchain_desc.Flags;
+
+    // Skip resizing swapchain if unchanged
     if swapchain_desc == old_swapchain_desc {
         return;
     }
 
-    // GPU should be idle since we waited on the fence in wait_for_ready_swapchain(),
-    // so it's safe to resize the swapchain
+    // The backbuffers must not be in use by the GPU when resizing. The frame wait in wait_for_ready_frame() normally
+    // ensures that already, but it may have been skipped by a custom frame loop, and resizes are rare enough to flush
+    if let Err(e) = gpu.flush() {
+        error!("BevyDirectX: Failed to wait for the GPU before resizing the swapchain: {e}");
+    }
 
     // Drop old textures
     render_target.textures = None;
@@ -210,7 +1702,7 @@ fn resize_swapchain_if_needed(
 
     // Resize swapchain
     unsafe {
-        render_target.swapchain.ResizeBuffers(
+        render_target.swapchain().unwrap().ResizeBuffers(
             swapchain_desc.BufferCount,
             swapchain_desc.Width,
             swapchain_desc.Height,
@@ -223,9 +1715,11 @@ fn resize_swapchain_if_needed(
     // Recreate RTVs
     let (textures, rtvs) = create_rtvs(
         &gpu.device,
-        &render_target.swapchain,
+        render_target.swapchain().unwrap(),
         &render_target.rtv_heap,
     );
+    render_target.backbuffer_states =
+        SmallVec::from_elem(D3D12_RESOURCE_STATE_PRESENT, textures.len());
     render_target.textures = Some(textures);
     render_target.rtvs = Some(rtvs);
 }
@@ -235,39 +1729,268 @@ fn create_rtvs(
     swapchain: &IDXGISwapChain4,
     rtv_heap: &ID3D12DescriptorHeap,
 ) -> (
-    [ID3D12Resource; SWAPCHAIN_BUFFER_COUNT],
-    [D3D12_CPU_DESCRIPTOR_HANDLE; SWAPCHAIN_BUFFER_COUNT],
+    SmallVec<[ID3D12Resource; 3]>,
+    SmallVec<[D3D12_CPU_DESCRIPTOR_HANDLE; 3]>,
 ) {
-    let mut textures = SmallVec::with_capacity(SWAPCHAIN_BUFFER_COUNT);
-    let mut rtvs = [D3D12_CPU_DESCRIPTOR_HANDLE::default(); SWAPCHAIN_BUFFER_COUNT];
+    let mut swapchain_desc = Default::default();
+    unsafe { swapchain.GetDesc1(&mut swapchain_desc) }.unwrap();
+
+    let mut textures = SmallVec::new();
+    let mut rtvs = SmallVec::new();
 
     let heap_increment =
         unsafe { device.GetDescriptorHandleIncrementSize(D3D12_DESCRIPTOR_HEAP_TYPE_RTV) } as usize;
     let mut rtv = unsafe { rtv_heap.GetCPUDescriptorHandleForHeapStart() };
 
-    for i in 0..SWAPCHAIN_BUFFER_COUNT {
-        let texture = unsafe { swapchain.GetBuffer::<ID3D12Resource>(i as u32) }.unwrap();
+    for i in 0..swapchain_desc.BufferCount {
+        let texture = unsafe { swapchain.GetBuffer::<ID3D12Resource>(i) }.unwrap();
         unsafe { device.CreateRenderTargetView(&texture, None, rtv) };
 
         textures.push(texture);
-        rtvs[i] = rtv;
+        rtvs.push(rtv);
 
         rtv.ptr += heap_increment;
     }
 
-    (textures.into_inner().unwrap(), rtvs)
+    (textures, rtvs)
 }
 
-fn get_hwnd(window_handle: &RawHandleWrapperHolder) -> HWND {
-    match window_handle
-        .0
-        .lock()
-        .unwrap()
-        .as_ref()
-        .unwrap()
-        .window_handle
-    {
-        RawWindowHandle::Win32(window_handle) => HWND(window_handle.hwnd.into()),
-        _ => unreachable!(),
+/// The kind of window surface a swapchain is created for.
+///
+/// Supported surfaces:
+/// * [`SwapchainSurface::Hwnd`]: Win32 windows, which Bevy uses on Windows. Created via `CreateSwapChainForHwnd`.
+/// * [`SwapchainSurface::CoreWindow`]: WinRT `CoreWindow`s (UWP). Created via `CreateSwapChainForCoreWindow`.
+///
+/// Composition surfaces (e.g. WinUI/XAML islands) and non-Windows handles are not supported.
+#[derive(Clone, Debug)]
+pub enum SwapchainSurface {
+    Hwnd(HWND),
+    CoreWindow(IUnknown),
+}
+
+impl SwapchainSurface {
+    /// Get the surface for a window from its raw handle, or a description of why it's unsupported.
+    pub fn from_window_handle(window_handle: &RawHandleWrapperHolder) -> Result<Self, String> {
+        let window_handle = window_handle.0.lock().unwrap();
+        let Some(window_handle) = window_handle.as_ref() else {
+            return Err("Window handle is not yet available".to_owned());
+        };
+
+        match window_handle.window_handle {
+            RawWindowHandle::Win32(window_handle) => {
+                Ok(Self::Hwnd(HWND(window_handle.hwnd.into())))
+            }
+            RawWindowHandle::WinRt(window_handle) => {
+                let core_window =
+                    unsafe { IUnknown::from_raw_borrowed(&window_handle.core_window.as_ptr()) }
+                        .unwrap()
+                        .clone();
+                Ok(Self::CoreWindow(core_window))
+            }
+            other => Err(format!("Unsupported window handle type {other:?}")),
+        }
+    }
+
+    fn create_swapchain(
+        &self,
+        gpu: &Gpu,
+        swapchain_desc: &DXGI_SWAP_CHAIN_DESC1,
+    ) -> Result<IDXGISwapChain1, Error> {
+        let factory = gpu.factory.cast::<IDXGIFactory2>()?;
+        unsafe {
+            match self {
+                Self::Hwnd(hwnd) => {
+                    factory.CreateSwapChainForHwnd(&gpu.queue, *hwnd, swapchain_desc, None, None)
+                }
+                Self::CoreWindow(core_window) => factory.CreateSwapChainForCoreWindow(
+                    &gpu.queue,
+                    core_window,
+                    swapchain_desc,
+                    None,
+                ),
+            }
+        }
+    }
+
+    /// Create a composition swapchain, and show it in the window via DirectComposition.
+    fn create_composition_swapchain(
+        &self,
+        gpu: &Gpu,
+        swapchain_desc: &DXGI_SWAP_CHAIN_DESC1,
+    ) -> Result<(IDXGISwapChain1, WindowComposition), Error> {
+        let Self::Hwnd(hwnd) = self else {
+            panic!("BevyDirectX: SwapchainConfig::transparent_window is only supported for Win32 windows");
+        };
+
+        let factory = gpu.factory.cast::<IDXGIFactory2>()?;
+        unsafe {
+            let swapchain =
+                factory.CreateSwapChainForComposition(&gpu.queue, swapchain_desc, None)?;
+
+            let device: IDCompositionDevice = DCompositionCreateDevice(None)?;
+            let target = device.CreateTargetForHwnd(*hwnd, true)?;
+            let visual = device.CreateVisual()?;
+            visual.SetContent(&swapchain)?;
+            target.SetRoot(&visual)?;
+            device.Commit()?;
+
+            Ok((
+                swapchain,
+                WindowComposition {
+                    _device: device,
+                    _target: target,
+                    _visual: visual,
+                },
+            ))
+        }
+    }
+}
+
+/// Region of a `size` backbuffer that a `render_size` texture is scaled onto, see
+/// [`WindowRenderTarget::upscale_to_backbuffer`].
+fn upscale_region(
+    size: UVec2,
+    render_size: UVec2,
+    fixed_resolution_scaling: Option<FixedResolutionScaling>,
+) -> URect {
+    let full = URect::from_corners(UVec2::ZERO, size);
+    if fixed_resolution_scaling != Some(FixedResolutionScaling::Integer) {
+        return full;
+    }
+
+    let render_size = render_size.max(UVec2::ONE);
+    let scale = (size / render_size).min_element();
+    let region_size = if scale >= 1 {
+        render_size * scale
+    } else {
+        // Window is smaller than the fixed resolution, so scale down to fit while keeping the aspect ratio
+        let fit = (size.as_vec2() / render_size.as_vec2()).min_element();
+        (render_size.as_vec2() * fit)
+            .round()
+            .as_uvec2()
+            .max(UVec2::ONE)
+    }
+    .min(size);
+    let min = (size - region_size) / 2;
+
+    URect::from_corners(min, min + region_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows::Win32::Graphics::Dxgi::Common::DXGI_ALPHA_MODE_STRAIGHT;
+
+    #[test]
+    fn default_config_is_valid() {
+        assert_eq!(SwapchainConfig::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn validate_rejects_illegal_combinations() {
+        let invalid = [
+            SwapchainConfig {
+                swap_effect: DXGI_SWAP_EFFECT_DISCARD,
+                ..Default::default()
+            },
+            SwapchainConfig {
+                alpha_mode: DXGI_ALPHA_MODE_PREMULTIPLIED,
+                ..Default::default()
+            },
+            SwapchainConfig {
+                alpha_mode: DXGI_ALPHA_MODE_STRAIGHT,
+                transparent_window: true,
+                ..Default::default()
+            },
+            SwapchainConfig {
+                buffer_count: 1,
+                ..Default::default()
+            },
+            SwapchainConfig {
+                buffer_count: DXGI_MAX_SWAP_CHAIN_BUFFERS + 1,
+                ..Default::default()
+            },
+            SwapchainConfig {
+                max_frame_latency: 0,
+                ..Default::default()
+            },
+            SwapchainConfig {
+                buffer_count: 3,
+                max_frame_latency: 3,
+                ..Default::default()
+            },
+            SwapchainConfig {
+                present_mode: PresentMode::Immediate,
+                ..Default::default()
+            },
+            SwapchainConfig {
+                aspect_ratio: Some(0.0),
+                ..Default::default()
+            },
+            SwapchainConfig {
+                aspect_ratio: Some(f32::NAN),
+                ..Default::default()
+            },
+        ];
+        for config in invalid {
+            assert!(config.validate().is_err(), "{config:?} should be invalid");
+        }
+    }
+
+    #[test]
+    fn validate_accepts_legal_combinations() {
+        let valid = [
+            SwapchainConfig {
+                alpha_mode: DXGI_ALPHA_MODE_PREMULTIPLIED,
+                transparent_window: true,
+                ..Default::default()
+            },
+            SwapchainConfig {
+                swap_effect: DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL,
+                buffer_count: 3,
+                max_frame_latency: 2,
+                ..Default::default()
+            },
+            SwapchainConfig {
+                present_mode: PresentMode::Immediate,
+                allow_tearing: true,
+                aspect_ratio: Some(16.0 / 9.0),
+                ..Default::default()
+            },
+        ];
+        for config in valid {
+            assert_eq!(config.validate(), Ok(()), "{config:?} should be valid");
+        }
+    }
+
+    #[test]
+    fn upscale_region_fills_window_without_integer_scaling() {
+        let size = UVec2::new(1920, 1080);
+        let render_size = UVec2::new(320, 240);
+        let full = URect::from_corners(UVec2::ZERO, size);
+        assert_eq!(upscale_region(size, render_size, None), full);
+        let stretch = Some(FixedResolutionScaling::Stretch);
+        assert_eq!(upscale_region(size, render_size, stretch), full);
+    }
+
+    #[test]
+    fn upscale_region_centers_largest_integer_multiple() {
+        // 4x fits vertically, as 960 <= 1080, but 5x doesn't, leaving bars on every side
+        let region = upscale_region(
+            UVec2::new(1920, 1080),
+            UVec2::new(320, 240),
+            Some(FixedResolutionScaling::Integer),
+        );
+        assert_eq!(region, URect::new(320, 60, 1600, 1020));
+    }
+
+    #[test]
+    fn upscale_region_shrinks_to_fit_smaller_window() {
+        let region = upscale_region(
+            UVec2::new(200, 300),
+            UVec2::new(400, 300),
+            Some(FixedResolutionScaling::Integer),
+        );
+        assert_eq!(region, URect::new(0, 75, 200, 225));
     }
 }
@@ -0,0 +1,238 @@
+use crate::{
+    error::DxError, format_support::resource_creation_error, gpu::Gpu,
+    resource_tracker::transition_barrier,
+};
+use std::{mem::transmute_copy, ptr};
+use windows::{
+    core::Error,
+    Win32::{
+        Foundation::E_INVALIDARG,
+        Graphics::{Direct3D12::*, Dxgi::Common::*},
+    },
+};
+
+impl Gpu {
+    /// Create a texture in GPU memory, and fill it with data copied from a temporary upload buffer.
+    ///
+    /// `subresources` holds the data for each subresource in D3D12 subresource order (every mip of the first array
+    /// slice, then every mip of the next, and so on). The rows of each subresource must be tightly packed, with no
+    /// padding between them. See [`Gpu::subresource_layouts`] for the expected size of each subresource.
+    ///
+    /// For block-compressed (BCn) formats, a row is a row of 4x4 blocks, and mips smaller than 4x4 texels still take
+    /// up a whole block. The width and height of the texture (its first mip) must be multiples of 4.
+    ///
+    /// The texture is left in the COMMON state, from which it's implicitly promoted when first read by a shader.
+    ///
+    /// This records, executes, and waits for its own commands using [`Gpu::reset_commands`], so it must not be called
+    /// while recording a frame.
+    pub fn create_texture_with_data(
+        &mut self,
+        desc: &D3D12_RESOURCE_DESC,
+        subresources: &[&[u8]],
+    ) -> Result<ID3D12Resource, DxError> {
+        if block_compressed_block_size(desc.Format).is_some()
+            && (desc.Width % 4 != 0 || desc.Height % 4 != 0)
+        {
+            return Err(Error::new(
+                E_INVALIDARG,
+                "BevyDirectX: Block-compressed texture dimensions must be multiples of 4",
+            )
+            .into());
+        }
+
+        let layouts = self.subresource_layouts(desc);
+        assert_eq!(
+            subresources.len(),
+            layouts.len(),
+            "BevyDirectX: create_texture_with_data() needs data for each of the texture's subresources"
+        );
+
+        self.check_memory_budget(desc, D3D12_HEAP_TYPE_DEFAULT)?;
+
+        let mut texture = None;
+        unsafe {
+            self.device.CreateCommittedResource(
+                &D3D12_HEAP_PROPERTIES {
+                    Type: D3D12_HEAP_TYPE_DEFAULT,
+                    ..Default::default()
+                },
+                D3D12_HEAP_FLAG_NONE,
+                desc,
+                D3D12_RESOURCE_STATE_COPY_DEST,
+                None,
+                &mut texture,
+            )
+        }
+        .map_err(|e| resource_creation_error(&self.device, desc, e))?;
+        let texture: ID3D12Resource = texture.unwrap();
+
+        // Copy each subresource into the upload buffer, padding rows to the footprint's row pitch
+        let upload_size = layouts
+            .last()
+            .map_or(0, |layout| layout.footprint.Offset + layout.size_in_bytes());
+        let upload_buffer = self.create_buffer(
+            upload_size,
+            D3D12_HEAP_TYPE_UPLOAD,
+            D3D12_RESOURCE_FLAG_NONE,
+            D3D12_RESOURCE_STATE_GENERIC_READ,
+        )?;
+        unsafe {
+            let mut mapped = ptr::null_mut();
+            upload_buffer.Map(
+                0,
+                Some(&D3D12_RANGE { Begin: 0, End: 0 }),
+                Some(&mut mapped),
+            )?;
+            let mapped = mapped as *mut u8;
+            for (layout, data) in layouts.iter().zip(subresources) {
+                assert_eq!(
+                    data.len() as u64,
+                    layout.packed_size_in_bytes(),
+                    "BevyDirectX: create_texture_with_data() subresource data has the wrong size"
+                );
+                // Rows of each depth slice are contiguous, so the padded row pitch applies across slices too
+                for (row, source) in data.chunks_exact(layout.row_size as usize).enumerate() {
+                    let offset = layout.footprint.Offset
+                        + row as u64 * layout.footprint.Footprint.RowPitch as u64;
+                    ptr::copy_nonoverlapping(
+                        source.as_ptr(),
+                        mapped.add(offset as usize),
+                        source.len(),
+                    );
+                }
+            }
+            upload_buffer.Unmap(0, None);
+        }
+
+        let command_list = self.reset_commands(None)?;
+        unsafe {
+            for (i, layout) in layouts.iter().enumerate() {
+                command_list.CopyTextureRegion(
+                    &D3D12_TEXTURE_COPY_LOCATION {
+                        pResource: transmute_copy(&texture),
+                        Type: D3D12_TEXTURE_COPY_TYPE_SUBRESOURCE_INDEX,
+                        Anonymous: D3D12_TEXTURE_COPY_LOCATION_0 {
+                            SubresourceIndex: i as u32,
+                        },
+                    },
+                    0,
+                    0,
+                    0,
+                    &D3D12_TEXTURE_COPY_LOCATION {
+                        pResource: transmute_copy(&upload_buffer),
+                        Type: D3D12_TEXTURE_COPY_TYPE_PLACED_FOOTPRINT,
+                        Anonymous: D3D12_TEXTURE_COPY_LOCATION_0 {
+                            PlacedFootprint: layout.footprint,
+                        },
+                    },
+                    None,
+                );
+            }
+            command_list.ResourceBarrier(&[transition_barrier(
+                &texture,
+                D3D12_RESOURCE_STATE_COPY_DEST,
+                D3D12_RESOURCE_STATE_COMMON,
+            )]);
+        }
+
+        // Wait for the GPU before the upload buffer is dropped
+        self.execute_command_list()?;
+        self.signal_fence()?;
+        self.wait_for_fence()?;
+        Ok(texture)
+    }
+
+    /// Layout of each subresource of a texture with the given desc, when copied to or from a buffer.
+    ///
+    /// Uses `GetCopyableFootprints`, which accounts for block-compressed formats: row counts and sizes are in blocks,
+    /// and mip dimensions are rounded up to whole blocks.
+    pub fn subresource_layouts(&self, desc: &D3D12_RESOURCE_DESC) -> Vec<SubresourceLayout> {
+        let array_size = if desc.Dimension == D3D12_RESOURCE_DIMENSION_TEXTURE3D {
+            1
+        } else {
+            desc.DepthOrArraySize as u32
+        };
+        let count = desc.MipLevels as u32 * array_size;
+
+        let mut footprints = vec![D3D12_PLACED_SUBRESOURCE_FOOTPRINT::default(); count as usize];
+        let mut num_rows = vec![0; count as usize];
+        let mut row_sizes = vec![0; count as usize];
+        unsafe {
+            self.device.GetCopyableFootprints(
+                desc,
+                0,
+                count,
+                0,
+                Some(footprints.as_mut_ptr()),
+                Some(num_rows.as_mut_ptr()),
+                Some(row_sizes.as_mut_ptr()),
+                None,
+            );
+        }
+
+        footprints
+            .into_iter()
+            .zip(num_rows)
+            .zip(row_sizes)
+            .map(|((footprint, num_rows), row_size)| SubresourceLayout {
+                footprint,
+                num_rows,
+                row_size,
+            })
+            .collect()
+    }
+}
+
+/// Layout of a texture subresource in a buffer, from [`Gpu::subresource_layouts`].
+#[derive(Clone, Copy, Debug)]
+pub struct SubresourceLayout {
+    /// Offset and row pitch of the subresource in a buffer holding all subresources.
+    pub footprint: D3D12_PLACED_SUBRESOURCE_FOOTPRINT,
+    /// Number of rows of texels, or of 4x4 blocks for block-compressed formats.
+    pub num_rows: u32,
+    /// Size in bytes of each row, without padding.
+    pub row_size: u64,
+}
+
+impl SubresourceLayout {
+    /// Size in bytes of the subresource with tightly packed rows, as expected by [`Gpu::create_texture_with_data`].
+    pub fn packed_size_in_bytes(&self) -> u64 {
+        self.row_size * self.num_rows as u64 * self.footprint.Footprint.Depth as u64
+    }
+
+    /// Size in bytes of the subresource in a buffer, including row padding.
+    pub fn size_in_bytes(&self) -> u64 {
+        self.footprint.Footprint.RowPitch as u64
+            * self.num_rows as u64
+            * self.footprint.Footprint.Depth as u64
+    }
+}
+
+/// Size in bytes of each 4x4 texel block of a block-compressed (BCn) format, or `None` if the format isn't
+/// block-compressed.
+pub fn block_compressed_block_size(format: DXGI_FORMAT) -> Option<u32> {
+    match format {
+        DXGI_FORMAT_BC1_TYPELESS
+        | DXGI_FORMAT_BC1_UNORM
+        | DXGI_FORMAT_BC1_UNORM_SRGB
+        | DXGI_FORMAT_BC4_TYPELESS
+        | DXGI_FORMAT_BC4_UNORM
+        | DXGI_FORMAT_BC4_SNORM => Some(8),
+        DXGI_FORMAT_BC2_TYPELESS
+        | DXGI_FORMAT_BC2_UNORM
+        | DXGI_FORMAT_BC2_UNORM_SRGB
+        | DXGI_FORMAT_BC3_TYPELESS
+        | DXGI_FORMAT_BC3_UNORM
+        | DXGI_FORMAT_BC3_UNORM_SRGB
+        | DXGI_FORMAT_BC5_TYPELESS
+        | DXGI_FORMAT_BC5_UNORM
+        | DXGI_FORMAT_BC5_SNORM
+        | DXGI_FORMAT_BC6H_TYPELESS
+        | DXGI_FORMAT_BC6H_UF16
+        | DXGI_FORMAT_BC6H_SF16
+        | DXGI_FORMAT_BC7_TYPELESS
+        | DXGI_FORMAT_BC7_UNORM
+        | DXGI_FORMAT_BC7_UNORM_SRGB => Some(16),
+        _ => None,
+    }
+}
@@ -0,0 +1,327 @@
+use crate::{error::DxError, gpu::Gpu};
+use windows::{
+    core::Error,
+    Win32::{
+        Foundation::{E_INVALIDARG, E_NOTIMPL},
+        Graphics::{Direct3D12::*, Dxgi::Common::*},
+    },
+};
+
+/// File format of the bytes passed to [`Gpu::load_texture_from_bytes`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureFileFormat {
+    /// DirectDraw Surface, parsed directly, keeping its mips and block-compressed format.
+    Dds,
+    Png,
+    Jpeg,
+}
+
+impl TextureFileFormat {
+    /// Guess the format from the first bytes of a file.
+    pub fn from_magic_bytes(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [b'D', b'D', b'S', b' ', ..] => Some(Self::Dds),
+            [0x89, b'P', b'N', b'G', ..] => Some(Self::Png),
+            [0xFF, 0xD8, 0xFF, ..] => Some(Self::Jpeg),
+            _ => None,
+        }
+    }
+}
+
+impl Gpu {
+    /// Decode a texture file, and upload it to a new 2D texture with [`Gpu::create_texture_with_data`].
+    ///
+    /// PNG and JPEG files are decoded with the `image` crate to a single-mip `DXGI_FORMAT_R8G8B8A8_UNORM_SRGB` texture.
+    /// DDS files keep their format and mips, which is the only way to load block-compressed (BCn) textures. If
+    /// `format_hint` is `None`, the format is guessed from the file's contents.
+    ///
+    /// Only available with the `texture_loading` feature. Must not be called while recording a frame.
+    pub fn load_texture_from_bytes(
+        &mut self,
+        bytes: &[u8],
+        format_hint: Option<TextureFileFormat>,
+    ) -> Result<ID3D12Resource, DxError> {
+        let format = format_hint
+            .or_else(|| TextureFileFormat::from_magic_bytes(bytes))
+            .ok_or_else(|| {
+                Error::new(
+                    E_INVALIDARG,
+                    "BevyDirectX: Unrecognized texture file format",
+                )
+            })?;
+
+        match format {
+            TextureFileFormat::Dds => self.load_dds(bytes),
+            TextureFileFormat::Png | TextureFileFormat::Jpeg => {
+                let image_format = if format == TextureFileFormat::Png {
+                    image::ImageFormat::Png
+                } else {
+                    image::ImageFormat::Jpeg
+                };
+                let image = image::load_from_memory_with_format(bytes, image_format)
+                    .map_err(|e| {
+                        Error::new(
+                            E_INVALIDARG,
+                            format!("BevyDirectX: Failed to decode texture: {e}"),
+                        )
+                    })?
+                    .into_rgba8();
+
+                let desc = texture_2d_desc(
+                    image.width(),
+                    image.height(),
+                    1,
+                    1,
+                    DXGI_FORMAT_R8G8B8A8_UNORM_SRGB,
+                );
+                self.create_texture_with_data(&desc, &[image.as_raw()])
+            }
+        }
+    }
+
+    fn load_dds(&mut self, bytes: &[u8]) -> Result<ID3D12Resource, DxError> {
+        let header = DdsHeader::parse(bytes)?;
+        let desc = texture_2d_desc(
+            header.width,
+            header.height,
+            header.array_size,
+            header.mip_count,
+            header.format,
+        );
+
+        // Subresources are stored back to back, in the same order as D3D12 subresource indices
+        let mut data = &bytes[header.data_offset..];
+        let mut subresources = Vec::new();
+        for layout in self.subresource_layouts(&desc) {
+            let size = layout.packed_size_in_bytes() as usize;
+            if data.len() < size {
+                return Err(Error::new(E_INVALIDARG, "BevyDirectX: DDS file is truncated").into());
+            }
+            let (subresource, rest) = data.split_at(size);
+            subresources.push(subresource);
+            data = rest;
+        }
+
+        self.create_texture_with_data(&desc, &subresources)
+    }
+}
+
+fn texture_2d_desc(
+    width: u32,
+    height: u32,
+    array_size: u32,
+    mip_levels: u32,
+    format: DXGI_FORMAT,
+) -> D3D12_RESOURCE_DESC {
+    D3D12_RESOURCE_DESC {
+        Dimension: D3D12_RESOURCE_DIMENSION_TEXTURE2D,
+        Width: width as u64,
+        Height: height,
+        DepthOrArraySize: array_size as u16,
+        MipLevels: mip_levels as u16,
+        Format: format,
+        SampleDesc: DXGI_SAMPLE_DESC {
+            Count: 1,
+            Quality: 0,
+        },
+        Layout: D3D12_TEXTURE_LAYOUT_UNKNOWN,
+        Flags: D3D12_RESOURCE_FLAG_NONE,
+        ..Default::default()
+    }
+}
+
+/// The parts of a DDS file's `DDS_HEADER` and optional `DDS_HEADER_DXT10` needed to create a 2D texture.
+struct DdsHeader {
+    width: u32,
+    height: u32,
+    mip_count: u32,
+    array_size: u32,
+    format: DXGI_FORMAT,
+    data_offset: usize,
+}
+
+impl DdsHeader {
+    fn parse(bytes: &[u8]) -> Result<Self, DxError> {
+        let invalid = |message: &str| -> DxError {
+            Error::new(
+                E_INVALIDARG,
+                format!("BevyDirectX: Invalid DDS file: {message}"),
+            )
+            .into()
+        };
+        let unsupported = |message: &str| -> DxError {
+            Error::new(
+                E_NOTIMPL,
+                format!("BevyDirectX: Unsupported DDS file: {message}"),
+            )
+            .into()
+        };
+        let read_u32 = |offset: usize| -> Result<u32, DxError> {
+            bytes
+                .get(offset..offset + 4)
+                .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+                .ok_or_else(|| invalid("header is truncated"))
+        };
+
+        if !bytes.starts_with(b"DDS ") || read_u32(4)? != 124 {
+            return Err(invalid("missing DDS header"));
+        }
+        let height = read_u32(12)?;
+        let width = read_u32(16)?;
+        let mip_count = read_u32(28)?.max(1);
+        let pixel_format_flags = read_u32(80)?;
+        let four_cc = read_u32(84)?.to_le_bytes();
+        let caps2 = read_u32(112)?;
+
+        const DDPF_FOURCC: u32 = 0x4;
+        const DDPF_RGB: u32 = 0x40;
+        const DDSCAPS2_CUBEMAP: u32 = 0x200;
+        const DDSCAPS2_VOLUME: u32 = 0x200000;
+        const DDS_RESOURCE_MISC_TEXTURECUBE: u32 = 0x4;
+        const DDS_DIMENSION_TEXTURE2D: u32 = 3;
+
+        if caps2 & (DDSCAPS2_CUBEMAP | DDSCAPS2_VOLUME) != 0 {
+            return Err(unsupported("cube maps and volume textures"));
+        }
+
+        let mut array_size = 1;
+        let mut data_offset = 128;
+        let format = if pixel_format_flags & DDPF_FOURCC != 0 {
+            match &four_cc {
+                b"DXT1" => DXGI_FORMAT_BC1_UNORM,
+                b"DXT2" | b"DXT3" => DXGI_FORMAT_BC2_UNORM,
+                b"DXT4" | b"DXT5" => DXGI_FORMAT_BC3_UNORM,
+                b"ATI1" | b"BC4U" => DXGI_FORMAT_BC4_UNORM,
+                b"BC4S" => DXGI_FORMAT_BC4_SNORM,
+                b"ATI2" | b"BC5U" => DXGI_FORMAT_BC5_UNORM,
+                b"BC5S" => DXGI_FORMAT_BC5_SNORM,
+                b"DX10" => {
+                    if read_u32(132)? != DDS_DIMENSION_TEXTURE2D
+                        || read_u32(136)? & DDS_RESOURCE_MISC_TEXTURECUBE != 0
+                    {
+                        return Err(unsupported("only 2D textures can be loaded"));
+                    }
+                    array_size = read_u32(140)?.max(1);
+                    data_offset = 148;
+                    DXGI_FORMAT(read_u32(128)? as i32)
+                }
+                _ => return Err(unsupported("unknown FourCC")),
+            }
+        } else if pixel_format_flags & DDPF_RGB != 0 && read_u32(88)? == 32 {
+            // Distinguish RGBA and BGRA by the red channel's bit mask
+            match read_u32(92)? {
+                0x000000FF => DXGI_FORMAT_R8G8B8A8_UNORM,
+                0x00FF0000 => DXGI_FORMAT_B8G8R8A8_UNORM,
+                _ => return Err(unsupported("unknown 32-bit pixel layout")),
+            }
+        } else {
+            return Err(unsupported("unknown pixel format"));
+        };
+        if bytes.len() < data_offset {
+            return Err(invalid("header is truncated"));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            mip_count,
+            array_size,
+            format,
+            data_offset,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows::core::HRESULT;
+
+    const DDPF_FOURCC: u32 = 0x4;
+    const DDPF_RGB: u32 = 0x40;
+
+    fn write_u32(bytes: &mut [u8], offset: usize, value: u32) {
+        bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// A 128 byte `DDS_HEADER` for a 64x32 texture with 7 mips, and the given pixel format flags and FourCC.
+    fn dds_header(pixel_format_flags: u32, four_cc: &[u8; 4]) -> Vec<u8> {
+        let mut bytes = vec![0; 128];
+        bytes[..4].copy_from_slice(b"DDS ");
+        write_u32(&mut bytes, 4, 124);
+        write_u32(&mut bytes, 12, 32);
+        write_u32(&mut bytes, 16, 64);
+        write_u32(&mut bytes, 28, 7);
+        write_u32(&mut bytes, 80, pixel_format_flags);
+        bytes[84..88].copy_from_slice(four_cc);
+        bytes
+    }
+
+    fn error_code(bytes: &[u8]) -> HRESULT {
+        match DdsHeader::parse(bytes) {
+            Ok(_) => panic!("Parsing succeeded"),
+            Err(e) => e.windows_error().code(),
+        }
+    }
+
+    #[test]
+    fn parses_fourcc_header() {
+        let header = DdsHeader::parse(&dds_header(DDPF_FOURCC, b"DXT1")).unwrap();
+        assert_eq!((header.width, header.height), (64, 32));
+        assert_eq!((header.mip_count, header.array_size), (7, 1));
+        assert_eq!(header.format, DXGI_FORMAT_BC1_UNORM);
+        assert_eq!(header.data_offset, 128);
+    }
+
+    #[test]
+    fn parses_dx10_header() {
+        let mut bytes = dds_header(DDPF_FOURCC, b"DX10");
+        bytes.resize(148, 0);
+        write_u32(&mut bytes, 128, DXGI_FORMAT_BC7_UNORM_SRGB.0 as u32);
+        write_u32(&mut bytes, 132, 3);
+        write_u32(&mut bytes, 140, 4);
+
+        let header = DdsHeader::parse(&bytes).unwrap();
+        assert_eq!(header.format, DXGI_FORMAT_BC7_UNORM_SRGB);
+        assert_eq!(header.array_size, 4);
+        assert_eq!(header.data_offset, 148);
+    }
+
+    #[test]
+    fn parses_uncompressed_header() {
+        let mut bytes = dds_header(DDPF_RGB, &[0; 4]);
+        write_u32(&mut bytes, 88, 32);
+        write_u32(&mut bytes, 92, 0x00FF0000);
+        let header = DdsHeader::parse(&bytes).unwrap();
+        assert_eq!(header.format, DXGI_FORMAT_B8G8R8A8_UNORM);
+
+        write_u32(&mut bytes, 92, 0x000000FF);
+        let header = DdsHeader::parse(&bytes).unwrap();
+        assert_eq!(header.format, DXGI_FORMAT_R8G8B8A8_UNORM);
+    }
+
+    #[test]
+    fn rejects_truncated_header() {
+        let bytes = dds_header(DDPF_FOURCC, b"DXT5");
+        assert_eq!(error_code(&bytes[..100]), E_INVALIDARG);
+        assert_eq!(error_code(&bytes[..4]), E_INVALIDARG);
+        assert_eq!(error_code(&[]), E_INVALIDARG);
+
+        // Missing the DDS_HEADER_DXT10 that the FourCC says follows
+        let bytes = dds_header(DDPF_FOURCC, b"DX10");
+        assert_eq!(error_code(&bytes), E_INVALIDARG);
+        assert_eq!(
+            error_code(&[bytes.as_slice(), &[0; 16]].concat()),
+            E_INVALIDARG
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_textures() {
+        assert_eq!(error_code(&dds_header(DDPF_FOURCC, b"ABCD")), E_NOTIMPL);
+
+        let mut cube_map = dds_header(DDPF_FOURCC, b"DXT1");
+        write_u32(&mut cube_map, 112, 0x200);
+        assert_eq!(error_code(&cube_map), E_NOTIMPL);
+    }
+}
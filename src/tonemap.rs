@@ -0,0 +1,203 @@
+use crate::{
+    error::DxError,
+    fullscreen_triangle::{FULLSCREEN_TRIANGLE_HLSL, FULLSCREEN_TRIANGLE_VS},
+    gpu::Gpu,
+    shader::compile_shader,
+};
+use bevy::prelude::Resource;
+use std::{borrow::Cow, mem::transmute_copy};
+use windows::Win32::{
+    Foundation::RECT,
+    Graphics::{
+        Direct3D::D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST,
+        Direct3D12::*,
+        Dxgi::Common::{
+            DXGI_FORMAT, DXGI_FORMAT_B8G8R8A8_UNORM_SRGB, DXGI_FORMAT_R8G8B8A8_UNORM_SRGB,
+        },
+    },
+};
+
+const TONEMAP_HLSL: &str = include_str!("../assets/tonemap.hlsl");
+/// [`TONEMAP_HLSL`], compiled to DXIL by the build script, or empty if DXC wasn't available.
+const TONEMAP_PS: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/tonemap_ps.dxil"));
+
+/// Curve used by [`TonemapPass`] to map HDR colors to the displayable 0-1 range.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TonemapOperator {
+    /// Simple and cheap, but desaturates and dulls bright colors.
+    Reinhard,
+    /// Fit of the filmic ACES curve, with more contrast, but oversaturates and hue-shifts bright colors.
+    Aces,
+    /// Filmic curve that desaturates bright colors towards white, and handles very bright saturated colors well.
+    #[default]
+    Agx,
+}
+
+/// Settings for [`TonemapPass::draw`].
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub struct TonemapSettings {
+    pub operator: TonemapOperator,
+    /// Multiplier applied to HDR colors before tonemapping. Defaults to 1.
+    pub exposure: f32,
+}
+
+impl Default for TonemapSettings {
+    fn default() -> Self {
+        Self {
+            operator: TonemapOperator::default(),
+            exposure: 1.0,
+        }
+    }
+}
+
+/// Fullscreen pass that tonemaps an HDR texture, such as an [`crate::OffscreenTarget`], onto a render target such
+/// as the window's backbuffer.
+///
+/// If the render target format isn't an `_SRGB` format, the output is sRGB-encoded in the shader.
+pub struct TonemapPass {
+    root_signature: ID3D12RootSignature,
+    pipeline: ID3D12PipelineState,
+    encode_srgb: bool,
+}
+
+impl TonemapPass {
+    pub fn new(gpu: &Gpu, rtv_format: DXGI_FORMAT) -> Result<Self, DxError> {
+        let srv_range = D3D12_DESCRIPTOR_RANGE1 {
+            RangeType: D3D12_DESCRIPTOR_RANGE_TYPE_SRV,
+            NumDescriptors: 1,
+            ..Default::default()
+        };
+        let root_signature = gpu.create_root_signature(
+            &[
+                D3D12_ROOT_PARAMETER1 {
+                    ParameterType: D3D12_ROOT_PARAMETER_TYPE_32BIT_CONSTANTS,
+                    Anonymous: D3D12_ROOT_PARAMETER1_0 {
+                        Constants: D3D12_ROOT_CONSTANTS {
+                            Num32BitValues: 3,
+                            ..Default::default()
+                        },
+                    },
+                    ShaderVisibility: D3D12_SHADER_VISIBILITY_PIXEL,
+                },
+                D3D12_ROOT_PARAMETER1 {
+                    ParameterType: D3D12_ROOT_PARAMETER_TYPE_DESCRIPTOR_TABLE,
+                    Anonymous: D3D12_ROOT_PARAMETER1_0 {
+                        DescriptorTable: D3D12_ROOT_DESCRIPTOR_TABLE1 {
+                            NumDescriptorRanges: 1,
+                            pDescriptorRanges: &srv_range,
+                        },
+                    },
+                    ShaderVisibility: D3D12_SHADER_VISIBILITY_PIXEL,
+                },
+            ],
+            &[D3D12_STATIC_SAMPLER_DESC {
+                Filter: D3D12_FILTER_MIN_MAG_MIP_POINT,
+                AddressU: D3D12_TEXTURE_ADDRESS_MODE_CLAMP,
+                AddressV: D3D12_TEXTURE_ADDRESS_MODE_CLAMP,
+                AddressW: D3D12_TEXTURE_ADDRESS_MODE_CLAMP,
+                ShaderVisibility: D3D12_SHADER_VISIBILITY_PIXEL,
+                ..Default::default()
+            }],
+            D3D12_ROOT_SIGNATURE_FLAG_NONE,
+        )?;
+
+        // FXC and DXC shaders can't be mixed in a pipeline, so without DXIL both stages are compiled with FXC
+        let (vs, ps) = if TONEMAP_PS.is_empty() {
+            // D3DCompile is given no include handler, so resolve the include by hand
+            let source = TONEMAP_HLSL.replace(
+                "#include \"fullscreen_triangle.hlsl\"",
+                FULLSCREEN_TRIANGLE_HLSL,
+            );
+            (
+                Cow::Owned(compile_shader(&source, "FullscreenVSMain", "vs_5_1")?),
+                Cow::Owned(compile_shader(&source, "PSMain", "ps_5_1")?),
+            )
+        } else {
+            (
+                Cow::Borrowed(FULLSCREEN_TRIANGLE_VS),
+                Cow::Borrowed(TONEMAP_PS),
+            )
+        };
+        let mut desc = D3D12_GRAPHICS_PIPELINE_STATE_DESC {
+            pRootSignature: unsafe { transmute_copy(&root_signature) },
+            VS: D3D12_SHADER_BYTECODE {
+                pShaderBytecode: vs.as_ptr() as _,
+                BytecodeLength: vs.len(),
+            },
+            PS: D3D12_SHADER_BYTECODE {
+                pShaderBytecode: ps.as_ptr() as _,
+                BytecodeLength: ps.len(),
+            },
+            SampleMask: u32::MAX,
+            RasterizerState: D3D12_RASTERIZER_DESC {
+                FillMode: D3D12_FILL_MODE_SOLID,
+                CullMode: D3D12_CULL_MODE_NONE,
+                ..Default::default()
+            },
+            PrimitiveTopologyType: D3D12_PRIMITIVE_TOPOLOGY_TYPE_TRIANGLE,
+            NumRenderTargets: 1,
+            ..Default::default()
+        };
+        desc.BlendState.RenderTarget[0].RenderTargetWriteMask =
+            D3D12_COLOR_WRITE_ENABLE_ALL.0 as u8;
+        desc.RTVFormats[0] = rtv_format;
+        desc.SampleDesc.Count = 1;
+        let pipeline = gpu
+            .pipeline_cache()
+            .create_graphics_pipeline(&gpu.device, &desc)?;
+
+        Ok(Self {
+            root_signature,
+            pipeline,
+            encode_srgb: !matches!(
+                rtv_format,
+                DXGI_FORMAT_R8G8B8A8_UNORM_SRGB | DXGI_FORMAT_B8G8R8A8_UNORM_SRGB
+            ),
+        })
+    }
+
+    /// Tonemap `hdr_srv` (a GPU handle within `srv_heap`) onto `destination_rtv`, covering `viewport`.
+    ///
+    /// The source must be in a pixel shader resource state, and the destination in the render target state.
+    /// Binds `srv_heap` as the command list's descriptor heap.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &self,
+        command_list: &ID3D12GraphicsCommandList7,
+        settings: &TonemapSettings,
+        srv_heap: &ID3D12DescriptorHeap,
+        hdr_srv: D3D12_GPU_DESCRIPTOR_HANDLE,
+        destination_rtv: D3D12_CPU_DESCRIPTOR_HANDLE,
+        viewport: D3D12_VIEWPORT,
+        scissor_rect: RECT,
+    ) {
+        let operator = match settings.operator {
+            TonemapOperator::Reinhard => 0,
+            TonemapOperator::Aces => 1,
+            TonemapOperator::Agx => 2,
+        };
+        let constants = [
+            settings.exposure.to_bits(),
+            operator,
+            self.encode_srgb as u32,
+        ];
+
+        unsafe {
+            command_list.SetPipelineState(&self.pipeline);
+            command_list.SetGraphicsRootSignature(&self.root_signature);
+            command_list.SetDescriptorHeaps(&[Some(srv_heap.clone())]);
+            command_list.SetGraphicsRoot32BitConstants(
+                0,
+                constants.len() as u32,
+                constants.as_ptr() as _,
+                0,
+            );
+            command_list.SetGraphicsRootDescriptorTable(1, hdr_srv);
+            command_list.RSSetViewports(&[viewport]);
+            command_list.RSSetScissorRects(&[scissor_rect]);
+            command_list.OMSetRenderTargets(1, Some(&destination_rtv), false, None);
+            command_list.IASetPrimitiveTopology(D3D_PRIMITIVE_TOPOLOGY_TRIANGLELIST);
+            command_list.DrawInstanced(3, 1, 0, 0);
+        }
+    }
+}
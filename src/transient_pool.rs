@@ -0,0 +1,289 @@
+use crate::{
+    error::DxError, format_support::resource_creation_error, gpu::Gpu,
+    resource_tracker::aliasing_barrier,
+};
+use bevy::prelude::info;
+use std::{cmp::Reverse, ops::RangeInclusive};
+use windows::Win32::Graphics::Direct3D12::*;
+
+/// An intermediate resource used only during a range of passes within a frame, see [`TransientResourcePool`].
+#[derive(Clone)]
+pub struct TransientResourceDesc {
+    pub desc: D3D12_RESOURCE_DESC,
+    /// State the resource is created in. The resource must be back in this state after its last pass each frame.
+    pub initial_state: D3D12_RESOURCE_STATES,
+    /// Required for render target and depth stencil textures.
+    pub optimized_clear_value: Option<D3D12_CLEAR_VALUE>,
+    /// Indices of the first and last passes using the resource. Pass indices are arbitrary, but must increase in
+    /// the order passes are recorded.
+    pub passes: RangeInclusive<u32>,
+}
+
+/// Intermediate resources that don't overlap in time, placed into a shared heap so that they share memory.
+///
+/// Each frame, call [`TransientResourcePool::begin_pass`] before recording each pass, to insert aliasing barriers for
+/// resources whose first use is that pass. A resource's contents are undefined at the start of its first pass. Render
+/// target and depth stencil textures in particular must be cleared, discarded with [`Gpu::discard_resource`], or fully
+/// overwritten with a copy before being read from.
+///
+/// If the GPU only supports `D3D12_RESOURCE_HEAP_TIER_1`, which can't mix buffers, render target textures, and other
+/// textures in one heap, every resource instead gets its own committed resource, with no aliasing.
+pub struct TransientResourcePool {
+    resources: Vec<TransientResource>,
+    heap: Option<ID3D12Heap>,
+    heap_size: u64,
+}
+
+struct TransientResource {
+    resource: ID3D12Resource,
+    first_pass: u32,
+    /// Whether any other resource in the pool shares memory with this one.
+    aliased: bool,
+}
+
+impl TransientResourcePool {
+    pub fn new(gpu: &Gpu, descs: &[TransientResourceDesc]) -> Result<Self, DxError> {
+        if descs.is_empty() || gpu.capabilities().resource_heap_tier == D3D12_RESOURCE_HEAP_TIER_1 {
+            return Self::new_committed(gpu, descs);
+        }
+
+        let allocations = descs
+            .iter()
+            .map(|desc| unsafe { gpu.device.GetResourceAllocationInfo(0, &[desc.desc]) })
+            .collect::<Vec<_>>();
+
+        let lifetimes = descs
+            .iter()
+            .map(|desc| desc.passes.clone())
+            .collect::<Vec<_>>();
+        let offsets = place_resources(&lifetimes, &allocations);
+
+        let heap_size = (0..descs.len())
+            .map(|i| offsets[i] + allocations[i].SizeInBytes)
+            .max()
+            .unwrap();
+        let heap_alignment = allocations
+            .iter()
+            .map(|allocation| allocation.Alignment)
+            .max()
+            .unwrap();
+        let mut heap: Option<ID3D12Heap> = None;
+        unsafe {
+            gpu.device.CreateHeap(
+                &D3D12_HEAP_DESC {
+                    SizeInBytes: heap_size,
+                    Properties: D3D12_HEAP_PROPERTIES {
+                        Type: D3D12_HEAP_TYPE_DEFAULT,
+                        ..Default::default()
+                    },
+                    Alignment: heap_alignment,
+                    Flags: D3D12_HEAP_FLAG_ALLOW_ALL_BUFFERS_AND_TEXTURES,
+                },
+                &mut heap,
+            )?;
+        }
+        let heap = heap.unwrap();
+
+        let mut resources = Vec::with_capacity(descs.len());
+        for (i, desc) in descs.iter().enumerate() {
+            let mut resource = None;
+            unsafe {
+                gpu.device.CreatePlacedResource(
+                    &heap,
+                    offsets[i],
+                    &desc.desc,
+                    desc.initial_state,
+                    desc.optimized_clear_value.as_ref().map(|v| v as *const _),
+                    &mut resource,
+                )
+            }
+            .map_err(|e| resource_creation_error(&gpu.device, &desc.desc, e))?;
+
+            let memory = offsets[i]..offsets[i] + allocations[i].SizeInBytes;
+            let aliased = (0..descs.len()).any(|j| {
+                j != i
+                    && memory.start < offsets[j] + allocations[j].SizeInBytes
+                    && offsets[j] < memory.end
+            });
+            resources.push(TransientResource {
+                resource: resource.unwrap(),
+                first_pass: *desc.passes.start(),
+                aliased,
+            });
+        }
+
+        let unaliased_size = allocations
+            .iter()
+            .map(|allocation| allocation.SizeInBytes)
+            .sum::<u64>();
+        info!(
+            "BevyDirectX: Placed {} transient resources in {} MB, instead of {} MB",
+            descs.len(),
+            heap_size / 1_000_000,
+            unaliased_size / 1_000_000,
+        );
+
+        Ok(Self {
+            resources,
+            heap: Some(heap),
+            heap_size,
+        })
+    }
+
+    fn new_committed(gpu: &Gpu, descs: &[TransientResourceDesc]) -> Result<Self, DxError> {
+        let mut resources = Vec::with_capacity(descs.len());
+        let mut heap_size = 0;
+        for desc in descs {
+            let mut resource = None;
+            unsafe {
+                gpu.device
+                    .CreateCommittedResource(
+                        &D3D12_HEAP_PROPERTIES {
+                            Type: D3D12_HEAP_TYPE_DEFAULT,
+                            ..Default::default()
+                        },
+                        D3D12_HEAP_FLAG_NONE,
+                        &desc.desc,
+                        desc.initial_state,
+                        desc.optimized_clear_value.as_ref().map(|v| v as *const _),
+                        &mut resource,
+                    )
+                    .map_err(|e| resource_creation_error(&gpu.device, &desc.desc, e))?;
+                heap_size += gpu
+                    .device
+                    .GetResourceAllocationInfo(0, &[desc.desc])
+                    .SizeInBytes;
+            }
+            resources.push(TransientResource {
+                resource: resource.unwrap(),
+                first_pass: *desc.passes.start(),
+                aliased: false,
+            });
+        }
+
+        Ok(Self {
+            resources,
+            heap: None,
+            heap_size,
+        })
+    }
+
+    /// The resource created for `descs[index]` in [`TransientResourcePool::new`].
+    pub fn resource(&self, index: usize) -> &ID3D12Resource {
+        &self.resources[index].resource
+    }
+
+    /// Insert aliasing barriers for resources first used in `pass`, that share memory with other resources.
+    pub fn begin_pass(&self, command_list: &ID3D12GraphicsCommandList7, pass: u32) {
+        let barriers = self
+            .resources
+            .iter()
+            .filter(|resource| resource.aliased && resource.first_pass == pass)
+            .map(|resource| aliasing_barrier(None, Some(&resource.resource)))
+            .collect::<Vec<_>>();
+        if !barriers.is_empty() {
+            unsafe { command_list.ResourceBarrier(&barriers) };
+        }
+    }
+
+    /// Whether resources were placed in a shared heap, or fell back to separate committed resources.
+    pub fn is_aliasing(&self) -> bool {
+        self.heap.is_some()
+    }
+
+    /// Total GPU memory used by the pool's resources, in bytes.
+    pub fn memory_usage(&self) -> u64 {
+        self.heap_size
+    }
+}
+
+/// Heap offset for each resource, given the passes it's used in, and its size and alignment.
+///
+/// Greedily places the largest resources first, each at the lowest offset not overlapping in memory with any already
+/// placed resource that is in use at the same time.
+fn place_resources(
+    lifetimes: &[RangeInclusive<u32>],
+    allocations: &[D3D12_RESOURCE_ALLOCATION_INFO],
+) -> Vec<u64> {
+    let mut order = (0..lifetimes.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| Reverse(allocations[i].SizeInBytes));
+    let mut offsets = vec![0; lifetimes.len()];
+    let mut placed: Vec<usize> = Vec::with_capacity(lifetimes.len());
+    for i in order {
+        let lifetime = &lifetimes[i];
+        let mut conflicts = placed
+            .iter()
+            .filter(|&&j| {
+                let other = &lifetimes[j];
+                lifetime.start() <= other.end() && other.start() <= lifetime.end()
+            })
+            .map(|&j| offsets[j]..offsets[j] + allocations[j].SizeInBytes)
+            .collect::<Vec<_>>();
+        conflicts.sort_by_key(|range| range.start);
+
+        let (size, alignment) = (allocations[i].SizeInBytes, allocations[i].Alignment);
+        let mut offset = 0;
+        for range in conflicts {
+            if offset + size <= range.start {
+                break;
+            }
+            offset = offset.max(range.end.next_multiple_of(alignment));
+        }
+        offsets[i] = offset;
+        placed.push(i);
+    }
+    offsets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALIGNMENT: u64 = D3D12_DEFAULT_RESOURCE_PLACEMENT_ALIGNMENT as u64;
+
+    fn allocation(size: u64) -> D3D12_RESOURCE_ALLOCATION_INFO {
+        D3D12_RESOURCE_ALLOCATION_INFO {
+            SizeInBytes: size,
+            Alignment: ALIGNMENT,
+        }
+    }
+
+    #[test]
+    fn disjoint_lifetimes_share_memory() {
+        let offsets = place_resources(
+            &[0..=1, 2..=3, 4..=4],
+            &[
+                allocation(ALIGNMENT),
+                allocation(2 * ALIGNMENT),
+                allocation(ALIGNMENT),
+            ],
+        );
+        assert_eq!(offsets, [0, 0, 0]);
+    }
+
+    #[test]
+    fn overlapping_lifetimes_get_separate_aligned_memory() {
+        let allocations = [
+            allocation(ALIGNMENT / 2),
+            allocation(3 * ALIGNMENT),
+            allocation(ALIGNMENT),
+        ];
+        let offsets = place_resources(&[0..=3, 1..=2, 2..=3], &allocations);
+
+        // Largest first, then each after the ones in use at the same time, rounded up to its alignment
+        assert_eq!(offsets, [4 * ALIGNMENT, 0, 3 * ALIGNMENT]);
+    }
+
+    #[test]
+    fn resources_fill_gaps_below_others() {
+        let allocations = [
+            allocation(4 * ALIGNMENT),
+            allocation(2 * ALIGNMENT),
+            allocation(ALIGNMENT),
+        ];
+        let offsets = place_resources(&[0..=0, 0..=1, 1..=1], &allocations);
+
+        // The second is pushed past the first, leaving the memory below it free for the third once the first is done
+        assert_eq!(offsets, [0, 4 * ALIGNMENT, 0]);
+    }
+}
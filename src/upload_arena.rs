@@ -0,0 +1,174 @@
+use crate::{error::DxError, gpu::Gpu};
+use bevy::prelude::{FromWorld, Resource, World};
+use std::{ptr, slice};
+use windows::Win32::Graphics::Direct3D12::*;
+
+/// Default size of each buffer in an [`UploadArena`], 4 MB.
+pub const DEFAULT_UPLOAD_PAGE_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Ring of persistently mapped UPLOAD heap buffers ("pages"), suballocated for staging data to copy to the GPU,
+/// avoiding creating a new buffer per upload.
+///
+/// Allocations are made linearly from the current page. When it's full, the arena moves on to the next page in
+/// the ring, reusing it if the GPU has finished all work submitted while it was last allocated from, as tracked by
+/// [`Gpu::completed_fence_value`]. Otherwise a new page is inserted into the ring. Since the GPU is waited on once
+/// per frame, pages are typically reclaimed the frame after they were used.
+///
+/// Not added by default; add it with `app.init_resource::<UploadArena>()` after [`crate::BevyDirectXPlugin`].
+#[derive(Resource)]
+pub struct UploadArena {
+    pages: Vec<UploadPage>,
+    current_page: usize,
+    page_size: u64,
+}
+
+struct UploadPage {
+    buffer: ID3D12Resource,
+    data: *mut u8,
+    size: u64,
+    offset: u64,
+    /// Fence value after which the GPU is done reading from this page.
+    fence_value: u64,
+}
+
+// Safety: The mapped pointers are only accessed through &mut UploadArena
+unsafe impl Send for UploadArena {}
+unsafe impl Sync for UploadArena {}
+
+/// A region of an [`UploadArena`] page, valid for use by commands recorded this frame.
+pub struct UploadAllocation<'a> {
+    /// The UPLOAD heap buffer containing the region, to use as a copy source.
+    pub buffer: &'a ID3D12Resource,
+    /// Offset of the region in bytes from the start of `buffer`.
+    pub offset: u64,
+    /// CPU-writable view of the region. The memory is write-combined, so avoid reading from it.
+    pub data: &'a mut [u8],
+}
+
+impl UploadArena {
+    /// Create an arena with one page of `page_size` bytes. Additional pages of the same size are created as needed.
+    pub fn new(gpu: &Gpu, page_size: u64) -> Result<Self, DxError> {
+        Ok(Self {
+            pages: vec![UploadPage::new(gpu, page_size)?],
+            current_page: 0,
+            page_size,
+        })
+    }
+
+    /// Allocate `size` bytes of staging memory, aligned to `alignment` bytes (a power of two).
+    ///
+    /// Allocations larger than the page size get a dedicated page.
+    pub fn allocate(
+        &mut self,
+        gpu: &Gpu,
+        size: u64,
+        alignment: u64,
+    ) -> Result<UploadAllocation<'_>, DxError> {
+        assert!(
+            alignment.is_power_of_two(),
+            "BevyDirectX: UploadArena::allocate() alignment must be a power of two, was {alignment}"
+        );
+
+        let fence_value = gpu.next_fence_value();
+        let completed_fence_value = gpu.completed_fence_value();
+
+        if self.pages[self.current_page]
+            .try_allocate(size, alignment)
+            .is_none()
+        {
+            // Move to the next page in the ring if the GPU is done with it, else insert a new page
+            let next_page = (self.current_page + 1) % self.pages.len();
+            let page = &mut self.pages[next_page];
+            if page.fence_value <= completed_fence_value && page.size >= size {
+                page.offset = 0;
+                self.current_page = next_page;
+            } else {
+                let page = UploadPage::new(gpu, size.max(self.page_size))?;
+                self.current_page += 1;
+                self.pages.insert(self.current_page, page);
+            }
+        }
+
+        let page = &mut self.pages[self.current_page];
+        let offset = page.try_allocate(size, alignment).unwrap();
+        page.offset = offset + size;
+        page.fence_value = fence_value;
+
+        Ok(UploadAllocation {
+            buffer: &page.buffer,
+            offset,
+            data: unsafe {
+                slice::from_raw_parts_mut(page.data.add(offset as usize), size as usize)
+            },
+        })
+    }
+
+    /// Copy `data` into staging memory, and record a copy from it into `destination` at `destination_offset`.
+    ///
+    /// `destination` must be a buffer in the COPY_DEST state, or in the COMMON state to be implicitly promoted.
+    pub fn upload(
+        &mut self,
+        gpu: &Gpu,
+        command_list: &ID3D12GraphicsCommandList7,
+        destination: &ID3D12Resource,
+        destination_offset: u64,
+        data: &[u8],
+    ) -> Result<(), DxError> {
+        let allocation = self.allocate(gpu, data.len() as u64, 4)?;
+        allocation.data.copy_from_slice(data);
+
+        unsafe {
+            command_list.CopyBufferRegion(
+                destination,
+                destination_offset,
+                allocation.buffer,
+                allocation.offset,
+                data.len() as u64,
+            );
+        }
+        Ok(())
+    }
+
+    /// Total size in bytes of all pages in the ring.
+    pub fn capacity(&self) -> u64 {
+        self.pages.iter().map(|page| page.size).sum()
+    }
+}
+
+impl FromWorld for UploadArena {
+    fn from_world(world: &mut World) -> Self {
+        Self::new(world.resource::<Gpu>(), DEFAULT_UPLOAD_PAGE_SIZE)
+            .expect("BevyDirectX: Failed to create upload arena")
+    }
+}
+
+impl UploadPage {
+    fn new(gpu: &Gpu, size: u64) -> Result<Self, DxError> {
+        let buffer = gpu.create_buffer(
+            size,
+            D3D12_HEAP_TYPE_UPLOAD,
+            D3D12_RESOURCE_FLAG_NONE,
+            D3D12_RESOURCE_STATE_GENERIC_READ,
+        )?;
+
+        // Upload heaps can stay mapped for their whole lifetime
+        let mut data = ptr::null_mut();
+        unsafe {
+            buffer.Map(0, Some(&D3D12_RANGE { Begin: 0, End: 0 }), Some(&mut data))?;
+        }
+
+        Ok(Self {
+            buffer,
+            data: data as *mut u8,
+            size,
+            offset: 0,
+            fence_value: 0,
+        })
+    }
+
+    /// Offset a new allocation would start at, if it fits in the rest of the page.
+    fn try_allocate(&self, size: u64, alignment: u64) -> Option<u64> {
+        let offset = self.offset.next_multiple_of(alignment);
+        (offset + size <= self.size).then_some(offset)
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{error::DxError, gpu::Gpu};
+use bevy::math::{UVec2, Vec2};
+use windows::Win32::Graphics::{
+    Direct3D12::*,
+    Dxgi::Common::{
+        DXGI_FORMAT_R16G16B16A16_FLOAT, DXGI_FORMAT_R16G16_FLOAT, DXGI_FORMAT_R32_TYPELESS,
+    },
+};
+
+/// Integration point for temporal upscalers such as FSR or DLSS.
+///
+/// Implementations record their upscaling work into the command list, reading the low resolution
+/// [`UpscalerInputs`] and writing the full resolution `output` texture.
+///
+/// Required inputs:
+/// * `color`: Scene color at render resolution, rendered with `jitter` applied to the projection matrix.
+/// * `depth`: Device depth at render resolution.
+/// * `motion_vectors`: Per-pixel screen-space motion at render resolution, in pixels, pointing from the current
+///   frame's position to the previous frame's position. Must not include jitter.
+/// * `jitter`: The sub-pixel jitter offset used this frame, in pixels (see [`crate::FrameCount::jitter`]).
+///
+/// All inputs are in the `D3D12_RESOURCE_STATE_NON_PIXEL_SHADER_RESOURCE` state, and `output` is in the
+/// `D3D12_RESOURCE_STATE_UNORDERED_ACCESS` state, both before and after upscaling.
+pub trait Upscaler: Send + Sync + 'static {
+    fn upscale(
+        &self,
+        command_list: &ID3D12GraphicsCommandList7,
+        inputs: &UpscalerInputs,
+        output: &ID3D12Resource,
+    );
+}
+
+/// Low resolution inputs to an [`Upscaler`].
+pub struct UpscalerInputs<'a> {
+    pub color: &'a ID3D12Resource,
+    pub depth: &'a ID3D12Resource,
+    pub motion_vectors: &'a ID3D12Resource,
+    pub jitter: Vec2,
+    pub render_size: UVec2,
+    pub output_size: UVec2,
+    /// Set when the previous frame's history is invalid, e.g. after a camera cut or resize.
+    pub reset: bool,
+}
+
+/// Textures needed for temporal upscaling: render resolution color, depth, and motion vectors, and a full
+/// resolution output.
+///
+/// Textures are created in the states expected by [`Upscaler::upscale`]. Recreate when either size changes.
+/// Depth is `R32_TYPELESS`, so that it can be viewed as `D32_FLOAT` for rendering and `R32_FLOAT` for sampling.
+pub struct UpscalerTargets {
+    pub color: ID3D12Resource,
+    pub depth: ID3D12Resource,
+    pub motion_vectors: ID3D12Resource,
+    pub output: ID3D12Resource,
+    render_size: UVec2,
+    output_size: UVec2,
+}
+
+impl UpscalerTargets {
+    pub fn new(gpu: &Gpu, render_size: UVec2, output_size: UVec2) -> Result<Self, DxError> {
+        let color = gpu.create_texture_2d(
+            render_size.x,
+            render_size.y,
+            DXGI_FORMAT_R16G16B16A16_FLOAT,
+            D3D12_RESOURCE_FLAG_ALLOW_RENDER_TARGET,
+            D3D12_RESOURCE_STATE_NON_PIXEL_SHADER_RESOURCE,
+            None,
+        )?;
+        let depth = gpu.create_texture_2d(
+            render_size.x,
+            render_size.y,
+            DXGI_FORMAT_R32_TYPELESS,
+            D3D12_RESOURCE_FLAG_ALLOW_DEPTH_STENCIL,
+            D3D12_RESOURCE_STATE_NON_PIXEL_SHADER_RESOURCE,
+            None,
+        )?;
+        let motion_vectors = gpu.create_texture_2d(
+            render_size.x,
+            render_size.y,
+            DXGI_FORMAT_R16G16_FLOAT,
+            D3D12_RESOURCE_FLAG_ALLOW_RENDER_TARGET,
+            D3D12_RESOURCE_STATE_NON_PIXEL_SHADER_RESOURCE,
+            None,
+        )?;
+        let output = gpu.create_texture_2d(
+            output_size.x,
+            output_size.y,
+            DXGI_FORMAT_R16G16B16A16_FLOAT,
+            D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS,
+            D3D12_RESOURCE_STATE_UNORDERED_ACCESS,
+            None,
+        )?;
+
+        Ok(Self {
+            color,
+            depth,
+            motion_vectors,
+            output,
+            render_size,
+            output_size,
+        })
+    }
+
+    pub fn render_size(&self) -> UVec2 {
+        self.render_size
+    }
+
+    pub fn output_size(&self) -> UVec2 {
+        self.output_size
+    }
+
+    /// Run an upscaler over these targets.
+    pub fn upscale(
+        &self,
+        upscaler: &dyn Upscaler,
+        command_list: &ID3D12GraphicsCommandList7,
+        jitter: Vec2,
+        reset: bool,
+    ) {
+        let inputs = UpscalerInputs {
+            color: &self.color,
+            depth: &self.depth,
+            motion_vectors: &self.motion_vectors,
+            jitter,
+            render_size: self.render_size,
+            output_size: self.output_size,
+            reset,
+        };
+        upscaler.upscale(command_list, &inputs, &self.output);
+    }
+}
@@ -0,0 +1,283 @@
+use crate::{
+    error::DxError,
+    gpu::Gpu,
+    offscreen::{full_scissor_rect, full_viewport},
+    resource_tracker::transition_barrier,
+};
+use bevy::math::UVec2;
+use windows::Win32::{
+    Foundation::RECT,
+    Graphics::{Direct3D12::*, Dxgi::Common::*},
+};
+
+/// Targets for visibility buffer rendering, where geometry is rasterized once to a buffer of instance/triangle IDs,
+/// and materials are evaluated afterwards in a compute pass, only once per pixel.
+///
+/// Pass flow:
+/// 1. [`VisibilityBuffer::begin_raster_pass`], then draw all geometry with a pipeline using
+///    [`VisibilityBuffer::ID_FORMAT`] and [`VisibilityBuffer::DSV_FORMAT`], writing packed IDs (see
+///    `packVisibility()` in `assets/visibility_buffer.hlsl`).
+/// 2. [`VisibilityBuffer::begin_material_pass`], then bind [`VisibilityBuffer::descriptor_table`] to a compute root
+///    signature built with [`VisibilityBuffer::DESCRIPTOR_RANGES`], and dispatch [`VisibilityBuffer::dispatch_size`]
+///    groups. The shader reads IDs from `t0` and depth from `t1`, fetches the triangle's vertices and material, and
+///    writes shaded color to `u0`.
+/// 3. [`VisibilityBuffer::end_material_pass`], after which [`VisibilityBuffer::output`] can be sampled, e.g. for
+///    post-processing or blitting to the window.
+///
+/// Between frames, the ID and depth textures rest in the NON_PIXEL_SHADER_RESOURCE state, and the output texture in
+/// the PIXEL_SHADER_RESOURCE state.
+pub struct VisibilityBuffer {
+    ids: ID3D12Resource,
+    depth: ID3D12Resource,
+    output: ID3D12Resource,
+    rtv_heap: ID3D12DescriptorHeap,
+    dsv_heap: ID3D12DescriptorHeap,
+    descriptor_heap: ID3D12DescriptorHeap,
+    size: UVec2,
+}
+
+impl VisibilityBuffer {
+    /// Format of the ID texture, to use for `RTVFormats[0]` in the raster pass pipeline.
+    pub const ID_FORMAT: DXGI_FORMAT = DXGI_FORMAT_R32_UINT;
+    /// Format to use for `DSVFormat` in the raster pass pipeline.
+    pub const DSV_FORMAT: DXGI_FORMAT = DXGI_FORMAT_D32_FLOAT;
+    /// Value of pixels no geometry was rasterized to.
+    pub const EMPTY_ID: u32 = 0;
+    /// Descriptor ranges of [`VisibilityBuffer::descriptor_table`]: the ID and depth textures as SRVs `t0` and `t1`,
+    /// and the output texture as UAV `u0`.
+    pub const DESCRIPTOR_RANGES: [D3D12_DESCRIPTOR_RANGE1; 2] = [
+        D3D12_DESCRIPTOR_RANGE1 {
+            RangeType: D3D12_DESCRIPTOR_RANGE_TYPE_SRV,
+            NumDescriptors: 2,
+            BaseShaderRegister: 0,
+            RegisterSpace: 0,
+            Flags: D3D12_DESCRIPTOR_RANGE_FLAG_NONE,
+            OffsetInDescriptorsFromTableStart: 0,
+        },
+        D3D12_DESCRIPTOR_RANGE1 {
+            RangeType: D3D12_DESCRIPTOR_RANGE_TYPE_UAV,
+            NumDescriptors: 1,
+            BaseShaderRegister: 0,
+            RegisterSpace: 0,
+            Flags: D3D12_DESCRIPTOR_RANGE_FLAG_NONE,
+            OffsetInDescriptorsFromTableStart: 2,
+        },
+    ];
+
+    /// Create targets of `size`, with the material pass writing to a texture of `output_format`, which must support
+    /// typed UAV stores.
+    pub fn new(gpu: &Gpu, size: UVec2, output_format: DXGI_FORMAT) -> Result<Self, DxError> {
+        let ids = gpu.create_texture_2d(
+            size.x,
+            size.y,
+            Self::ID_FORMAT,
+            D3D12_RESOURCE_FLAG_ALLOW_RENDER_TARGET,
+            D3D12_RESOURCE_STATE_NON_PIXEL_SHADER_RESOURCE,
+            Some(&D3D12_CLEAR_VALUE {
+                Format: Self::ID_FORMAT,
+                Anonymous: D3D12_CLEAR_VALUE_0 {
+                    Color: [Self::EMPTY_ID as f32; 4],
+                },
+            }),
+        )?;
+        let depth = gpu.create_texture_2d(
+            size.x,
+            size.y,
+            DXGI_FORMAT_R32_TYPELESS,
+            D3D12_RESOURCE_FLAG_ALLOW_DEPTH_STENCIL,
+            D3D12_RESOURCE_STATE_NON_PIXEL_SHADER_RESOURCE,
+            Some(&D3D12_CLEAR_VALUE {
+                Format: Self::DSV_FORMAT,
+                Anonymous: D3D12_CLEAR_VALUE_0 {
+                    DepthStencil: D3D12_DEPTH_STENCIL_VALUE {
+                        Depth: 1.0,
+                        Stencil: 0,
+                    },
+                },
+            }),
+        )?;
+        let output = gpu.create_texture_2d(
+            size.x,
+            size.y,
+            output_format,
+            D3D12_RESOURCE_FLAG_ALLOW_UNORDERED_ACCESS,
+            D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE,
+            None,
+        )?;
+
+        let rtv_heap = gpu.create_descriptor_heap(D3D12_DESCRIPTOR_HEAP_TYPE_RTV, 1, false)?;
+        let dsv_heap = gpu.create_descriptor_heap(D3D12_DESCRIPTOR_HEAP_TYPE_DSV, 1, false)?;
+        let descriptor_heap =
+            gpu.create_descriptor_heap(D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV, 3, true)?;
+        unsafe {
+            let descriptor_size = gpu
+                .device
+                .GetDescriptorHandleIncrementSize(D3D12_DESCRIPTOR_HEAP_TYPE_CBV_SRV_UAV)
+                as usize;
+            let descriptor = |i: usize| D3D12_CPU_DESCRIPTOR_HANDLE {
+                ptr: descriptor_heap.GetCPUDescriptorHandleForHeapStart().ptr + i * descriptor_size,
+            };
+
+            gpu.device.CreateRenderTargetView(
+                &ids,
+                None,
+                rtv_heap.GetCPUDescriptorHandleForHeapStart(),
+            );
+            gpu.device.CreateDepthStencilView(
+                &depth,
+                Some(&D3D12_DEPTH_STENCIL_VIEW_DESC {
+                    Format: Self::DSV_FORMAT,
+                    ViewDimension: D3D12_DSV_DIMENSION_TEXTURE2D,
+                    Flags: D3D12_DSV_FLAG_NONE,
+                    Anonymous: D3D12_DEPTH_STENCIL_VIEW_DESC_0 {
+                        Texture2D: D3D12_TEX2D_DSV { MipSlice: 0 },
+                    },
+                }),
+                dsv_heap.GetCPUDescriptorHandleForHeapStart(),
+            );
+
+            gpu.device
+                .CreateShaderResourceView(&ids, None, descriptor(0));
+            gpu.device.CreateShaderResourceView(
+                &depth,
+                Some(&D3D12_SHADER_RESOURCE_VIEW_DESC {
+                    Format: DXGI_FORMAT_R32_FLOAT,
+                    ViewDimension: D3D12_SRV_DIMENSION_TEXTURE2D,
+                    Shader4ComponentMapping: D3D12_DEFAULT_SHADER_4_COMPONENT_MAPPING,
+                    Anonymous: D3D12_SHADER_RESOURCE_VIEW_DESC_0 {
+                        Texture2D: D3D12_TEX2D_SRV {
+                            MipLevels: 1,
+                            ..Default::default()
+                        },
+                    },
+                }),
+                descriptor(1),
+            );
+            gpu.device
+                .CreateUnorderedAccessView(&output, None, None, descriptor(2));
+        }
+
+        Ok(Self {
+            ids,
+            depth,
+            output,
+            rtv_heap,
+            dsv_heap,
+            descriptor_heap,
+            size,
+        })
+    }
+
+    /// Texture of packed instance/triangle IDs, [`VisibilityBuffer::EMPTY_ID`] where nothing was rasterized.
+    pub fn ids(&self) -> &ID3D12Resource {
+        &self.ids
+    }
+
+    pub fn depth(&self) -> &ID3D12Resource {
+        &self.depth
+    }
+
+    /// Texture written by the material pass.
+    pub fn output(&self) -> &ID3D12Resource {
+        &self.output
+    }
+
+    pub fn rtv(&self) -> D3D12_CPU_DESCRIPTOR_HANDLE {
+        unsafe { self.rtv_heap.GetCPUDescriptorHandleForHeapStart() }
+    }
+
+    pub fn dsv(&self) -> D3D12_CPU_DESCRIPTOR_HANDLE {
+        unsafe { self.dsv_heap.GetCPUDescriptorHandleForHeapStart() }
+    }
+
+    /// Shader-visible descriptor heap containing only [`VisibilityBuffer::descriptor_table`].
+    pub fn descriptor_heap(&self) -> &ID3D12DescriptorHeap {
+        &self.descriptor_heap
+    }
+
+    /// Descriptor table for the material pass, laid out as in [`VisibilityBuffer::DESCRIPTOR_RANGES`].
+    pub fn descriptor_table(&self) -> D3D12_GPU_DESCRIPTOR_HANDLE {
+        unsafe { self.descriptor_heap.GetGPUDescriptorHandleForHeapStart() }
+    }
+
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// Viewport covering the whole buffer.
+    pub fn viewport(&self) -> D3D12_VIEWPORT {
+        full_viewport(self.size)
+    }
+
+    /// Scissor rect covering the whole buffer.
+    pub fn scissor_rect(&self) -> RECT {
+        full_scissor_rect(self.size)
+    }
+
+    /// Number of thread groups to dispatch in the material pass to cover every pixel, for a compute shader with
+    /// `[numthreads(group_size.x, group_size.y, 1)]`.
+    pub fn dispatch_size(&self, group_size: UVec2) -> UVec2 {
+        UVec2::new(
+            self.size.x.div_ceil(group_size.x),
+            self.size.y.div_ceil(group_size.y),
+        )
+    }
+
+    /// Transition the ID and depth textures for rasterization, bind them, and clear them.
+    pub fn begin_raster_pass(&self, command_list: &ID3D12GraphicsCommandList7) {
+        let (rtv, dsv) = (self.rtv(), self.dsv());
+        unsafe {
+            command_list.ResourceBarrier(&[
+                transition_barrier(
+                    &self.ids,
+                    D3D12_RESOURCE_STATE_NON_PIXEL_SHADER_RESOURCE,
+                    D3D12_RESOURCE_STATE_RENDER_TARGET,
+                ),
+                transition_barrier(
+                    &self.depth,
+                    D3D12_RESOURCE_STATE_NON_PIXEL_SHADER_RESOURCE,
+                    D3D12_RESOURCE_STATE_DEPTH_WRITE,
+                ),
+            ]);
+            command_list.OMSetRenderTargets(1, Some(&rtv), false, Some(&dsv));
+            command_list.ClearRenderTargetView(rtv, &[Self::EMPTY_ID as f32; 4], None);
+            command_list.ClearDepthStencilView(dsv, D3D12_CLEAR_FLAG_DEPTH, 1.0, 0, &[]);
+        }
+    }
+
+    /// Transition the ID and depth textures for reading, and the output texture for writing, in the material pass.
+    /// Binds [`VisibilityBuffer::descriptor_heap`].
+    pub fn begin_material_pass(&self, command_list: &ID3D12GraphicsCommandList7) {
+        unsafe {
+            command_list.ResourceBarrier(&[
+                transition_barrier(
+                    &self.ids,
+                    D3D12_RESOURCE_STATE_RENDER_TARGET,
+                    D3D12_RESOURCE_STATE_NON_PIXEL_SHADER_RESOURCE,
+                ),
+                transition_barrier(
+                    &self.depth,
+                    D3D12_RESOURCE_STATE_DEPTH_WRITE,
+                    D3D12_RESOURCE_STATE_NON_PIXEL_SHADER_RESOURCE,
+                ),
+                transition_barrier(
+                    &self.output,
+                    D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE,
+                    D3D12_RESOURCE_STATE_UNORDERED_ACCESS,
+                ),
+            ]);
+            command_list.SetDescriptorHeaps(&[Some(self.descriptor_heap.clone())]);
+        }
+    }
+
+    /// Transition the output texture back to PIXEL_SHADER_RESOURCE, so that it can be sampled.
+    pub fn end_material_pass(&self, command_list: &ID3D12GraphicsCommandList7) {
+        unsafe {
+            command_list.ResourceBarrier(&[transition_barrier(
+                &self.output,
+                D3D12_RESOURCE_STATE_UNORDERED_ACCESS,
+                D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE,
+            )]);
+        }
+    }
+}